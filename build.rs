@@ -1,13 +1,18 @@
 // SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
 // Build scripts regenerate protobuf bindings so API updates stay in sync across languages.
 
+use std::env;
+use std::path::PathBuf;
+
 fn main() {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by cargo"));
     let proto_files = ["proto/synagraph.proto"];
     let proto_includes = ["proto"];
 
     tonic_build::configure()
         .build_server(true)
         .build_client(true)
+        .file_descriptor_set_path(out_dir.join("synagraph_descriptor.bin"))
         .compile(&proto_files, &proto_includes)
         .expect("failed to compile protobuf definitions");
 }
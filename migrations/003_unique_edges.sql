@@ -0,0 +1,16 @@
+-- One edge per (tenant, src, dst, rel) so links are idempotent and edge ids stay stable.
+BEGIN;
+
+-- Keep the earliest edge of any duplicate group created before the constraint existed.
+DELETE FROM knowledge_edges e
+USING knowledge_edges older
+WHERE e.tenant_id = older.tenant_id
+  AND e.src = older.src
+  AND e.dst = older.dst
+  AND e.rel = older.rel
+  AND (e.created_at, e.id) > (older.created_at, older.id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_edges_unique_rel
+  ON knowledge_edges(tenant_id, src, dst, rel);
+
+COMMIT;
@@ -0,0 +1,25 @@
+-- Validity intervals on edges: an edge holds from valid_from until valid_to (open when NULL).
+-- Expired edges are kept so traversals can be replayed as of any earlier time.
+BEGIN;
+
+ALTER TABLE knowledge_edges
+  ADD COLUMN IF NOT EXISTS valid_from TIMESTAMPTZ,
+  ADD COLUMN IF NOT EXISTS valid_to TIMESTAMPTZ;
+
+UPDATE knowledge_edges SET valid_from = created_at WHERE valid_from IS NULL;
+
+ALTER TABLE knowledge_edges
+  ALTER COLUMN valid_from SET DEFAULT now(),
+  ALTER COLUMN valid_from SET NOT NULL;
+
+ALTER TABLE knowledge_edges
+  ADD CONSTRAINT edges_valid_interval CHECK (valid_to IS NULL OR valid_to >= valid_from);
+
+-- Only open edges are unique per relation, so linking again after an edge expired starts a new
+-- edge instead of rewriting history.
+DROP INDEX IF EXISTS idx_edges_unique_rel;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_edges_unique_open_rel
+  ON knowledge_edges(tenant_id, src, dst, rel)
+  WHERE valid_to IS NULL;
+
+COMMIT;
@@ -0,0 +1,24 @@
+-- Cluster labels written by the community detection job, one row per node that has edges.
+-- Each run replaces the tenant's rows, so the table always reflects the latest run.
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS node_communities (
+  node_id UUID PRIMARY KEY REFERENCES knowledge_nodes(id) ON DELETE CASCADE,
+  tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+  component UUID NOT NULL,
+  community UUID NOT NULL,
+  algorithm TEXT NOT NULL,
+  computed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_communities_tenant_component ON node_communities(tenant_id, component);
+CREATE INDEX IF NOT EXISTS idx_communities_tenant_community ON node_communities(tenant_id, community);
+
+ALTER TABLE node_communities ENABLE ROW LEVEL SECURITY;
+ALTER TABLE node_communities FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY communities_tenant_isolation ON node_communities
+USING (tenant_id = app_current_tenant())
+WITH CHECK (tenant_id = app_current_tenant());
+
+COMMIT;
@@ -0,0 +1,20 @@
+-- Approximate nearest-neighbour index for similarity search over node vectors.
+-- The index serves cosine distance (`<=>`), the default search metric; L2 and inner-product
+-- searches still return exact results through a scan. HNSW needs pgvector 0.5+, so older
+-- installs fall back to IVFFlat.
+BEGIN;
+
+DO $$
+BEGIN
+  IF EXISTS (SELECT 1 FROM pg_am WHERE amname = 'hnsw') THEN
+    EXECUTE 'CREATE INDEX IF NOT EXISTS idx_nodes_vector_cosine ON knowledge_nodes '
+            'USING hnsw (vector vector_cosine_ops) WITH (m = 16, ef_construction = 64)';
+  ELSIF EXISTS (SELECT 1 FROM pg_am WHERE amname = 'ivfflat') THEN
+    EXECUTE 'CREATE INDEX IF NOT EXISTS idx_nodes_vector_cosine ON knowledge_nodes '
+            'USING ivfflat (vector vector_cosine_ops) WITH (lists = 100)';
+  ELSE
+    RAISE NOTICE 'pgvector provides no ANN access method; similarity search will scan';
+  END IF;
+END $$;
+
+COMMIT;
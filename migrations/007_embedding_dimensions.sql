@@ -0,0 +1,14 @@
+-- Embeddings from different models have different sizes, so node_embeddings.vec drops its fixed
+-- dimension and `dim` records each row's size. Searches compare vectors of one model and dim.
+-- ANN indexes need a fixed dimension; add a partial expression index per model, e.g.
+--   CREATE INDEX ON node_embeddings USING hnsw ((vec::vector(768)) vector_cosine_ops)
+--   WHERE model = 'text-embedding-small';
+-- once a model's embeddings outgrow exact search.
+BEGIN;
+
+ALTER TABLE node_embeddings ALTER COLUMN vec TYPE VECTOR;
+
+ALTER TABLE node_embeddings
+  ADD CONSTRAINT node_embeddings_dim_matches CHECK (dim > 0 AND vector_dims(vec) = dim);
+
+COMMIT;
@@ -0,0 +1,11 @@
+-- Full-text search over the string values of node payloads, used by hybrid search alongside the
+-- vector index. The column is generated, so existing rows are indexed when it is added.
+BEGIN;
+
+ALTER TABLE knowledge_nodes
+  ADD COLUMN IF NOT EXISTS search_tsv TSVECTOR
+  GENERATED ALWAYS AS (jsonb_to_tsvector('english', payload_json, '["string"]')) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_nodes_search_tsv ON knowledge_nodes USING GIN (search_tsv);
+
+COMMIT;
@@ -0,0 +1,9 @@
+-- QueryNodesByKind pages by (created_at, id) within a tenant and kind; this index serves both the
+-- keyset predicate and the ordering, and supersedes the plain (tenant_id, kind) index.
+BEGIN;
+
+CREATE INDEX IF NOT EXISTS idx_nodes_tenant_kind_created
+  ON knowledge_nodes(tenant_id, kind, created_at, id);
+DROP INDEX IF EXISTS idx_nodes_tenant_kind;
+
+COMMIT;
@@ -0,0 +1,31 @@
+-- Vector indexes for every search metric. IVFFlat built its lists when the table was created,
+-- usually empty, and searched one list per query, so the fallback from 006 is dropped rather than
+-- kept with a fixed list count; pgvector releases without HNSW rank every search exactly.
+-- L2 and inner-product searches get HNSW indexes of their own instead of scanning.
+BEGIN;
+
+DO $$
+BEGIN
+  IF EXISTS (
+    SELECT 1
+    FROM pg_class c
+    JOIN pg_am am ON am.oid = c.relam
+    WHERE c.relname = 'idx_nodes_vector_cosine'
+      AND am.amname = 'ivfflat'
+  ) THEN
+    EXECUTE 'DROP INDEX idx_nodes_vector_cosine';
+  END IF;
+
+  IF EXISTS (SELECT 1 FROM pg_am WHERE amname = 'hnsw') THEN
+    EXECUTE 'CREATE INDEX IF NOT EXISTS idx_nodes_vector_cosine ON knowledge_nodes '
+            'USING hnsw (vector vector_cosine_ops) WITH (m = 16, ef_construction = 64)';
+    EXECUTE 'CREATE INDEX IF NOT EXISTS idx_nodes_vector_l2 ON knowledge_nodes '
+            'USING hnsw (vector vector_l2_ops) WITH (m = 16, ef_construction = 64)';
+    EXECUTE 'CREATE INDEX IF NOT EXISTS idx_nodes_vector_ip ON knowledge_nodes '
+            'USING hnsw (vector vector_ip_ops) WITH (m = 16, ef_construction = 64)';
+  ELSE
+    RAISE NOTICE 'pgvector provides no HNSW access method; similarity search will scan';
+  END IF;
+END $$;
+
+COMMIT;
@@ -8,6 +8,8 @@ use std::net::SocketAddr;
 use anyhow::{Context, Result};
 use uuid::Uuid;
 
+use crate::repository::DistanceMetric;
+
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     pub http_addr: SocketAddr,
@@ -20,6 +22,11 @@ pub struct AppConfig {
     pub scedge_event_bus_enabled: bool,
     pub scedge_event_bus_subject: String,
     pub tenant_slugs: HashMap<String, Uuid>,
+    /// Whether gRPC calls without the tenant header run against `default_tenant_id` instead of
+    /// being rejected.
+    pub grpc_default_tenant_fallback: bool,
+    /// Default metric for similarity searches when a request does not name one.
+    pub vector_distance_metric: DistanceMetric,
 }
 
 impl AppConfig {
@@ -49,6 +56,13 @@ impl AppConfig {
         let scedge_event_bus_subject =
             env::var("SCEDGE_EVENT_BUS_SUBJECT").unwrap_or_else(|_| "scedge:events".to_string());
         let tenant_slugs = parse_slug_map(env::var("TENANT_SLUGS").ok());
+        let grpc_default_tenant_fallback = env::var("GRPC_DEFAULT_TENANT_FALLBACK")
+            .map(|v| matches!(v.as_str(), "1" | "true" | "TRUE"))
+            .unwrap_or(false);
+        let vector_distance_metric = match env::var("VECTOR_DISTANCE_METRIC") {
+            Ok(value) => value.parse().context("invalid VECTOR_DISTANCE_METRIC")?,
+            Err(_) => DistanceMetric::default(),
+        };
 
         Ok(Self {
             http_addr,
@@ -61,6 +75,8 @@ impl AppConfig {
             scedge_event_bus_enabled,
             scedge_event_bus_subject,
             tenant_slugs,
+            grpc_default_tenant_fallback,
+            vector_distance_metric,
         })
     }
 }
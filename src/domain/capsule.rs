@@ -2,11 +2,14 @@ use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use thiserror::Error;
 use uuid::Uuid;
 
+use crate::config::AppConfig;
 use crate::domain::node::KnowledgeNode;
+use crate::repository::{EventBusHandle, RepositoryBundle, UpsertOutcome};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct CapsuleProvenance {
     pub source: String,
     #[serde(default)]
@@ -17,18 +20,7 @@ pub struct CapsuleProvenance {
     pub generated_at: Option<DateTime<Utc>>,
 }
 
-impl Default for CapsuleProvenance {
-    fn default() -> Self {
-        Self {
-            source: String::new(),
-            hash: String::new(),
-            version: None,
-            generated_at: None,
-        }
-    }
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct CapsulePolicy {
     pub tenant: String,
     #[serde(default)]
@@ -41,18 +33,6 @@ pub struct CapsulePolicy {
     pub compliance_tags: Vec<String>,
 }
 
-impl Default for CapsulePolicy {
-    fn default() -> Self {
-        Self {
-            tenant: String::new(),
-            phi: false,
-            pii: false,
-            region: None,
-            compliance_tags: Vec::new(),
-        }
-    }
-}
-
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CapsuleArtifact {
     #[serde(default)]
@@ -169,3 +149,163 @@ impl CapsuleIngestRequest {
         Ok(node)
     }
 }
+
+#[derive(Debug, Error)]
+pub enum CapsuleError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+/// Destination for capsule lifecycle GraphEvents; absent when the Scedge event bus is disabled.
+#[derive(Clone)]
+pub struct CapsuleEvents {
+    bus: EventBusHandle,
+    subject: String,
+}
+
+impl CapsuleEvents {
+    pub fn from_config(cfg: &AppConfig, bus: EventBusHandle) -> Option<Self> {
+        cfg.scedge_event_bus_enabled.then(|| Self {
+            bus,
+            subject: cfg.scedge_event_bus_subject.clone(),
+        })
+    }
+
+    async fn publish(&self, payload: Value) {
+        if let Err(err) = self.bus.publish(&self.subject, &payload).await {
+            tracing::error!(error = %err, "failed to publish scedge graph event");
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CapsuleStoreReceipt {
+    pub outcome: UpsertOutcome,
+    pub key: String,
+    pub hash: String,
+    pub tenant: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CapsulePurgeReceipt {
+    pub purged: u32,
+    pub revoked_hashes: Vec<String>,
+}
+
+/// Persists a capsule, emitting `SUPERSEDED_BY` when it replaces an existing capsule under the
+/// same key and `UPSERT_NODE` otherwise. `tenant_slug`, when the caller named one, must match
+/// `artifact.policy.tenant`.
+pub async fn store_capsule(
+    repos: &RepositoryBundle,
+    events: Option<&CapsuleEvents>,
+    tenant_id: Uuid,
+    tenant_slug: Option<&str>,
+    capsule: CapsuleIngestRequest,
+) -> Result<CapsuleStoreReceipt, CapsuleError> {
+    if let Some(expected) = tenant_slug {
+        if capsule.artifact.policy.tenant != expected {
+            return Err(CapsuleError::Invalid("policy.tenant mismatch".into()));
+        }
+    }
+    if capsule.artifact.policy.tenant.is_empty() {
+        return Err(CapsuleError::Invalid(
+            "artifact.policy.tenant is required".into(),
+        ));
+    }
+    if capsule.artifact.hash.is_empty() {
+        return Err(CapsuleError::Invalid("artifact.hash is required".into()));
+    }
+
+    let existing = repos.nodes.get_by_key(tenant_id, &capsule.key).await?;
+    let existing_capsule = existing
+        .as_ref()
+        .and_then(|node| CapsuleLookupResponse::from_node(node).ok());
+
+    let receipt_key = capsule.key.clone();
+    let receipt_hash = capsule.artifact.hash.clone();
+    let receipt_tenant = capsule.artifact.policy.tenant.clone();
+    let node = capsule.into_node(tenant_id)?;
+    let outcome = repos.nodes.upsert(tenant_id, node).await?;
+
+    if let Some(events) = events {
+        let event = match (&outcome, existing_capsule) {
+            (UpsertOutcome::Updated, Some(old_capsule)) => json!({
+                "type": "SUPERSEDED_BY",
+                "tenant": receipt_tenant,
+                "old_hash": old_capsule.artifact.hash,
+                "new_hash": receipt_hash,
+            }),
+            _ => json!({
+                "type": "UPSERT_NODE",
+                "tenant": receipt_tenant,
+                "key": receipt_key,
+                "hash": receipt_hash,
+            }),
+        };
+        events.publish(event).await;
+    }
+
+    Ok(CapsuleStoreReceipt {
+        outcome,
+        key: receipt_key,
+        hash: receipt_hash,
+        tenant: receipt_tenant,
+    })
+}
+
+/// Resolves a capsule by key, treating a capsule owned by a different tenant slug as a miss.
+pub async fn lookup_capsule(
+    repos: &RepositoryBundle,
+    tenant_id: Uuid,
+    tenant_slug: Option<&str>,
+    key: &str,
+) -> Result<Option<CapsuleLookupResponse>> {
+    let Some(node) = repos.nodes.get_by_key(tenant_id, key).await? else {
+        return Ok(None);
+    };
+
+    let capsule = CapsuleLookupResponse::from_node(&node)?;
+    if let Some(expected) = tenant_slug {
+        if capsule.artifact.policy.tenant != expected {
+            return Ok(None);
+        }
+    }
+    Ok(Some(capsule))
+}
+
+/// Deletes capsules by key, emitting `REVOKE_CAPSULE` for each removed capsule.
+pub async fn purge_capsules(
+    repos: &RepositoryBundle,
+    events: Option<&CapsuleEvents>,
+    tenant_id: Uuid,
+    keys: &[String],
+) -> Result<CapsulePurgeReceipt> {
+    let mut receipt = CapsulePurgeReceipt::default();
+
+    for key in keys.iter().filter(|key| !key.is_empty()) {
+        let Some(node) = repos.nodes.delete_by_key(tenant_id, key).await? else {
+            continue;
+        };
+        receipt.purged += 1;
+
+        let Some(events) = events else {
+            continue;
+        };
+        if let Ok(capsule) = CapsuleLookupResponse::from_node(&node) {
+            let hash = capsule.artifact.hash.clone();
+            receipt.revoked_hashes.push(hash.clone());
+            events
+                .publish(json!({
+                    "type": "REVOKE_CAPSULE",
+                    "tenant": capsule.artifact.policy.tenant,
+                    "capsule_id": capsule.key,
+                    "hash": hash,
+                }))
+                .await;
+        }
+    }
+
+    Ok(receipt)
+}
@@ -0,0 +1,446 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// Community detection: weakly connected components plus Louvain or label-propagation clusters.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::repository::{KnowledgeEdge, NodeCommunity, RepositoryBundle};
+
+/// Edges loaded per run; larger graphs are clustered over this prefix and flagged as truncated.
+pub const MAX_COMMUNITY_EDGES: usize = 100_000;
+pub const DEFAULT_COMMUNITY_ITERATIONS: usize = 20;
+pub const MAX_COMMUNITY_ITERATIONS: usize = 100;
+/// Gains smaller than this are treated as ties, which keeps runs deterministic.
+const EPSILON: f64 = 1e-12;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommunityAlgorithm {
+    /// Greedy modularity optimisation, aggregating communities into nodes level by level.
+    #[default]
+    Louvain,
+    /// Nodes repeatedly adopt the label carrying the most edge weight among their neighbours.
+    LabelPropagation,
+}
+
+impl CommunityAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Louvain => "louvain",
+            Self::LabelPropagation => "label_propagation",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CommunitySpec {
+    pub algorithm: CommunityAlgorithm,
+    /// Only cluster over edges with this relation.
+    pub rel: Option<String>,
+    /// Passes over the nodes, per level for Louvain.
+    pub max_iterations: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct CommunityReport {
+    pub algorithm: CommunityAlgorithm,
+    /// Nodes that received labels: every endpoint of a current edge.
+    pub nodes: usize,
+    pub components: usize,
+    pub communities: usize,
+    /// Newman modularity of the community split.
+    pub modularity: f64,
+    /// Set when the tenant has more edges than `MAX_COMMUNITY_EDGES`.
+    pub truncated: bool,
+}
+
+/// Clusters the tenant's current edges and replaces the stored labels with the result. Edge
+/// direction is ignored; components use every edge, while communities weigh edges by `weight`
+/// and ignore non-positive ones. Nodes without edges are left unlabelled.
+pub async fn detect_communities(
+    repos: &RepositoryBundle,
+    tenant: Uuid,
+    spec: &CommunitySpec,
+) -> Result<CommunityReport> {
+    let mut edges = repos
+        .edges
+        .list_edges(tenant, spec.rel.as_deref(), None, MAX_COMMUNITY_EDGES + 1)
+        .await?;
+    let truncated = edges.len() > MAX_COMMUNITY_EDGES;
+    edges.truncate(MAX_COMMUNITY_EDGES);
+
+    let clustering = cluster(&edges, spec.algorithm, spec.max_iterations);
+    repos
+        .nodes
+        .replace_communities(tenant, spec.algorithm.as_str(), &clustering.labels)
+        .await?;
+
+    let distinct = |label: fn(&NodeCommunity) -> Uuid| {
+        clustering
+            .labels
+            .iter()
+            .map(label)
+            .collect::<BTreeSet<_>>()
+            .len()
+    };
+    Ok(CommunityReport {
+        algorithm: spec.algorithm,
+        nodes: clustering.labels.len(),
+        components: distinct(|c| c.component),
+        communities: distinct(|c| c.community),
+        modularity: clustering.modularity,
+        truncated,
+    })
+}
+
+struct Clustering {
+    labels: Vec<NodeCommunity>,
+    modularity: f64,
+}
+
+/// Undirected weighted adjacency lists. A self-loop on `i` is stored once with weight `A_ii`,
+/// twice the loop's edge weight, so that degrees are plain row sums.
+struct WeightedGraph {
+    adj: Vec<Vec<(usize, f64)>>,
+}
+
+impl WeightedGraph {
+    fn from_rows(rows: Vec<BTreeMap<usize, f64>>) -> Self {
+        Self {
+            adj: rows
+                .into_iter()
+                .map(|row| row.into_iter().collect())
+                .collect(),
+        }
+    }
+
+    fn degrees(&self) -> Vec<f64> {
+        self.adj
+            .iter()
+            .map(|row| row.iter().map(|(_, weight)| weight).sum())
+            .collect()
+    }
+
+    /// Merges each community into one node, keeping internal weight as a self-loop.
+    fn aggregate(&self, community: &[usize], count: usize) -> Self {
+        let mut rows = vec![BTreeMap::new(); count];
+        for (i, row) in self.adj.iter().enumerate() {
+            for (j, weight) in row {
+                *rows[community[i]].entry(community[*j]).or_default() += weight;
+            }
+        }
+        Self::from_rows(rows)
+    }
+
+    fn modularity(&self, community: &[usize]) -> f64 {
+        let degrees = self.degrees();
+        let total: f64 = degrees.iter().sum();
+        if total == 0.0 {
+            return 0.0;
+        }
+        let mut inside: HashMap<usize, f64> = HashMap::new();
+        let mut degree: HashMap<usize, f64> = HashMap::new();
+        for (i, row) in self.adj.iter().enumerate() {
+            *degree.entry(community[i]).or_default() += degrees[i];
+            for (j, weight) in row {
+                if community[*j] == community[i] {
+                    *inside.entry(community[i]).or_default() += weight;
+                }
+            }
+        }
+        degree
+            .iter()
+            .map(|(c, d)| inside.get(c).copied().unwrap_or(0.0) / total - (d / total).powi(2))
+            .sum()
+    }
+}
+
+fn cluster(
+    edges: &[KnowledgeEdge],
+    algorithm: CommunityAlgorithm,
+    max_iterations: usize,
+) -> Clustering {
+    let ids: Vec<Uuid> = edges
+        .iter()
+        .flat_map(|edge| [edge.src, edge.dst])
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let index: HashMap<Uuid, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+    let mut parent: Vec<usize> = (0..ids.len()).collect();
+    let mut rows = vec![BTreeMap::new(); ids.len()];
+    for edge in edges {
+        let (u, v) = (index[&edge.src], index[&edge.dst]);
+        union(&mut parent, u, v);
+        if edge.weight > 0.0 {
+            let weight = f64::from(edge.weight);
+            *rows[u].entry(v).or_default() += weight;
+            *rows[v].entry(u).or_default() += weight;
+        }
+    }
+    let graph = WeightedGraph::from_rows(rows);
+
+    let components: Vec<usize> = (0..ids.len()).map(|i| find(&mut parent, i)).collect();
+    let communities = match algorithm {
+        CommunityAlgorithm::Louvain => louvain(&graph, max_iterations),
+        CommunityAlgorithm::LabelPropagation => label_propagation(&graph, max_iterations),
+    };
+
+    let component_labels = smallest_member(&components);
+    let community_labels = smallest_member(&communities);
+    let labels = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| NodeCommunity {
+            node_id: *id,
+            component: ids[component_labels[&components[i]]],
+            community: ids[community_labels[&communities[i]]],
+        })
+        .collect();
+    Clustering {
+        labels,
+        modularity: graph.modularity(&communities),
+    }
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    let mut node = i;
+    while parent[node] != root {
+        let next = parent[node];
+        parent[node] = root;
+        node = next;
+    }
+    root
+}
+
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parent, a), find(parent, b));
+    parent[a.max(b)] = a.min(b);
+}
+
+/// Maps each group to its lowest node index. Ids are sorted, so that is also its smallest id.
+fn smallest_member(groups: &[usize]) -> HashMap<usize, usize> {
+    let mut smallest = HashMap::new();
+    for (i, group) in groups.iter().enumerate() {
+        smallest.entry(*group).or_insert(i);
+    }
+    smallest
+}
+
+/// Returns a community per node. Each level moves nodes to the neighbouring community with the
+/// best modularity gain until no move helps, then aggregates communities into nodes for the next
+/// level; it stops once a level moves nothing.
+fn louvain(graph: &WeightedGraph, max_iterations: usize) -> Vec<usize> {
+    let mut membership: Vec<usize> = (0..graph.adj.len()).collect();
+    let mut level = None;
+    loop {
+        let current = level.as_ref().unwrap_or(graph);
+        let Some(community) = local_moving(current, max_iterations) else {
+            break;
+        };
+        let (community, count) = renumber(&community);
+        for member in membership.iter_mut() {
+            *member = community[*member];
+        }
+        level = Some(current.aggregate(&community, count));
+    }
+    membership
+}
+
+/// One Louvain level; `None` when no node moved.
+fn local_moving(graph: &WeightedGraph, max_iterations: usize) -> Option<Vec<usize>> {
+    let degrees = graph.degrees();
+    let total: f64 = degrees.iter().sum();
+    let mut community: Vec<usize> = (0..graph.adj.len()).collect();
+    if total == 0.0 {
+        return None;
+    }
+    let mut community_degree = degrees.clone();
+    let mut moved_any = false;
+    for _ in 0..max_iterations {
+        let mut moved = false;
+        for (i, row) in graph.adj.iter().enumerate() {
+            let mut links: BTreeMap<usize, f64> = BTreeMap::new();
+            for (j, weight) in row.iter().filter(|(j, _)| *j != i) {
+                *links.entry(community[*j]).or_default() += weight;
+            }
+            let current = community[i];
+            community_degree[current] -= degrees[i];
+            let gain = |c: usize| {
+                links.get(&c).copied().unwrap_or(0.0) - community_degree[c] * degrees[i] / total
+            };
+            let mut best = (current, gain(current));
+            for candidate in links.keys() {
+                let candidate_gain = gain(*candidate);
+                if candidate_gain > best.1 + EPSILON {
+                    best = (*candidate, candidate_gain);
+                }
+            }
+            community_degree[best.0] += degrees[i];
+            if best.0 != current {
+                community[i] = best.0;
+                moved = true;
+            }
+        }
+        if !moved {
+            break;
+        }
+        moved_any = true;
+    }
+    moved_any.then_some(community)
+}
+
+/// Relabels communities as `0..count` in order of first appearance.
+fn renumber(community: &[usize]) -> (Vec<usize>, usize) {
+    let mut dense = HashMap::new();
+    let relabelled = community
+        .iter()
+        .map(|c| {
+            let next = dense.len();
+            *dense.entry(*c).or_insert(next)
+        })
+        .collect();
+    (relabelled, dense.len())
+}
+
+/// Each pass visits nodes in id order; a node keeps its label when it is among the heaviest
+/// around it and otherwise takes the smallest heaviest one.
+fn label_propagation(graph: &WeightedGraph, max_iterations: usize) -> Vec<usize> {
+    let mut labels: Vec<usize> = (0..graph.adj.len()).collect();
+    for _ in 0..max_iterations {
+        let mut changed = false;
+        for (i, row) in graph.adj.iter().enumerate() {
+            let mut scores: BTreeMap<usize, f64> = BTreeMap::new();
+            for (j, weight) in row.iter().filter(|(j, _)| *j != i) {
+                *scores.entry(labels[*j]).or_default() += weight;
+            }
+            let Some(top) = scores.values().copied().reduce(f64::max) else {
+                continue;
+            };
+            if scores.get(&labels[i]).is_some_and(|s| *s >= top - EPSILON) {
+                continue;
+            }
+            if let Some((label, _)) = scores.iter().find(|(_, s)| **s >= top - EPSILON) {
+                labels[i] = *label;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::{cluster, detect_communities, CommunityAlgorithm, CommunitySpec};
+    use crate::domain::node::KnowledgeNode;
+    use crate::graph::query::run_query;
+    use crate::repository::pattern::PatternValue;
+    use crate::repository::{KnowledgeEdge, RepositoryBundle};
+
+    /// Two triangles joined by a weak bridge, plus a separate pair.
+    fn sample() -> (Vec<Uuid>, Vec<KnowledgeEdge>) {
+        let mut ids: Vec<Uuid> = (0..8).map(|_| Uuid::new_v4()).collect();
+        ids.sort();
+        let [a, b, c, d, e, f, g, h] = [0, 1, 2, 3, 4, 5, 6, 7].map(|i| ids[i]);
+        let edges = vec![
+            KnowledgeEdge::test(a, b, 1.0),
+            KnowledgeEdge::test(b, c, 1.0),
+            KnowledgeEdge::test(c, a, 1.0),
+            KnowledgeEdge::test(d, e, 1.0),
+            KnowledgeEdge::test(e, f, 1.0),
+            KnowledgeEdge::test(f, d, 1.0),
+            KnowledgeEdge::test(c, d, 0.1),
+            KnowledgeEdge::test(g, h, 1.0),
+        ];
+        (ids, edges)
+    }
+
+    #[test]
+    fn both_algorithms_split_the_bridged_triangles() {
+        let (ids, edges) = sample();
+        for algorithm in [
+            CommunityAlgorithm::Louvain,
+            CommunityAlgorithm::LabelPropagation,
+        ] {
+            let clustering = cluster(&edges, algorithm, 20);
+            let label = |id: Uuid| {
+                clustering
+                    .labels
+                    .iter()
+                    .find(|c| c.node_id == id)
+                    .cloned()
+                    .unwrap()
+            };
+            for id in &ids[..6] {
+                assert_eq!(label(*id).component, ids[0], "{algorithm:?}");
+            }
+            assert_eq!(label(ids[7]).component, ids[6]);
+            for id in &ids[..3] {
+                assert_eq!(label(*id).community, ids[0], "{algorithm:?}");
+            }
+            for id in &ids[3..6] {
+                assert_eq!(label(*id).community, ids[3], "{algorithm:?}");
+            }
+            assert_eq!(label(ids[7]).community, ids[6]);
+            assert!(clustering.modularity > 0.5, "{algorithm:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn detected_communities_can_be_queried() {
+        let repos = RepositoryBundle::in_memory();
+        let tenant = Uuid::new_v4();
+        let (ids, edges) = sample();
+        for id in &ids {
+            let mut node = KnowledgeNode::new(tenant, "note", json!({}));
+            node.id = *id;
+            repos.nodes.upsert(tenant, node).await.unwrap();
+        }
+        for edge in &edges {
+            repos
+                .edges
+                .link(tenant, edge.src, edge.dst, &edge.rel, edge.weight, None)
+                .await
+                .unwrap();
+        }
+
+        let spec = CommunitySpec {
+            algorithm: CommunityAlgorithm::Louvain,
+            rel: None,
+            max_iterations: 20,
+        };
+        let report = detect_communities(&repos, tenant, &spec).await.unwrap();
+        assert_eq!(
+            (report.nodes, report.components, report.communities),
+            (8, 2, 3)
+        );
+        assert!(!report.truncated);
+
+        let query = format!("MATCH (n) WHERE n.community = '{}' RETURN n", ids[3]);
+        let members = run_query(&repos, tenant, &query, None).await.unwrap();
+        let mut found: Vec<Uuid> = members
+            .rows
+            .iter()
+            .map(|row| match &row[0] {
+                PatternValue::Node(node) => node.id,
+                _ => unreachable!(),
+            })
+            .collect();
+        found.sort();
+        assert_eq!(found, ids[3..6].to_vec());
+    }
+}
@@ -0,0 +1,182 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// Hybrid retrieval: full-text and vector rankings merged with reciprocal rank fusion.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::domain::node::KnowledgeNode;
+use crate::repository::{DistanceMetric, RepositoryBundle, SimilarityFilter};
+
+pub const DEFAULT_TOP_K: usize = 10;
+pub const MAX_TOP_K: usize = 100;
+/// Damping constant of reciprocal rank fusion; 60 is the value from the original paper. Larger
+/// values flatten the gap between the top ranks and the rest.
+pub const RRF_K: f64 = 60.0;
+/// Each ranking contributes this many candidates per requested result, so nodes ranked modestly
+/// by both can still overtake nodes found by only one.
+const CANDIDATES_PER_RESULT: usize = 4;
+
+#[derive(Clone, Debug)]
+pub struct HybridSpec {
+    /// Words matched against payload text; `None` skips the lexical ranking.
+    pub text: Option<String>,
+    /// `None` skips the vector ranking.
+    pub vector: Option<Vec<f32>>,
+    pub metric: DistanceMetric,
+    /// Applied to both rankings before they are fused.
+    pub filter: SimilarityFilter,
+    pub top_k: usize,
+}
+
+/// Where a node placed in one of the fused rankings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RankedBy {
+    /// 1 for the best hit.
+    pub rank: usize,
+    /// The ranking's own score: text rank, or the similarity score under the search metric.
+    pub score: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct HybridHit {
+    pub node: KnowledgeNode,
+    /// Sum of `1 / (RRF_K + rank)` over the rankings that found the node.
+    pub score: f64,
+    pub text: Option<RankedBy>,
+    pub vector: Option<RankedBy>,
+}
+
+impl HybridHit {
+    /// Says which rankings found the node and where, e.g. `text #1 (0.100), vector #3 (cosine
+    /// 0.912)`.
+    pub fn reason(&self, metric: DistanceMetric) -> String {
+        let mut parts = Vec::new();
+        if let Some(text) = self.text {
+            parts.push(format!("text #{} ({:.3})", text.rank, text.score));
+        }
+        if let Some(vector) = self.vector {
+            parts.push(format!(
+                "vector #{} ({} {:.3})",
+                vector.rank,
+                metric.as_str(),
+                vector.score
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Runs the full-text and vector searches the spec asks for and fuses their rankings. Nodes found
+/// by both rank above nodes found by one at similar positions; ties go to the smaller id.
+pub async fn hybrid_search(
+    repos: &RepositoryBundle,
+    tenant: Uuid,
+    spec: &HybridSpec,
+) -> Result<Vec<HybridHit>> {
+    let candidates = spec.top_k * CANDIDATES_PER_RESULT;
+    let mut hits: HashMap<Uuid, HybridHit> = HashMap::new();
+
+    if let Some(text) = &spec.text {
+        let matches = repos
+            .nodes
+            .search_text(tenant, text, &spec.filter, candidates)
+            .await?;
+        for (index, found) in matches.into_iter().enumerate() {
+            let ranked = RankedBy {
+                rank: index + 1,
+                score: found.score,
+            };
+            fuse(&mut hits, found.node, ranked).text = Some(ranked);
+        }
+    }
+    if let Some(vector) = &spec.vector {
+        let matches = repos
+            .nodes
+            .search_similar(tenant, vector, spec.metric, &spec.filter, candidates)
+            .await?;
+        for (index, found) in matches.into_iter().enumerate() {
+            let ranked = RankedBy {
+                rank: index + 1,
+                score: found.score,
+            };
+            fuse(&mut hits, found.node, ranked).vector = Some(ranked);
+        }
+    }
+
+    let mut ranked: Vec<HybridHit> = hits.into_values().collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.node.id.cmp(&b.node.id)));
+    ranked.truncate(spec.top_k);
+    Ok(ranked)
+}
+
+/// Adds `ranked`'s share of the fused score to `node`'s hit and returns the hit.
+fn fuse(
+    hits: &mut HashMap<Uuid, HybridHit>,
+    node: KnowledgeNode,
+    ranked: RankedBy,
+) -> &mut HybridHit {
+    let hit = hits.entry(node.id).or_insert_with(|| HybridHit {
+        node,
+        score: 0.0,
+        text: None,
+        vector: None,
+    });
+    hit.score += 1.0 / (RRF_K + ranked.rank as f64);
+    hit
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::{hybrid_search, HybridSpec, RRF_K};
+    use crate::domain::node::KnowledgeNode;
+    use crate::repository::{DistanceMetric, RepositoryBundle, SimilarityFilter};
+
+    #[tokio::test]
+    async fn nodes_found_by_both_rankings_rank_first() {
+        let repos = RepositoryBundle::in_memory();
+        let tenant = Uuid::new_v4();
+        let node = |title: &str, vector: [f32; 2]| {
+            let mut node = KnowledgeNode::new(tenant, "note", json!({ "title": title }));
+            node.vector = Some(vector.to_vec());
+            node
+        };
+        // Best by text but without a vector, best by vector alone, and second in both.
+        let mut lexical = node("refund refund policy", [0.0, 1.0]);
+        lexical.vector = None;
+        let semantic = node("returns", [1.0, 0.0]);
+        let both = node("refund window", [0.9, 0.1]);
+        for node in [&lexical, &semantic, &both] {
+            repos.nodes.upsert(tenant, node.clone()).await.unwrap();
+        }
+
+        let mut spec = HybridSpec {
+            text: Some("refund".into()),
+            vector: Some(vec![1.0, 0.0]),
+            metric: DistanceMetric::Cosine,
+            filter: SimilarityFilter::default(),
+            top_k: 2,
+        };
+        let hits = hybrid_search(&repos, tenant, &spec).await.unwrap();
+        let ids: Vec<Uuid> = hits.iter().map(|hit| hit.node.id).collect();
+        assert_eq!(ids, vec![both.id, semantic.id.min(lexical.id)]);
+        assert_eq!(hits[0].score, 2.0 / (RRF_K + 2.0));
+        assert_eq!(hits[0].text.map(|text| text.rank), Some(2));
+        assert_eq!(hits[0].vector.map(|vector| vector.rank), Some(2));
+        assert!(hits[0]
+            .reason(spec.metric)
+            .starts_with("text #2 (1.000), vector #2 (cosine"));
+
+        // Either ranking works on its own, and the filter applies to both.
+        spec.vector = None;
+        spec.filter.payload = Some(json!({ "title": "refund window" }));
+        let hits = hybrid_search(&repos, tenant, &spec).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].node.id, both.id);
+        assert!(hits[0].vector.is_none());
+    }
+}
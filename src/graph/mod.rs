@@ -0,0 +1,8 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// Graph algorithms composed from the repository traits and shared by the HTTP and gRPC surfaces.
+
+pub mod activation;
+pub mod community;
+pub mod hybrid;
+pub mod query;
+pub mod subgraph;
@@ -0,0 +1,182 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// Declarative pattern queries: a Cypher-like MATCH/WHERE/RETURN subset over nodes and edges.
+//
+//   MATCH (c:capsule)-[:derived_from]->(s:source)
+//   WHERE s.payload.region = 'EU'
+//   RETURN c, s LIMIT 20
+//
+// A query matches one path of node patterns `(var:kind)` joined by relationships `-[var:rel]->`,
+// `<-[...]-` or `-[...]-` (either direction). `[:a|b]` accepts several relations and `[*1..3]`
+// a bounded variable-length path. WHERE takes `AND`ed comparisons on `id`, `kind` and
+// `payload.<key>...` for nodes, or `id`, `rel`, `weight` and `props.<key>...` for single-edge
+// relationships. RETURN lists variables; rows are distinct and ordered by the returned ids.
+// Only edges valid at the query's as-of time (now by default) take part in a match.
+
+mod parser;
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::repository::pattern::PatternRow;
+use crate::repository::RepositoryBundle;
+
+pub const DEFAULT_QUERY_LIMIT: usize = 100;
+pub const MAX_QUERY_LIMIT: usize = 1_000;
+
+#[derive(Debug, Error)]
+pub enum QueryError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+#[derive(Clone, Debug)]
+pub struct QueryResult {
+    /// Variable names of the returned columns.
+    pub columns: Vec<String>,
+    pub rows: Vec<PatternRow>,
+}
+
+/// Parses `text` and runs it against the tenant's graph as it stood at `as_of` (now when `None`).
+pub async fn run_query(
+    repos: &RepositoryBundle,
+    tenant: Uuid,
+    text: &str,
+    as_of: Option<DateTime<Utc>>,
+) -> Result<QueryResult, QueryError> {
+    let mut parsed =
+        parser::parse(text, DEFAULT_QUERY_LIMIT, MAX_QUERY_LIMIT).map_err(QueryError::Invalid)?;
+    parsed.plan.as_of = as_of;
+    let rows = repos.edges.match_pattern(tenant, &parsed.plan).await?;
+    Ok(QueryResult {
+        columns: parsed.columns,
+        rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use uuid::Uuid;
+
+    use super::{run_query, QueryError};
+    use crate::domain::node::KnowledgeNode;
+    use crate::repository::pattern::PatternValue;
+    use crate::repository::RepositoryBundle;
+
+    async fn add(repos: &RepositoryBundle, tenant: Uuid, kind: &str, payload: Value) -> Uuid {
+        let node = KnowledgeNode::new(tenant, kind, payload);
+        repos.nodes.upsert(tenant, node.clone()).await.unwrap();
+        node.id
+    }
+
+    fn titles(rows: &[Vec<PatternValue>], column: usize) -> Vec<String> {
+        let mut titles: Vec<String> = rows
+            .iter()
+            .map(|row| match &row[column] {
+                PatternValue::Node(node) => node.payload_json["title"].as_str().unwrap().into(),
+                PatternValue::Edge(edge) => edge.rel.clone(),
+            })
+            .collect();
+        titles.sort();
+        titles
+    }
+
+    #[tokio::test]
+    async fn matches_patterns_on_the_in_memory_store() {
+        let repos = RepositoryBundle::in_memory();
+        let tenant = Uuid::new_v4();
+        let eu = add(
+            &repos,
+            tenant,
+            "source",
+            json!({ "title": "eu", "region": "EU" }),
+        )
+        .await;
+        let us = add(
+            &repos,
+            tenant,
+            "source",
+            json!({ "title": "us", "region": "US" }),
+        )
+        .await;
+        let a = add(
+            &repos,
+            tenant,
+            "capsule",
+            json!({ "title": "a", "score": 3 }),
+        )
+        .await;
+        let b = add(
+            &repos,
+            tenant,
+            "capsule",
+            json!({ "title": "b", "score": 7 }),
+        )
+        .await;
+        let c = add(&repos, tenant, "capsule", json!({ "title": "c" })).await;
+        for (src, dst, rel) in [
+            (a, eu, "derived_from"),
+            (b, us, "derived_from"),
+            (c, a, "summarizes"),
+            (b, eu, "cites"),
+        ] {
+            repos
+                .edges
+                .link(tenant, src, dst, rel, 1.0, None)
+                .await
+                .unwrap();
+        }
+
+        let result = run_query(
+            &repos,
+            tenant,
+            "MATCH (c:capsule)-[:derived_from]->(s:source) \
+             WHERE s.payload.region = 'EU' RETURN c, s",
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.columns, vec!["c", "s"]);
+        assert_eq!(titles(&result.rows, 0), vec!["a"]);
+        assert_eq!(titles(&result.rows, 1), vec!["eu"]);
+
+        // Either relation, returning the edge; numeric comparison on the payload.
+        let result = run_query(
+            &repos,
+            tenant,
+            "MATCH (c:capsule)-[r:derived_from|cites]->(:source) \
+             WHERE c.payload.score > 5 RETURN c, r",
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(titles(&result.rows, 1), vec!["cites", "derived_from"]);
+
+        // Variable-length and reverse traversal reach sources through other capsules.
+        let result = run_query(
+            &repos,
+            tenant,
+            "MATCH (s:source)<-[*1..2]-(c:capsule) WHERE s.payload.region = 'EU' RETURN c",
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(titles(&result.rows, 0), vec!["a", "b", "c"]);
+
+        let limited = run_query(&repos, tenant, "MATCH (n) RETURN n LIMIT 2", None)
+            .await
+            .unwrap();
+        assert_eq!(limited.rows.len(), 2);
+
+        let other = run_query(&repos, Uuid::new_v4(), "MATCH (n) RETURN n", None)
+            .await
+            .unwrap();
+        assert!(other.rows.is_empty());
+
+        let invalid = run_query(&repos, tenant, "MATCH (n RETURN n", None).await;
+        assert!(matches!(invalid, Err(QueryError::Invalid(_))));
+    }
+}
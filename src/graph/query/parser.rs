@@ -0,0 +1,688 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// Parser for the MATCH/WHERE/RETURN pattern language; produces a validated `PatternPlan`.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::repository::pattern::{
+    CompareOp, Field, HopStep, NodeStep, PatternPlan, Predicate, Slot, MAX_PATTERN_HOPS,
+};
+use crate::repository::Direction;
+
+/// A parsed query: the plan plus the variable name of each returned column.
+#[derive(Debug)]
+pub(super) struct ParsedQuery {
+    pub plan: PatternPlan,
+    pub columns: Vec<String>,
+}
+
+pub(super) fn parse(
+    text: &str,
+    default_limit: usize,
+    max_limit: usize,
+) -> Result<ParsedQuery, String> {
+    let tokens = tokenize(text)?;
+    Parser {
+        tokens,
+        pos: 0,
+        end: text.len(),
+        plan: PatternPlan::default(),
+        vars: HashMap::new(),
+    }
+    .query(default_limit, max_limit)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    /// Backtick-quoted name, for kinds and relations that are not plain identifiers.
+    Quoted(String),
+    Str(String),
+    Number(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Colon,
+    Comma,
+    Dot,
+    DotDot,
+    Star,
+    Pipe,
+    Minus,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(name) => format!("'{name}'"),
+            Token::Quoted(name) => format!("`{name}`"),
+            Token::Str(value) => format!("string \"{value}\""),
+            Token::Number(value) => format!("number {value}"),
+            Token::LParen => "'('".into(),
+            Token::RParen => "')'".into(),
+            Token::LBracket => "'['".into(),
+            Token::RBracket => "']'".into(),
+            Token::Colon => "':'".into(),
+            Token::Comma => "','".into(),
+            Token::Dot => "'.'".into(),
+            Token::DotDot => "'..'".into(),
+            Token::Star => "'*'".into(),
+            Token::Pipe => "'|'".into(),
+            Token::Minus => "'-'".into(),
+            Token::Lt => "'<'".into(),
+            Token::Le => "'<='".into(),
+            Token::Gt => "'>'".into(),
+            Token::Ge => "'>='".into(),
+            Token::Eq => "'='".into(),
+            Token::Ne => "'<>'".into(),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, String> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (offset, c) = chars[i];
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ':' => Token::Colon,
+            ',' => Token::Comma,
+            '*' => Token::Star,
+            '|' => Token::Pipe,
+            '-' => Token::Minus,
+            '=' => Token::Eq,
+            '.' if next == Some('.') => {
+                i += 1;
+                Token::DotDot
+            }
+            '.' => Token::Dot,
+            '<' if next == Some('=') => {
+                i += 1;
+                Token::Le
+            }
+            '<' if next == Some('>') => {
+                i += 1;
+                Token::Ne
+            }
+            '<' => Token::Lt,
+            '>' if next == Some('=') => {
+                i += 1;
+                Token::Ge
+            }
+            '>' => Token::Gt,
+            '!' if next == Some('=') => {
+                i += 1;
+                Token::Ne
+            }
+            '\'' | '"' | '`' => {
+                let mut value = String::new();
+                let mut closed = false;
+                i += 1;
+                while i < chars.len() {
+                    let ch = chars[i].1;
+                    if ch == '\\' && c != '`' {
+                        match chars.get(i + 1) {
+                            Some((_, escaped)) => value.push(*escaped),
+                            None => break,
+                        }
+                        i += 2;
+                        continue;
+                    }
+                    if ch == c {
+                        closed = true;
+                        break;
+                    }
+                    value.push(ch);
+                    i += 1;
+                }
+                if !closed {
+                    return Err(format!("unterminated quote at offset {offset}"));
+                }
+                if c == '`' {
+                    Token::Quoted(value)
+                } else {
+                    Token::Str(value)
+                }
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while chars.get(i + 1).is_some_and(|(_, c)| c.is_ascii_digit()) {
+                    i += 1;
+                }
+                // A '.' continues the number only when a digit follows, so `1..3` stays a range.
+                if chars.get(i + 1).map(|(_, c)| *c) == Some('.')
+                    && chars.get(i + 2).is_some_and(|(_, c)| c.is_ascii_digit())
+                {
+                    i += 2;
+                    while chars.get(i + 1).is_some_and(|(_, c)| c.is_ascii_digit()) {
+                        i += 1;
+                    }
+                }
+                Token::Number(chars[start..=i].iter().map(|(_, c)| c).collect())
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while chars
+                    .get(i + 1)
+                    .is_some_and(|(_, c)| c.is_alphanumeric() || *c == '_')
+                {
+                    i += 1;
+                }
+                Token::Ident(chars[start..=i].iter().map(|(_, c)| c).collect())
+            }
+            other => return Err(format!("unexpected character '{other}' at offset {offset}")),
+        };
+        tokens.push((offset, token));
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+    plan: PatternPlan,
+    /// Variables bound in the pattern and the slot of their first occurrence.
+    vars: HashMap<String, Slot>,
+}
+
+impl Parser {
+    fn query(mut self, default_limit: usize, max_limit: usize) -> Result<ParsedQuery, String> {
+        self.keyword("MATCH")?;
+        self.path()?;
+
+        if self.eat_keyword("WHERE") {
+            loop {
+                self.condition()?;
+                if !self.eat_keyword("AND") {
+                    break;
+                }
+            }
+        }
+
+        self.keyword("RETURN")?;
+        let mut columns = Vec::new();
+        loop {
+            let (offset, name) = self.ident("a variable to return")?;
+            let slot = *self
+                .vars
+                .get(&name)
+                .ok_or_else(|| format!("unknown variable '{name}' at offset {offset}"))?;
+            self.plan.returns.push(slot);
+            columns.push(name);
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+
+        self.plan.limit = default_limit;
+        if self.eat_keyword("LIMIT") {
+            let (offset, limit) = self.integer("a row limit")?;
+            if limit == 0 || limit > max_limit {
+                return Err(format!(
+                    "LIMIT at offset {offset} must be between 1 and {max_limit}"
+                ));
+            }
+            self.plan.limit = limit;
+        }
+
+        if let Some((offset, token)) = self.tokens.get(self.pos) {
+            return Err(format!(
+                "unexpected {} at offset {offset}, expected end of query",
+                token.describe()
+            ));
+        }
+        Ok(ParsedQuery {
+            plan: self.plan,
+            columns,
+        })
+    }
+
+    fn path(&mut self) -> Result<(), String> {
+        self.node()?;
+        loop {
+            let direction = match self.peek() {
+                Some(Token::Lt) => {
+                    self.pos += 1;
+                    self.expect(&Token::Minus, "'-'")?;
+                    Direction::Incoming
+                }
+                Some(Token::Minus) => {
+                    self.pos += 1;
+                    Direction::Outgoing
+                }
+                _ => return Ok(()),
+            };
+            let (var, mut hop) = if self.peek() == Some(&Token::LBracket) {
+                self.relationship()?
+            } else {
+                (None, HopStep::single())
+            };
+            self.expect(&Token::Minus, "'-'")?;
+            hop.direction = match (direction, self.eat(&Token::Gt)) {
+                (Direction::Incoming, true) => {
+                    return Err("a relationship cannot point both ways".into())
+                }
+                (Direction::Incoming, false) => Direction::Incoming,
+                (_, true) => Direction::Outgoing,
+                (_, false) => Direction::Both,
+            };
+
+            let index = self.plan.hops.len();
+            if let Some((offset, name)) = var {
+                if !hop.is_single() {
+                    return Err(format!(
+                        "variable-length relationship '{name}' at offset {offset} cannot be bound to a variable"
+                    ));
+                }
+                self.bind(offset, name, Slot::Edge(index))?;
+            }
+            self.plan.hops.push(hop);
+            self.node()?;
+        }
+    }
+
+    fn node(&mut self) -> Result<(), String> {
+        self.expect(&Token::LParen, "'(' to start a node")?;
+        let index = self.plan.nodes.len();
+        let mut step = NodeStep::default();
+        if let Some(Token::Ident(_)) = self.peek() {
+            let (offset, name) = self.ident("a node variable")?;
+            match self.vars.get(&name) {
+                Some(Slot::Node(first)) => step.same_as = Some(*first),
+                Some(Slot::Edge(_)) => {
+                    return Err(format!(
+                        "'{name}' at offset {offset} is already bound to a relationship"
+                    ))
+                }
+                None => self.bind(offset, name, Slot::Node(index))?,
+            }
+        }
+        if self.eat(&Token::Colon) {
+            step.kind = Some(self.name("a node kind")?);
+        }
+        self.expect(&Token::RParen, "')' to close the node")?;
+        self.plan.nodes.push(step);
+        Ok(())
+    }
+
+    /// The bracketed part of a relationship; the caller sets the direction from the arrows.
+    fn relationship(&mut self) -> Result<(Option<(usize, String)>, HopStep), String> {
+        self.expect(&Token::LBracket, "'['")?;
+        let var = match self.peek() {
+            Some(Token::Ident(_)) => Some(self.ident("a relationship variable")?),
+            _ => None,
+        };
+        let mut rels = Vec::new();
+        if self.eat(&Token::Colon) {
+            rels.push(self.name("a relation")?);
+            while self.eat(&Token::Pipe) {
+                rels.push(self.name("a relation")?);
+            }
+        }
+        let (mut min, mut max) = (1, 1);
+        if self.eat(&Token::Star) {
+            max = MAX_PATTERN_HOPS as usize;
+            if let Some(Token::Number(_)) = self.peek() {
+                min = self.integer("a minimum length")?.1;
+                max = min;
+            }
+            if self.eat(&Token::DotDot) {
+                max = MAX_PATTERN_HOPS as usize;
+                if let Some(Token::Number(_)) = self.peek() {
+                    max = self.integer("a maximum length")?.1;
+                }
+            }
+            if min > max || max > MAX_PATTERN_HOPS as usize {
+                return Err(format!(
+                    "relationship length {min}..{max} must be ordered and at most {MAX_PATTERN_HOPS}"
+                ));
+            }
+        }
+        self.expect(&Token::RBracket, "']' to close the relationship")?;
+        let hop = HopStep {
+            rels,
+            min: min as u8,
+            max: max as u8,
+            ..HopStep::single()
+        };
+        Ok((var, hop))
+    }
+
+    fn condition(&mut self) -> Result<(), String> {
+        let (offset, name) = self.ident("a variable")?;
+        let slot = *self
+            .vars
+            .get(&name)
+            .ok_or_else(|| format!("unknown variable '{name}' at offset {offset}"))?;
+        self.expect(&Token::Dot, "'.' after the variable")?;
+        let (_, property) = self.ident("a property")?;
+        let mut path = Vec::new();
+        while self.eat(&Token::Dot) {
+            path.push(self.name("a property key")?);
+        }
+
+        let field = match (slot, property.as_str(), path.is_empty()) {
+            (_, "id", true) => Field::Id,
+            (Slot::Node(_), "kind", true) => Field::Kind,
+            (Slot::Node(_), "payload", false) => Field::Payload(path),
+            (Slot::Node(_), "component", true) => Field::Component,
+            (Slot::Node(_), "community", true) => Field::Community,
+            (Slot::Edge(_), "rel", true) => Field::Rel,
+            (Slot::Edge(_), "weight", true) => Field::Weight,
+            (Slot::Edge(_), "props", false) => Field::Props(path),
+            (Slot::Node(_), _, _) => {
+                return Err(format!(
+                    "unknown node property '{name}.{property}'; use id, kind, component, community or payload.<key>"
+                ))
+            }
+            (Slot::Edge(_), _, _) => {
+                return Err(format!(
+                    "unknown relationship property '{name}.{property}'; use id, rel, weight or props.<key>"
+                ))
+            }
+        };
+
+        let op = match self.advance() {
+            Some((_, Token::Eq)) => CompareOp::Eq,
+            Some((_, Token::Ne)) => CompareOp::Ne,
+            Some((_, Token::Lt)) => CompareOp::Lt,
+            Some((_, Token::Le)) => CompareOp::Le,
+            Some((_, Token::Gt)) => CompareOp::Gt,
+            Some((_, Token::Ge)) => CompareOp::Ge,
+            other => return Err(self.unexpected(other, "a comparison operator")),
+        };
+        let (offset, value) = self.literal()?;
+
+        let ordered = !matches!(op, CompareOp::Eq | CompareOp::Ne);
+        let value = match (&field, value) {
+            (Field::Id | Field::Component | Field::Community, Value::String(id)) if !ordered => {
+                match Uuid::parse_str(&id) {
+                    Ok(id) => Value::String(id.to_string()),
+                    Err(_) => return Err(format!("'{id}' at offset {offset} is not a valid id")),
+                }
+            }
+            (Field::Kind | Field::Rel, value @ Value::String(_)) if !ordered => value,
+            (Field::Id | Field::Component | Field::Community | Field::Kind | Field::Rel, _) => {
+                return Err(format!(
+                    "'{name}.{property}' only supports = and <> against a string"
+                ))
+            }
+            (_, value @ Value::Number(_)) => value,
+            (Field::Weight, _) => {
+                return Err(format!(
+                    "'{name}.weight' can only be compared with a number"
+                ))
+            }
+            (_, value) if !ordered => value,
+            _ => {
+                return Err(format!(
+                    "ordering comparisons need a number at offset {offset}"
+                ))
+            }
+        };
+
+        self.plan.predicates.push(Predicate {
+            slot,
+            field,
+            op,
+            value,
+        });
+        Ok(())
+    }
+
+    fn literal(&mut self) -> Result<(usize, Value), String> {
+        let negative = self.eat(&Token::Minus);
+        match self.advance() {
+            Some((offset, Token::Number(text))) => {
+                let text = if negative { format!("-{text}") } else { text };
+                let value: Value = serde_json::from_str(&text)
+                    .map_err(|_| format!("invalid number {text} at offset {offset}"))?;
+                Ok((offset, value))
+            }
+            Some((offset, Token::Str(value))) if !negative => Ok((offset, Value::String(value))),
+            Some((offset, Token::Ident(word))) if !negative => {
+                match word.to_ascii_lowercase().as_str() {
+                    "true" => Ok((offset, Value::Bool(true))),
+                    "false" => Ok((offset, Value::Bool(false))),
+                    "null" => Ok((offset, Value::Null)),
+                    _ => Err(format!(
+                        "unexpected '{word}' at offset {offset}, expected a literal"
+                    )),
+                }
+            }
+            other => Err(self.unexpected(other, "a literal")),
+        }
+    }
+
+    fn bind(&mut self, offset: usize, name: String, slot: Slot) -> Result<(), String> {
+        if self.vars.contains_key(&name) {
+            return Err(format!(
+                "variable '{name}' at offset {offset} is already bound"
+            ));
+        }
+        self.vars.insert(name, slot);
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn advance(&mut self) -> Option<(usize, Token)> {
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: &Token, what: &str) -> Result<(), String> {
+        match self.advance() {
+            Some((_, token)) if &token == expected => Ok(()),
+            other => Err(self.unexpected(other, what)),
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Ident(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            let next = self.advance();
+            Err(self.unexpected(next, keyword))
+        }
+    }
+
+    fn ident(&mut self, what: &str) -> Result<(usize, String), String> {
+        match self.advance() {
+            Some((offset, Token::Ident(name))) => Ok((offset, name)),
+            other => Err(self.unexpected(other, what)),
+        }
+    }
+
+    /// An identifier or a backtick-quoted name.
+    fn name(&mut self, what: &str) -> Result<String, String> {
+        match self.advance() {
+            Some((_, Token::Ident(name) | Token::Quoted(name))) => Ok(name),
+            other => Err(self.unexpected(other, what)),
+        }
+    }
+
+    fn integer(&mut self, what: &str) -> Result<(usize, usize), String> {
+        match self.advance() {
+            Some((offset, Token::Number(text))) => text
+                .parse()
+                .map(|n| (offset, n))
+                .map_err(|_| format!("expected {what} at offset {offset}, found {text}")),
+            other => Err(self.unexpected(other, what)),
+        }
+    }
+
+    fn unexpected(&self, found: Option<(usize, Token)>, expected: &str) -> String {
+        match found {
+            Some((offset, token)) => format!(
+                "unexpected {} at offset {offset}, expected {expected}",
+                token.describe()
+            ),
+            None => format!(
+                "unexpected end of query at offset {}, expected {expected}",
+                self.end
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse;
+    use crate::repository::pattern::{CompareOp, Field, HopStep, NodeStep, Predicate, Slot};
+    use crate::repository::Direction;
+
+    #[test]
+    fn parses_a_filtered_path_pattern() {
+        let parsed = parse(
+            "MATCH (c:capsule)-[d:derived_from|`cites-v2`]->(s:source)<-[*..2]-(c) \
+             WHERE s.payload.geo.region = 'EU' AND d.weight >= -1.5 AND c.kind <> \"x\" \
+             RETURN c, d, s LIMIT 5",
+            100,
+            1000,
+        )
+        .unwrap();
+        let plan = parsed.plan;
+
+        assert_eq!(parsed.columns, vec!["c", "d", "s"]);
+        assert_eq!(
+            plan.nodes,
+            vec![
+                NodeStep {
+                    kind: Some("capsule".into()),
+                    same_as: None
+                },
+                NodeStep {
+                    kind: Some("source".into()),
+                    same_as: None
+                },
+                NodeStep {
+                    kind: None,
+                    same_as: Some(0)
+                },
+            ]
+        );
+        assert_eq!(
+            plan.hops,
+            vec![
+                HopStep {
+                    rels: vec!["derived_from".into(), "cites-v2".into()],
+                    direction: Direction::Outgoing,
+                    min: 1,
+                    max: 1,
+                },
+                HopStep {
+                    rels: Vec::new(),
+                    direction: Direction::Incoming,
+                    min: 1,
+                    max: 2,
+                },
+            ]
+        );
+        assert_eq!(
+            plan.predicates[0],
+            Predicate {
+                slot: Slot::Node(1),
+                field: Field::Payload(vec!["geo".into(), "region".into()]),
+                op: CompareOp::Eq,
+                value: json!("EU"),
+            }
+        );
+        assert_eq!(plan.predicates[1].value, json!(-1.5));
+        assert_eq!(plan.predicates[2].op, CompareOp::Ne);
+        assert_eq!(
+            plan.returns,
+            vec![Slot::Node(0), Slot::Edge(0), Slot::Node(1)]
+        );
+        assert_eq!(plan.limit, 5);
+    }
+
+    #[test]
+    fn undirected_and_bare_relationships() {
+        let plan = parse("match (a)--(b)-[*2]-(c) return c", 10, 10)
+            .unwrap()
+            .plan;
+        assert_eq!(plan.hops[0].direction, Direction::Both);
+        assert!(plan.hops[0].is_single());
+        assert_eq!((plan.hops[1].min, plan.hops[1].max), (2, 2));
+        assert_eq!(plan.limit, 10);
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        let cases = [
+            ("MATCH (a) RETURN b", "unknown variable 'b'"),
+            ("MATCH (a)-[r*1..3]->(b) RETURN r", "cannot be bound"),
+            ("MATCH (a)-[*1..9]->(b) RETURN b", "at most 4"),
+            (
+                "MATCH (a) WHERE a.region = 'EU' RETURN a",
+                "use id, kind, component, community or payload",
+            ),
+            (
+                "MATCH (a) WHERE a.community = 'x' RETURN a",
+                "not a valid id",
+            ),
+            (
+                "MATCH (a) WHERE a.payload.n < 'x' RETURN a",
+                "need a number",
+            ),
+            ("MATCH (a) WHERE a.id = 'nope' RETURN a", "not a valid id"),
+            ("MATCH (a)<-[]->(b) RETURN a", "both ways"),
+            ("MATCH (a) RETURN a LIMIT 5000", "between 1 and 1000"),
+            ("MATCH (a RETURN a", "expected ')'"),
+            ("MATCH (a) RETURN a extra", "expected end of query"),
+            ("MATCH (a) WHERE a.payload.t = 'open", "unterminated quote"),
+        ];
+        for (query, message) in cases {
+            let err = parse(query, 100, 1000).unwrap_err();
+            assert!(err.contains(message), "{query}: {err}");
+        }
+    }
+}
@@ -0,0 +1,237 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// Ego-network export: the induced subgraph around a set of seed nodes.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::domain::node::KnowledgeNode;
+use crate::repository::{KnowledgeEdge, RepositoryBundle, TraversalFilter};
+
+/// Deepest expansion a caller may request.
+pub const MAX_SUBGRAPH_HOPS: u8 = 4;
+pub const DEFAULT_MAX_NODES: usize = 200;
+pub const MAX_NODES_LIMIT: usize = 2_000;
+pub const DEFAULT_MAX_EDGES: usize = 1_000;
+pub const MAX_EDGES_LIMIT: usize = 10_000;
+
+#[derive(Clone, Debug)]
+pub struct SubgraphSpec {
+    pub seeds: Vec<Uuid>,
+    pub hops: u8,
+    /// Relation and direction followed while expanding; the relation also filters the returned
+    /// edges.
+    pub filter: TraversalFilter,
+    /// Node kinds admitted while expanding. Empty admits every kind; seeds are always kept.
+    pub kinds: Vec<String>,
+    pub max_nodes: usize,
+    pub max_edges: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Subgraph {
+    pub nodes: Vec<KnowledgeNode>,
+    pub edges: Vec<KnowledgeEdge>,
+    /// Set when a cap cut the export short, so the payload is not the complete neighbourhood.
+    pub truncated: bool,
+}
+
+/// Collects the nodes within `spec.hops` of the seeds, expanding one hop at a time so nodes of
+/// excluded kinds are neither returned nor walked through, then returns every edge among the
+/// collected nodes. Unknown seeds are skipped.
+pub async fn extract_subgraph(
+    repos: &RepositoryBundle,
+    tenant: Uuid,
+    spec: &SubgraphSpec,
+) -> Result<Subgraph> {
+    let mut seen = HashSet::new();
+    let seeds: Vec<Uuid> = spec
+        .seeds
+        .iter()
+        .copied()
+        .filter(|id| seen.insert(*id))
+        .collect();
+    seen.clear();
+
+    let mut subgraph = Subgraph::default();
+    for node in repos.nodes.get_many(tenant, &seeds).await? {
+        if subgraph.nodes.len() == spec.max_nodes {
+            subgraph.truncated = true;
+            break;
+        }
+        seen.insert(node.id);
+        subgraph.nodes.push(node);
+    }
+
+    let mut frontier: Vec<Uuid> = subgraph.nodes.iter().map(|node| node.id).collect();
+    'expand: for _ in 0..spec.hops {
+        let mut next = Vec::new();
+        for id in frontier {
+            // One row per edge, so a full page may hide further neighbours.
+            let limit = spec.max_nodes + 1;
+            let reached = repos
+                .edges
+                .neighbors(tenant, id, &spec.filter, 1, limit)
+                .await?;
+            if reached.len() == limit {
+                subgraph.truncated = true;
+            }
+            for neighbor in reached {
+                let node = neighbor.node;
+                if seen.contains(&node.id) || !admits(&spec.kinds, &node.kind) {
+                    continue;
+                }
+                if subgraph.nodes.len() == spec.max_nodes {
+                    subgraph.truncated = true;
+                    break 'expand;
+                }
+                seen.insert(node.id);
+                next.push(node.id);
+                subgraph.nodes.push(node);
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+
+    let ids: Vec<Uuid> = subgraph.nodes.iter().map(|node| node.id).collect();
+    let mut edges = repos
+        .edges
+        .edges_among(
+            tenant,
+            &ids,
+            spec.filter.rel.as_deref(),
+            spec.filter.as_of,
+            spec.max_edges + 1,
+        )
+        .await?;
+    if edges.len() > spec.max_edges {
+        edges.truncate(spec.max_edges);
+        subgraph.truncated = true;
+    }
+    subgraph.edges = edges;
+
+    Ok(subgraph)
+}
+
+fn admits(kinds: &[String], kind: &str) -> bool {
+    kinds.is_empty() || kinds.iter().any(|k| k == kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::{extract_subgraph, SubgraphSpec};
+    use crate::domain::node::KnowledgeNode;
+    use crate::repository::{RepositoryBundle, TraversalFilter};
+
+    fn spec(seeds: Vec<Uuid>, hops: u8) -> SubgraphSpec {
+        SubgraphSpec {
+            seeds,
+            hops,
+            filter: TraversalFilter::default(),
+            kinds: Vec::new(),
+            max_nodes: 100,
+            max_edges: 100,
+        }
+    }
+
+    async fn add(repos: &RepositoryBundle, tenant: Uuid, kind: &str) -> Uuid {
+        let node = KnowledgeNode::new(tenant, kind, json!({}));
+        repos.nodes.upsert(tenant, node.clone()).await.unwrap();
+        node.id
+    }
+
+    async fn link(repos: &RepositoryBundle, tenant: Uuid, src: Uuid, dst: Uuid, rel: &str) {
+        repos
+            .edges
+            .link(tenant, src, dst, rel, 1.0, None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn exports_induced_edges_within_hops() {
+        let repos = RepositoryBundle::in_memory();
+        let tenant = Uuid::new_v4();
+        let a = add(&repos, tenant, "note").await;
+        let b = add(&repos, tenant, "note").await;
+        let c = add(&repos, tenant, "note").await;
+        let d = add(&repos, tenant, "note").await;
+        link(&repos, tenant, a, b, "cites").await;
+        link(&repos, tenant, a, c, "cites").await;
+        // Not a traversal edge from the seed, but both endpoints are in the subgraph.
+        link(&repos, tenant, b, c, "mentions").await;
+        link(&repos, tenant, c, d, "cites").await;
+
+        let one = extract_subgraph(&repos, tenant, &spec(vec![a, a], 1))
+            .await
+            .unwrap();
+        let ids: Vec<_> = one.nodes.iter().map(|n| n.id).collect();
+        assert_eq!(ids.len(), 3);
+        assert!(!ids.contains(&d));
+        assert_eq!(one.edges.len(), 3);
+        assert!(!one.truncated);
+
+        let two = extract_subgraph(&repos, tenant, &spec(vec![a], 2))
+            .await
+            .unwrap();
+        assert_eq!(two.nodes.len(), 4);
+        assert_eq!(two.edges.len(), 4);
+
+        let mut cites = spec(vec![a], 1);
+        cites.filter.rel = Some("cites".into());
+        let cites = extract_subgraph(&repos, tenant, &cites).await.unwrap();
+        assert_eq!(cites.edges.len(), 2);
+        assert!(cites.edges.iter().all(|e| e.rel == "cites"));
+
+        let seeds_only = extract_subgraph(&repos, tenant, &spec(vec![b, c, Uuid::new_v4()], 0))
+            .await
+            .unwrap();
+        assert_eq!(seeds_only.nodes.len(), 2);
+        assert_eq!(seeds_only.edges.len(), 1);
+
+        let other = extract_subgraph(&repos, Uuid::new_v4(), &spec(vec![a], 2))
+            .await
+            .unwrap();
+        assert!(other.nodes.is_empty() && other.edges.is_empty());
+    }
+
+    #[tokio::test]
+    async fn kind_filter_blocks_expansion_and_caps_truncate() {
+        let repos = RepositoryBundle::in_memory();
+        let tenant = Uuid::new_v4();
+        let seed = add(&repos, tenant, "topic").await;
+        let person = add(&repos, tenant, "person").await;
+        let behind = add(&repos, tenant, "note").await;
+        let note = add(&repos, tenant, "note").await;
+        link(&repos, tenant, seed, person, "about").await;
+        link(&repos, tenant, person, behind, "wrote").await;
+        link(&repos, tenant, seed, note, "about").await;
+
+        let mut notes = spec(vec![seed], 2);
+        notes.kinds = vec!["note".into()];
+        let notes = extract_subgraph(&repos, tenant, &notes).await.unwrap();
+        let ids: Vec<_> = notes.nodes.iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec![seed, note]);
+        assert!(!notes.truncated);
+
+        let mut capped = spec(vec![seed], 2);
+        capped.max_nodes = 2;
+        let capped = extract_subgraph(&repos, tenant, &capped).await.unwrap();
+        assert_eq!(capped.nodes.len(), 2);
+        assert!(capped.truncated);
+
+        let mut few_edges = spec(vec![seed], 2);
+        few_edges.max_edges = 1;
+        let few_edges = extract_subgraph(&repos, tenant, &few_edges).await.unwrap();
+        assert_eq!(few_edges.nodes.len(), 4);
+        assert_eq!(few_edges.edges.len(), 1);
+        assert!(few_edges.truncated);
+    }
+}
@@ -3,6 +3,7 @@
 
 pub mod config;
 pub mod domain;
+pub mod graph;
 pub mod pb;
 pub mod repository;
 pub mod scedge;
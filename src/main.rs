@@ -5,10 +5,7 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use synagraph::config::AppConfig;
-use synagraph::repository::in_memory::{
-    InMemoryBus, InMemoryCache, InMemoryEdgeRepository, InMemoryEmbeddingRepository,
-    InMemoryNodeRepository, InMemoryOutboxRepository,
-};
+use synagraph::repository::in_memory::{InMemoryBus, InMemoryCache};
 use synagraph::repository::postgres::{
     PostgresEdgeRepository, PostgresEmbeddingRepository, PostgresNodeRepository,
     PostgresOutboxRepository,
@@ -35,20 +32,13 @@ async fn main() -> Result<()> {
                 Arc::new(PostgresEdgeRepository::new(pool.clone())),
                 Arc::new(PostgresEmbeddingRepository::new(pool.clone())),
                 Arc::new(PostgresOutboxRepository::new(pool)),
-                Arc::new(InMemoryCache::default()),
-                Arc::new(InMemoryBus::default()),
+                Arc::new(InMemoryCache),
+                Arc::new(InMemoryBus),
             )
         }
         None => {
             tracing::info!("initializing in-memory repositories");
-            RepositoryBundle::new(
-                Arc::new(InMemoryNodeRepository::new()),
-                Arc::new(InMemoryEdgeRepository::new()),
-                Arc::new(InMemoryEmbeddingRepository::new()),
-                Arc::new(InMemoryOutboxRepository::new()),
-                Arc::new(InMemoryCache::default()),
-                Arc::new(InMemoryBus::default()),
-            )
+            RepositoryBundle::in_memory()
         }
     };
 
@@ -1,8 +1,13 @@
 // SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
 // Generated protobuf bindings live in this module tree to keep API contracts versioned.
 
+pub mod convert;
+
 pub mod synagraph {
     pub mod v1 {
         tonic::include_proto!("synagraph.v1");
     }
 }
+
+/// Encoded descriptors for `proto/synagraph.proto`, served through gRPC reflection.
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("synagraph_descriptor");
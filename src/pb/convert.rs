@@ -0,0 +1,315 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// Conversions between domain types and their protobuf representations.
+
+use chrono::{DateTime, Utc};
+use prost_types::value::Kind;
+use prost_types::{ListValue, Struct, Timestamp};
+use serde_json::{Map, Number, Value};
+
+use crate::domain::capsule::{
+    CapsuleArtifact, CapsuleIngestRequest, CapsuleLookupResponse, CapsulePolicy, CapsuleProvenance,
+};
+use crate::domain::node::KnowledgeNode;
+use crate::pb::synagraph::v1 as pb;
+use crate::repository::KnowledgeEdge;
+
+pub fn timestamp(value: DateTime<Utc>) -> Timestamp {
+    Timestamp {
+        seconds: value.timestamp(),
+        nanos: value.timestamp_subsec_nanos() as i32,
+    }
+}
+
+/// Returns `None` for timestamps outside the range chrono can represent.
+pub fn datetime(value: &Timestamp) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(value.seconds, value.nanos.max(0) as u32)
+}
+
+/// Converts a JSON object into a protobuf `Struct`. Other JSON values have no `Struct`
+/// representation and yield `None`.
+pub fn json_to_struct(value: &Value) -> Option<Struct> {
+    match value {
+        Value::Object(map) => Some(Struct {
+            fields: map
+                .iter()
+                .map(|(key, value)| (key.clone(), json_to_value(value)))
+                .collect(),
+        }),
+        _ => None,
+    }
+}
+
+pub fn struct_to_json(value: &Struct) -> Value {
+    Value::Object(
+        value
+            .fields
+            .iter()
+            .map(|(key, value)| (key.clone(), value_to_json(value)))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+pub fn json_to_value(value: &Value) -> prost_types::Value {
+    let kind = match value {
+        Value::Null => Kind::NullValue(0),
+        Value::Bool(flag) => Kind::BoolValue(*flag),
+        Value::Number(number) => Kind::NumberValue(number.as_f64().unwrap_or_default()),
+        Value::String(text) => Kind::StringValue(text.clone()),
+        Value::Array(items) => Kind::ListValue(ListValue {
+            values: items.iter().map(json_to_value).collect(),
+        }),
+        Value::Object(_) => Kind::StructValue(json_to_struct(value).unwrap_or_default()),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+pub fn value_to_json(value: &prost_types::Value) -> Value {
+    match &value.kind {
+        None | Some(Kind::NullValue(_)) => Value::Null,
+        Some(Kind::BoolValue(flag)) => Value::Bool(*flag),
+        Some(Kind::NumberValue(number)) => number_to_json(*number),
+        Some(Kind::StringValue(text)) => Value::String(text.clone()),
+        Some(Kind::ListValue(list)) => {
+            Value::Array(list.values.iter().map(value_to_json).collect())
+        }
+        Some(Kind::StructValue(fields)) => struct_to_json(fields),
+    }
+}
+
+/// Protobuf numbers are always doubles; integral values map back to JSON integers so that
+/// payloads written over gRPC read the same as those written over HTTP.
+fn number_to_json(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Value::Number(Number::from(number as i64))
+    } else {
+        Number::from_f64(number)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+}
+
+pub fn node_to_proto(node: &KnowledgeNode) -> pb::Node {
+    pb::Node {
+        node_id: node.id.to_string(),
+        tenant_id: node.tenant_id.to_string(),
+        kind: node.kind.clone(),
+        payload_json: node.payload_json.to_string(),
+        created_at: Some(timestamp(node.created_at)),
+        updated_at: Some(timestamp(node.updated_at)),
+        payload: json_to_struct(&node.payload_json),
+        vector: node.vector.clone().unwrap_or_default(),
+        provenance: node.provenance.as_ref().and_then(json_to_struct),
+        policy: node.policy.as_ref().and_then(json_to_struct),
+    }
+}
+
+pub fn edge_to_proto(edge: &KnowledgeEdge) -> pb::Edge {
+    pb::Edge {
+        edge_id: edge.id.to_string(),
+        tenant_id: edge.tenant_id.to_string(),
+        src_id: edge.src.to_string(),
+        dst_id: edge.dst.to_string(),
+        rel: edge.rel.clone(),
+        weight: edge.weight,
+        props_json: edge
+            .props
+            .as_ref()
+            .map(|props| props.to_string())
+            .unwrap_or_default(),
+        created_at: Some(timestamp(edge.created_at)),
+        props: edge.props.as_ref().and_then(json_to_struct),
+        depth: 0,
+        valid_from: Some(timestamp(edge.valid_from)),
+        valid_to: edge.valid_to.map(timestamp),
+    }
+}
+
+pub fn capsule_artifact_to_proto(artifact: &CapsuleArtifact) -> pb::CapsuleArtifact {
+    pb::CapsuleArtifact {
+        answer: Some(json_to_value(&artifact.answer)),
+        policy: Some(pb::CapsulePolicy {
+            tenant: artifact.policy.tenant.clone(),
+            phi: artifact.policy.phi,
+            pii: artifact.policy.pii,
+            region: artifact.policy.region.clone(),
+            compliance_tags: artifact.policy.compliance_tags.clone(),
+        }),
+        provenance: artifact
+            .provenance
+            .iter()
+            .map(|entry| pb::CapsuleProvenance {
+                source: entry.source.clone(),
+                hash: entry.hash.clone(),
+                version: entry.version.clone(),
+                generated_at: entry.generated_at.map(timestamp),
+            })
+            .collect(),
+        metrics: artifact.metrics.as_ref().and_then(json_to_struct),
+        ttl_seconds: artifact.ttl_seconds,
+        hash: artifact.hash.clone(),
+        metadata: artifact.metadata.as_ref().and_then(json_to_struct),
+    }
+}
+
+pub fn capsule_artifact_from_proto(artifact: pb::CapsuleArtifact) -> CapsuleArtifact {
+    let policy = artifact.policy.unwrap_or_default();
+    CapsuleArtifact {
+        answer: artifact
+            .answer
+            .as_ref()
+            .map(value_to_json)
+            .unwrap_or(Value::Null),
+        policy: CapsulePolicy {
+            tenant: policy.tenant,
+            phi: policy.phi,
+            pii: policy.pii,
+            region: policy.region,
+            compliance_tags: policy.compliance_tags,
+        },
+        provenance: artifact
+            .provenance
+            .into_iter()
+            .map(|entry| CapsuleProvenance {
+                source: entry.source,
+                hash: entry.hash,
+                version: entry.version,
+                generated_at: entry.generated_at.as_ref().and_then(datetime),
+            })
+            .collect(),
+        metrics: artifact.metrics.as_ref().map(struct_to_json),
+        ttl_seconds: artifact.ttl_seconds,
+        hash: artifact.hash,
+        metadata: artifact.metadata.as_ref().map(struct_to_json),
+    }
+}
+
+pub fn capsule_request_from_proto(request: pb::StoreCapsuleRequest) -> CapsuleIngestRequest {
+    CapsuleIngestRequest {
+        key: request.key,
+        artifact: capsule_artifact_from_proto(request.artifact.unwrap_or_default()),
+        expires_at: request.expires_at.as_ref().and_then(datetime),
+    }
+}
+
+pub fn capsule_lookup_to_proto(capsule: &CapsuleLookupResponse) -> pb::LookupCapsuleResponse {
+    pb::LookupCapsuleResponse {
+        key: capsule.key.clone(),
+        artifact: Some(capsule_artifact_to_proto(&capsule.artifact)),
+        expires_at: capsule.expires_at.map(timestamp),
+        ttl_remaining_seconds: capsule.ttl_remaining_seconds,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        capsule_artifact_from_proto, capsule_artifact_to_proto, datetime, edge_to_proto,
+        json_to_struct, node_to_proto, struct_to_json, timestamp,
+    };
+    use crate::domain::capsule::{CapsuleArtifact, CapsulePolicy, CapsuleProvenance};
+    use crate::domain::node::KnowledgeNode;
+    use crate::repository::KnowledgeEdge;
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[test]
+    fn timestamp_preserves_subsecond_precision() {
+        let value = Utc.timestamp_opt(1_700_000_000, 250_000_000).unwrap();
+        let ts = timestamp(value);
+        assert_eq!(ts.seconds, 1_700_000_000);
+        assert_eq!(ts.nanos, 250_000_000);
+    }
+
+    #[test]
+    fn node_to_proto_serializes_payload() {
+        let node = KnowledgeNode::new(Uuid::new_v4(), "note", json!({"title": "hi"}));
+        let proto = node_to_proto(&node);
+        assert_eq!(proto.node_id, node.id.to_string());
+        assert_eq!(proto.kind, "note");
+        assert_eq!(proto.payload_json, "{\"title\":\"hi\"}");
+        assert_eq!(
+            struct_to_json(&proto.payload.unwrap()),
+            json!({"title": "hi"})
+        );
+        assert!(proto.vector.is_empty());
+        assert!(proto.provenance.is_none());
+    }
+
+    #[test]
+    fn struct_roundtrip_preserves_json_shape() {
+        let original = json!({
+            "title": "report",
+            "pages": 12,
+            "score": 0.75,
+            "draft": false,
+            "tags": ["q3", "finance"],
+            "owner": {"team": "analytics", "lead": null}
+        });
+        let converted = json_to_struct(&original).expect("objects convert");
+        assert_eq!(struct_to_json(&converted), original);
+    }
+
+    #[test]
+    fn non_object_values_have_no_struct_form() {
+        assert!(json_to_struct(&json!([1, 2, 3])).is_none());
+        assert!(json_to_struct(&json!(null)).is_none());
+    }
+
+    #[test]
+    fn edge_to_proto_leaves_missing_props_empty() {
+        let edge = KnowledgeEdge {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            src: Uuid::new_v4(),
+            dst: Uuid::new_v4(),
+            rel: "cites".into(),
+            weight: 0.5,
+            props: None,
+            created_at: Utc::now(),
+            valid_from: Utc::now(),
+            valid_to: None,
+        };
+        let proto = edge_to_proto(&edge);
+        assert_eq!(proto.src_id, edge.src.to_string());
+        assert_eq!(proto.rel, "cites");
+        assert!(proto.props_json.is_empty());
+        assert!(proto.valid_to.is_none());
+    }
+
+    #[test]
+    fn datetime_inverts_timestamp() {
+        let value = Utc.timestamp_opt(1_700_000_000, 250_000_000).unwrap();
+        assert_eq!(datetime(&timestamp(value)), Some(value));
+    }
+
+    #[test]
+    fn capsule_artifact_roundtrips_through_proto() {
+        let artifact = CapsuleArtifact {
+            answer: json!("Quarterly revenue was up 23%."),
+            policy: CapsulePolicy {
+                tenant: "acme".into(),
+                pii: true,
+                region: Some("eu".into()),
+                compliance_tags: vec!["gdpr".into()],
+                ..Default::default()
+            },
+            provenance: vec![CapsuleProvenance {
+                source: "synagraph:artifact".into(),
+                hash: "sg-123".into(),
+                version: Some("v1".into()),
+                generated_at: Some(Utc.timestamp_opt(1_700_000_000, 0).unwrap()),
+            }],
+            metrics: Some(json!({"latency_ms": 12})),
+            ttl_seconds: Some(3600),
+            hash: "sg-123".into(),
+            metadata: None,
+        };
+
+        let roundtrip = capsule_artifact_from_proto(capsule_artifact_to_proto(&artifact));
+        assert_eq!(
+            serde_json::to_value(&roundtrip).unwrap(),
+            serde_json::to_value(&artifact).unwrap()
+        );
+    }
+}
@@ -1,100 +1,476 @@
 // SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
-// Simple in-memory repository used for early development and testing flows.
+// In-memory repositories used for local development and tests; they mirror the Postgres semantics.
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::domain::node::KnowledgeNode;
 
+use super::pattern::{
+    edge_field, node_field, HopStep, PatternPlan, PatternRow, PatternValue, Slot,
+};
 use super::{
-    ArtifactCache, BusSubscription, EdgeRepository, EmbeddingRepository, EventBus, KnowledgeEdge,
-    NodeEmbedding, NodeRepository, OutboxEvent, OutboxKind, OutboxRepository, UpsertOutcome,
+    ArtifactCache, BusSubscription, DimensionMismatch, Direction, DistanceMetric, EdgeLink,
+    EdgeRepository, EdgeUpdate, EmbeddingRepository, EventBus, GraphPath, KnowledgeEdge,
+    LinkedEdge, Neighbor, NodeCommunity, NodeCursor, NodeEmbedding, NodeRepository, OutboxEvent,
+    OutboxKind, OutboxRepository, RepositoryBundle, ScoredEmbedding, ScoredNode, SimilarityFilter,
+    TextMatch, TraversalFilter, UpsertOutcome,
 };
 
+/// Nodes and edges for every tenant behind one lock, so traversals see the real nodes and node
+/// deletes can cascade to edges the way the Postgres foreign keys do. The same store backs both
+/// `NodeRepository` and `EdgeRepository`; share it through an `Arc`.
 #[derive(Default)]
-pub struct InMemoryNodeRepository {
-    inner: RwLock<HashMap<Uuid, HashMap<Uuid, KnowledgeNode>>>,
+pub struct InMemoryGraphStore {
+    inner: RwLock<GraphState>,
+}
+
+#[derive(Default)]
+struct GraphState {
+    tenants: HashMap<Uuid, TenantGraph>,
+    /// Node ids are unique across tenants, like the `knowledge_nodes` primary key, so an id
+    /// owned by one tenant cannot be written by another.
+    owners: HashMap<Uuid, Uuid>,
+}
+
+#[derive(Default)]
+struct TenantGraph {
+    nodes: HashMap<Uuid, KnowledgeNode>,
+    edges: Vec<KnowledgeEdge>,
+    /// Cluster labels from the last community detection run, like the `node_communities` table.
+    communities: HashMap<Uuid, NodeCommunity>,
+    /// Embeddings keyed by model, then node, like the `node_embeddings` table.
+    embeddings: HashMap<String, HashMap<Uuid, NodeEmbedding>>,
+}
+
+/// One usable direction of an edge, oriented the way a traversal moves along it.
+struct Step<'a> {
+    from: Uuid,
+    to: Uuid,
+    edge: &'a KnowledgeEdge,
 }
 
-impl InMemoryNodeRepository {
+impl TenantGraph {
+    /// The edges `filter` allows, once per direction a traversal may take them. Mirrors the
+    /// `steps` relation used by the Postgres traversal queries.
+    fn steps<'a>(&'a self, filter: &'a TraversalFilter) -> impl Iterator<Item = Step<'a>> + 'a {
+        let forward = matches!(filter.direction, Direction::Outgoing | Direction::Both);
+        let backward = matches!(filter.direction, Direction::Incoming | Direction::Both);
+        let at = filter.as_of.unwrap_or_else(Utc::now);
+        self.edges
+            .iter()
+            .filter(move |edge| {
+                edge.is_valid_at(at) && filter.rel.as_ref().map(|r| *r == edge.rel).unwrap_or(true)
+            })
+            .flat_map(move |edge| {
+                let out = forward.then_some(Step {
+                    from: edge.src,
+                    to: edge.dst,
+                    edge,
+                });
+                let back = backward.then_some(Step {
+                    from: edge.dst,
+                    to: edge.src,
+                    edge,
+                });
+                out.into_iter().chain(back)
+            })
+    }
+}
+
+/// Backtracking evaluation of a `PatternPlan`: binds node positions left to right, then checks
+/// the predicates once the whole path is bound. Matches are collected as the ids of the returned
+/// columns, which both de-duplicates and orders them like the Postgres plan.
+struct PatternMatch<'a> {
+    graph: &'a TenantGraph,
+    plan: &'a PatternPlan,
+    /// Time at which edges must be valid to take part in a match.
+    at: DateTime<Utc>,
+    nodes: Vec<&'a KnowledgeNode>,
+    edges: Vec<Option<&'a KnowledgeEdge>>,
+    found: BTreeSet<Vec<Uuid>>,
+}
+
+impl<'a> PatternMatch<'a> {
+    fn run(graph: &'a TenantGraph, plan: &'a PatternPlan) -> Vec<PatternRow> {
+        let mut search = Self {
+            graph,
+            plan,
+            at: plan.as_of.unwrap_or_else(Utc::now),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            found: BTreeSet::new(),
+        };
+        for node in graph.nodes.values() {
+            search.visit(node, None);
+        }
+
+        let edges: HashMap<Uuid, &KnowledgeEdge> =
+            graph.edges.iter().map(|edge| (edge.id, edge)).collect();
+        search
+            .found
+            .into_iter()
+            .take(plan.limit)
+            .map(|ids| {
+                ids.iter()
+                    .zip(&plan.returns)
+                    .map(|(id, slot)| match slot {
+                        Slot::Node(_) => PatternValue::Node(graph.nodes[id].clone()),
+                        Slot::Edge(_) => PatternValue::Edge(edges[id].clone()),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Binds `node` to the next node position, reached over `edge` when the hop was single.
+    fn visit(&mut self, node: &'a KnowledgeNode, edge: Option<&'a KnowledgeEdge>) {
+        let index = self.nodes.len();
+        let step = &self.plan.nodes[index];
+        if step.kind.as_ref().is_some_and(|kind| *kind != node.kind) {
+            return;
+        }
+        if step
+            .same_as
+            .is_some_and(|first| self.nodes[first].id != node.id)
+        {
+            return;
+        }
+        if index > 0 {
+            self.edges.push(edge);
+        }
+        self.nodes.push(node);
+
+        match self.plan.hops.get(index) {
+            None => self.record(),
+            Some(hop) if hop.is_single() => {
+                let graph = self.graph;
+                for (edge, to) in graph.hop_steps(hop, self.at, node.id) {
+                    if let Some(next) = graph.nodes.get(&to) {
+                        self.visit(next, Some(edge));
+                    }
+                }
+            }
+            Some(hop) => {
+                let graph = self.graph;
+                let mut reached = BTreeSet::new();
+                graph.reach(hop, self.at, &mut vec![node.id], &mut reached);
+                for to in reached {
+                    if let Some(next) = graph.nodes.get(&to) {
+                        self.visit(next, None);
+                    }
+                }
+            }
+        }
+
+        self.nodes.pop();
+        if index > 0 {
+            self.edges.pop();
+        }
+    }
+
+    fn record(&mut self) {
+        let holds = self.plan.predicates.iter().all(|predicate| {
+            let actual = match predicate.slot {
+                Slot::Node(i) => {
+                    let node = self.nodes[i];
+                    node_field(node, self.graph.communities.get(&node.id), &predicate.field)
+                }
+                Slot::Edge(i) => self.edges[i].and_then(|edge| edge_field(edge, &predicate.field)),
+            };
+            predicate.op.holds(actual.as_ref(), &predicate.value)
+        });
+        if holds {
+            let ids = self
+                .plan
+                .returns
+                .iter()
+                .filter_map(|slot| match slot {
+                    Slot::Node(i) => Some(self.nodes[*i].id),
+                    Slot::Edge(i) => self.edges[*i].map(|edge| edge.id),
+                })
+                .collect();
+            self.found.insert(ids);
+        }
+    }
+}
+
+impl TenantGraph {
+    fn check_endpoints(&self, tenant: Uuid, link: &EdgeLink) -> Result<()> {
+        for endpoint in [link.src, link.dst] {
+            if !self.nodes.contains_key(&endpoint) {
+                bail!("edge endpoint {endpoint} not found for tenant {tenant}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Links an edge whose endpoints exist.
+    fn link(&mut self, tenant: Uuid, link: EdgeLink) -> LinkedEdge {
+        // Expired edges are history; only the open edge for the relation is updated in place.
+        if let Some(existing) = self.edges.iter_mut().find(|edge| {
+            edge.src == link.src
+                && edge.dst == link.dst
+                && edge.rel == link.rel
+                && edge.valid_to.is_none()
+        }) {
+            existing.weight = link.weight;
+            existing.props = link.props;
+            return LinkedEdge {
+                id: existing.id,
+                outcome: UpsertOutcome::Updated,
+            };
+        }
+
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        self.edges.push(KnowledgeEdge {
+            id,
+            tenant_id: tenant,
+            src: link.src,
+            dst: link.dst,
+            rel: link.rel,
+            weight: link.weight,
+            props: link.props,
+            created_at: now,
+            valid_from: now,
+            valid_to: None,
+        });
+        LinkedEdge {
+            id,
+            outcome: UpsertOutcome::Created,
+        }
+    }
+
+    /// Edges valid at `at` that `hop` may take from `from`, with the node at their other end.
+    fn hop_steps(
+        &self,
+        hop: &HopStep,
+        at: DateTime<Utc>,
+        from: Uuid,
+    ) -> Vec<(&KnowledgeEdge, Uuid)> {
+        let forward = matches!(hop.direction, Direction::Outgoing | Direction::Both);
+        let backward = matches!(hop.direction, Direction::Incoming | Direction::Both);
+        let mut steps = Vec::new();
+        for edge in self
+            .edges
+            .iter()
+            .filter(|edge| hop.accepts(&edge.rel) && edge.is_valid_at(at))
+        {
+            if forward && edge.src == from {
+                steps.push((edge, edge.dst));
+            }
+            if backward && edge.dst == from {
+                steps.push((edge, edge.src));
+            }
+        }
+        steps
+    }
+
+    /// Adds every node that a path without repeated nodes reaches from the end of `path` with a
+    /// length `hop` accepts, like the recursive CTE the Postgres plan uses.
+    fn reach(
+        &self,
+        hop: &HopStep,
+        at: DateTime<Utc>,
+        path: &mut Vec<Uuid>,
+        reached: &mut BTreeSet<Uuid>,
+    ) {
+        let depth = path.len() - 1;
+        if depth >= usize::from(hop.min) {
+            reached.insert(*path.last().expect("path starts at the origin"));
+        }
+        if depth == usize::from(hop.max) {
+            return;
+        }
+        let from = *path.last().expect("path starts at the origin");
+        for (_, to) in self.hop_steps(hop, at, from) {
+            if !path.contains(&to) {
+                path.push(to);
+                self.reach(hop, at, path, reached);
+                path.pop();
+            }
+        }
+    }
+}
+
+impl InMemoryGraphStore {
     pub fn new() -> Self {
         Self::default()
     }
+}
+
+impl GraphState {
+    fn ensure_writable(&self, tenant: Uuid, id: Uuid) -> Result<()> {
+        match self.owners.get(&id) {
+            Some(owner) if *owner != tenant => {
+                bail!("node {id} belongs to another tenant")
+            }
+            _ => Ok(()),
+        }
+    }
 
-    fn tenant_map_mut<'a>(
-        guard: &'a mut HashMap<Uuid, HashMap<Uuid, KnowledgeNode>>,
+    /// Vectors of one tenant share a length, as the fixed-size Postgres column enforces. Nodes
+    /// being replaced do not count, so a tenant's only vector can change size.
+    fn check_dims(&self, tenant: Uuid, nodes: &[KnowledgeNode]) -> Result<()> {
+        let replaced: HashSet<Uuid> = nodes.iter().map(|node| node.id).collect();
+        let stored = self.tenants.get(&tenant).and_then(|graph| {
+            graph
+                .nodes
+                .values()
+                .filter(|node| !replaced.contains(&node.id))
+                .find_map(|node| node.vector.as_ref().map(Vec::len))
+        });
+        DimensionMismatch::check(stored, nodes)
+    }
+
+    fn store(
+        &mut self,
         tenant: Uuid,
-    ) -> &'a mut HashMap<Uuid, KnowledgeNode> {
-        guard.entry(tenant).or_insert_with(HashMap::new)
+        mut node: KnowledgeNode,
+        now: DateTime<Utc>,
+    ) -> UpsertOutcome {
+        node.tenant_id = tenant;
+        node.updated_at = now;
+        self.owners.insert(node.id, tenant);
+        let tenant_map = &mut self.tenants.entry(tenant).or_default().nodes;
+        match tenant_map.get(&node.id) {
+            Some(existing) => {
+                node.created_at = existing.created_at;
+                tenant_map.insert(node.id, node);
+                UpsertOutcome::Updated
+            }
+            None => {
+                node.created_at = now;
+                tenant_map.insert(node.id, node);
+                UpsertOutcome::Created
+            }
+        }
+    }
+
+    /// Removes a node and every edge touching it.
+    fn remove(&mut self, tenant: Uuid, id: Uuid) -> Option<KnowledgeNode> {
+        let graph = self.tenants.get_mut(&tenant)?;
+        let removed = graph.nodes.remove(&id)?;
+        graph.edges.retain(|edge| edge.src != id && edge.dst != id);
+        graph.communities.remove(&id);
+        for nodes in graph.embeddings.values_mut() {
+            nodes.remove(&id);
+        }
+        self.owners.remove(&id);
+        Some(removed)
+    }
+}
+
+/// Lower-cased alphanumeric runs of `text`. Unlike Postgres text search there is no stemming and
+/// no stop-word list, so only exact words match.
+fn words(text: &str, out: &mut Vec<String>) {
+    out.extend(
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase),
+    );
+}
+
+/// The words of every string in `value`, which is what the Postgres `search_tsv` column indexes.
+fn payload_words(value: &serde_json::Value, out: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(text) => words(text, out),
+        serde_json::Value::Array(items) => items.iter().for_each(|item| payload_words(item, out)),
+        serde_json::Value::Object(fields) => {
+            fields.values().for_each(|item| payload_words(item, out))
+        }
+        _ => {}
     }
 }
 
 #[async_trait]
-impl NodeRepository for InMemoryNodeRepository {
-    async fn upsert(&self, tenant: Uuid, mut node: KnowledgeNode) -> Result<UpsertOutcome> {
+impl NodeRepository for InMemoryGraphStore {
+    async fn upsert(&self, tenant: Uuid, node: KnowledgeNode) -> Result<UpsertOutcome> {
         let mut guard = self.inner.write().await;
-        let tenant_map = Self::tenant_map_mut(&mut guard, tenant);
+        guard.ensure_writable(tenant, node.id)?;
+        guard.check_dims(tenant, std::slice::from_ref(&node))?;
+        Ok(guard.store(tenant, node, Utc::now()))
+    }
 
-        node.tenant_id = tenant;
+    async fn upsert_batch(
+        &self,
+        tenant: Uuid,
+        nodes: Vec<KnowledgeNode>,
+    ) -> Result<Vec<UpsertOutcome>> {
+        let mut guard = self.inner.write().await;
+        for node in &nodes {
+            guard.ensure_writable(tenant, node.id)?;
+        }
+        guard.check_dims(tenant, &nodes)?;
         let now = Utc::now();
-
-        let outcome = if let Some(existing) = tenant_map.get(&node.id) {
-            node.created_at = existing.created_at;
-            node.updated_at = now;
-            tenant_map.insert(node.id, node);
-            UpsertOutcome::Updated
-        } else {
-            node.created_at = now;
-            node.updated_at = now;
-            tenant_map.insert(node.id, node);
-            UpsertOutcome::Created
-        };
-
-        Ok(outcome)
+        Ok(nodes
+            .into_iter()
+            .map(|node| guard.store(tenant, node, now))
+            .collect())
     }
 
     async fn get(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeNode>> {
         let guard = self.inner.read().await;
-        Ok(guard.get(&tenant).and_then(|nodes| nodes.get(&id)).cloned())
+        Ok(guard
+            .tenants
+            .get(&tenant)
+            .and_then(|graph| graph.nodes.get(&id))
+            .cloned())
+    }
+
+    async fn get_many(&self, tenant: Uuid, ids: &[Uuid]) -> Result<Vec<KnowledgeNode>> {
+        let guard = self.inner.read().await;
+        let Some(graph) = guard.tenants.get(&tenant) else {
+            return Ok(Vec::new());
+        };
+        Ok(ids
+            .iter()
+            .filter_map(|id| graph.nodes.get(id))
+            .cloned()
+            .collect())
     }
 
     async fn get_by_key(&self, tenant: Uuid, key: &str) -> Result<Option<KnowledgeNode>> {
         let guard = self.inner.read().await;
-        let Some(nodes_map) = guard.get(&tenant) else {
+        let Some(graph) = guard.tenants.get(&tenant) else {
             return Ok(None);
         };
 
-        let mut candidates: Vec<KnowledgeNode> = nodes_map
+        let mut candidates: Vec<KnowledgeNode> = graph
+            .nodes
             .values()
             .filter(|node| node.payload_json.get("key").and_then(|v| v.as_str()) == Some(key))
             .cloned()
             .collect();
 
-        candidates.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        candidates.sort_by_key(|node| std::cmp::Reverse(node.updated_at));
         Ok(candidates.into_iter().next())
     }
 
+    async fn delete(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeNode>> {
+        let mut guard = self.inner.write().await;
+        Ok(guard.remove(tenant, id))
+    }
+
     async fn delete_by_key(&self, tenant: Uuid, key: &str) -> Result<Option<KnowledgeNode>> {
         let mut guard = self.inner.write().await;
-        let Some(nodes_map) = guard.get_mut(&tenant) else {
+        let Some(graph) = guard.tenants.get(&tenant) else {
             return Ok(None);
         };
 
-        if let Some((id, _)) = nodes_map
-            .iter()
-            .find(|(_, node)| node.payload_json.get("key").and_then(|v| v.as_str()) == Some(key))
-            .map(|(id, node)| (*id, node.clone()))
-        {
-            let removed = nodes_map.remove(&id);
-            return Ok(removed);
-        }
-
-        Ok(None)
+        let found = graph
+            .nodes
+            .values()
+            .find(|node| node.payload_json.get("key").and_then(|v| v.as_str()) == Some(key))
+            .map(|node| node.id);
+        Ok(found.and_then(|id| guard.remove(tenant, id)))
     }
 
     async fn query_by_kind(
@@ -102,28 +478,30 @@ impl NodeRepository for InMemoryNodeRepository {
         tenant: Uuid,
         kind: &str,
         limit: usize,
-        cursor: Option<Uuid>,
+        cursor: Option<NodeCursor>,
+        include_vectors: bool,
     ) -> Result<Vec<KnowledgeNode>> {
         let guard = self.inner.read().await;
-        let Some(nodes_map) = guard.get(&tenant) else {
+        let Some(graph) = guard.tenants.get(&tenant) else {
             return Ok(Vec::new());
         };
 
-        let mut nodes: Vec<KnowledgeNode> = nodes_map
+        let key = |node: &KnowledgeNode| (node.created_at, node.id);
+        let mut nodes: Vec<KnowledgeNode> = graph
+            .nodes
             .values()
             .filter(|node| node.kind == kind)
+            .filter(|node| cursor.is_none_or(|cursor| key(node) > (cursor.created_at, cursor.id)))
             .cloned()
             .collect();
 
-        nodes.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
-
-        if let Some(cursor_id) = cursor {
-            if let Some(pos) = nodes.iter().position(|node| node.id == cursor_id) {
-                nodes = nodes.into_iter().skip(pos + 1).collect();
+        nodes.sort_by_key(key);
+        nodes.truncate(limit);
+        if !include_vectors {
+            for node in &mut nodes {
+                node.vector = None;
             }
         }
-
-        nodes.truncate(limit);
         Ok(nodes)
     }
 
@@ -131,59 +509,112 @@ impl NodeRepository for InMemoryNodeRepository {
         &self,
         tenant: Uuid,
         vector: &[f32],
+        metric: DistanceMetric,
+        filter: &SimilarityFilter,
         limit: usize,
-    ) -> Result<Vec<KnowledgeNode>> {
+    ) -> Result<Vec<ScoredNode>> {
         if vector.is_empty() {
             return Ok(Vec::new());
         }
 
         let guard = self.inner.read().await;
-        let Some(nodes_map) = guard.get(&tenant) else {
+        let Some(graph) = guard.tenants.get(&tenant) else {
             return Ok(Vec::new());
         };
 
-        let mut scored: Vec<(f32, KnowledgeNode)> = nodes_map
-            .values()
-            .filter_map(|node| {
-                node.vector.as_ref().map(|candidate| {
-                    let score = candidate
-                        .iter()
-                        .zip(vector.iter())
-                        .map(|(a, b)| a * b)
-                        .sum();
-                    (score, node.clone())
-                })
+        let mut ranked: Vec<(f64, &KnowledgeNode)> = Vec::new();
+        // Only nodes the filter keeps are compared, so only their lengths must match.
+        for node in graph.nodes.values() {
+            let Some(candidate) = node.vector.as_ref().filter(|_| filter.matches(node)) else {
+                continue;
+            };
+            if candidate.len() != vector.len() {
+                return Err(DimensionMismatch {
+                    expected: candidate.len(),
+                    actual: vector.len(),
+                }
+                .into());
+            }
+            ranked.push((metric.distance(candidate, vector), node));
+        }
+
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.id.cmp(&b.1.id)));
+        Ok(ranked
+            .into_iter()
+            .take(limit)
+            .map(|(distance, node)| ScoredNode {
+                node: node.clone(),
+                score: metric.score(distance),
+                distance_metric: metric,
             })
-            .collect();
+            .collect())
+    }
 
-        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
-        let results = scored
+    async fn search_text(
+        &self,
+        tenant: Uuid,
+        query: &str,
+        filter: &SimilarityFilter,
+        limit: usize,
+    ) -> Result<Vec<TextMatch>> {
+        let mut terms = Vec::new();
+        words(query, &mut terms);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let guard = self.inner.read().await;
+        let Some(graph) = guard.tenants.get(&tenant) else {
+            return Ok(Vec::new());
+        };
+
+        let mut ranked: Vec<(usize, &KnowledgeNode)> = Vec::new();
+        for node in graph.nodes.values().filter(|node| filter.matches(node)) {
+            let mut text = Vec::new();
+            payload_words(&node.payload_json, &mut text);
+            let counts: Vec<usize> = terms
+                .iter()
+                .map(|term| text.iter().filter(|word| *word == term).count())
+                .collect();
+            if counts.iter().all(|count| *count > 0) {
+                ranked.push((counts.iter().sum(), node));
+            }
+        }
+
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.id.cmp(&b.1.id)));
+        Ok(ranked
             .into_iter()
             .take(limit)
-            .map(|(_, node)| node)
-            .collect();
-        Ok(results)
+            .map(|(hits, node)| TextMatch {
+                node: node.clone(),
+                score: hits as f64,
+            })
+            .collect())
     }
 
-    async fn health_check(&self) -> Result<()> {
+    async fn replace_communities(
+        &self,
+        tenant: Uuid,
+        _algorithm: &str,
+        assignments: &[NodeCommunity],
+    ) -> Result<()> {
+        let mut guard = self.inner.write().await;
+        let graph = guard.tenants.entry(tenant).or_default();
+        graph.communities = assignments
+            .iter()
+            .filter(|assignment| graph.nodes.contains_key(&assignment.node_id))
+            .map(|assignment| (assignment.node_id, assignment.clone()))
+            .collect();
         Ok(())
     }
-}
 
-#[allow(dead_code)]
-#[derive(Default)]
-pub struct InMemoryEdgeRepository {
-    edges: RwLock<HashMap<Uuid, Vec<(Uuid, KnowledgeEdge)>>>,
-}
-
-impl InMemoryEdgeRepository {
-    pub fn new() -> Self {
-        Self::default()
+    async fn health_check(&self) -> Result<()> {
+        Ok(())
     }
 }
 
 #[async_trait]
-impl EdgeRepository for InMemoryEdgeRepository {
+impl EdgeRepository for InMemoryGraphStore {
     async fn link(
         &self,
         tenant: Uuid,
@@ -192,73 +623,362 @@ impl EdgeRepository for InMemoryEdgeRepository {
         rel: &str,
         weight: f32,
         props: Option<serde_json::Value>,
-    ) -> Result<()> {
-        let mut guard = self.edges.write().await;
-        let list = guard.entry(tenant).or_insert_with(Vec::new);
-        list.push((
+    ) -> Result<LinkedEdge> {
+        let mut guard = self.inner.write().await;
+        let graph = guard.tenants.entry(tenant).or_default();
+        let link = EdgeLink {
             src,
-            KnowledgeEdge {
-                id: Uuid::new_v4(),
-                tenant_id: tenant,
-                src,
-                dst,
-                rel: rel.to_string(),
-                weight,
-                props,
-                created_at: Utc::now(),
-            },
-        ));
-        Ok(())
+            dst,
+            rel: rel.to_string(),
+            weight,
+            props,
+        };
+        graph.check_endpoints(tenant, &link)?;
+        Ok(graph.link(tenant, link))
     }
 
-    async fn neighbors(
+    async fn link_batch(&self, tenant: Uuid, links: Vec<EdgeLink>) -> Result<Vec<LinkedEdge>> {
+        let mut guard = self.inner.write().await;
+        let graph = guard.tenants.entry(tenant).or_default();
+        for link in &links {
+            graph.check_endpoints(tenant, link)?;
+        }
+        Ok(links
+            .into_iter()
+            .map(|link| graph.link(tenant, link))
+            .collect())
+    }
+
+    async fn unlink(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeEdge>> {
+        let mut guard = self.inner.write().await;
+        let Some(graph) = guard.tenants.get_mut(&tenant) else {
+            return Ok(None);
+        };
+        Ok(graph
+            .edges
+            .iter()
+            .position(|edge| edge.id == id)
+            .map(|index| graph.edges.remove(index)))
+    }
+
+    async fn get_edge(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeEdge>> {
+        let guard = self.inner.read().await;
+        Ok(guard
+            .tenants
+            .get(&tenant)
+            .and_then(|graph| graph.edges.iter().find(|edge| edge.id == id))
+            .cloned())
+    }
+
+    async fn update_edge(
+        &self,
+        tenant: Uuid,
+        id: Uuid,
+        update: EdgeUpdate,
+    ) -> Result<Option<KnowledgeEdge>> {
+        let mut guard = self.inner.write().await;
+        let Some(edge) = guard
+            .tenants
+            .get_mut(&tenant)
+            .and_then(|graph| graph.edges.iter_mut().find(|edge| edge.id == id))
+        else {
+            return Ok(None);
+        };
+        if let Some(weight) = update.weight {
+            edge.weight = weight;
+        }
+        if let Some(props) = update.props {
+            edge.props = Some(props);
+        }
+        if let Some(valid_from) = update.valid_from {
+            if edge.valid_to.is_some_and(|end| end < valid_from) {
+                bail!("edge {id} cannot become valid after it expired");
+            }
+            edge.valid_from = valid_from;
+        }
+        Ok(Some(edge.clone()))
+    }
+
+    async fn expire_edge(
         &self,
         tenant: Uuid,
         id: Uuid,
+        at: DateTime<Utc>,
+    ) -> Result<Option<KnowledgeEdge>> {
+        let mut guard = self.inner.write().await;
+        let Some(edge) = guard
+            .tenants
+            .get_mut(&tenant)
+            .and_then(|graph| graph.edges.iter_mut().find(|edge| edge.id == id))
+        else {
+            return Ok(None);
+        };
+        if edge.valid_to.is_none() {
+            if at < edge.valid_from {
+                bail!("edge {id} cannot expire before it became valid");
+            }
+            edge.valid_to = Some(at);
+        }
+        Ok(Some(edge.clone()))
+    }
+
+    async fn list_edges(
+        &self,
+        tenant: Uuid,
         rel: Option<&str>,
-        _hops: u8,
+        as_of: Option<DateTime<Utc>>,
         limit: usize,
-    ) -> Result<Vec<KnowledgeNode>> {
-        let guard = self.edges.read().await;
-        let Some(edges) = guard.get(&tenant) else {
+    ) -> Result<Vec<KnowledgeEdge>> {
+        let guard = self.inner.read().await;
+        let Some(graph) = guard.tenants.get(&tenant) else {
             return Ok(Vec::new());
         };
 
-        let nodes: Vec<KnowledgeNode> = edges
+        let at = as_of.unwrap_or_else(Utc::now);
+        let mut edges: Vec<KnowledgeEdge> = graph
+            .edges
             .iter()
-            .filter(|(src, edge)| *src == id && rel.map(|r| r == edge.rel).unwrap_or(true))
-            .take(limit)
-            .map(|(_, edge)| {
-                KnowledgeNode::new(
-                    tenant,
-                    edge.rel.clone(),
-                    serde_json::json!({ "target": edge.dst }),
-                )
+            .filter(|edge| edge.is_valid_at(at) && rel.map(|r| r == edge.rel).unwrap_or(true))
+            .cloned()
+            .collect();
+        edges.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        edges.truncate(limit);
+        Ok(edges)
+    }
+
+    async fn edges_among(
+        &self,
+        tenant: Uuid,
+        ids: &[Uuid],
+        rel: Option<&str>,
+        as_of: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeEdge>> {
+        let guard = self.inner.read().await;
+        let Some(graph) = guard.tenants.get(&tenant) else {
+            return Ok(Vec::new());
+        };
+
+        let at = as_of.unwrap_or_else(Utc::now);
+        let members: HashSet<&Uuid> = ids.iter().collect();
+        let mut edges: Vec<KnowledgeEdge> = graph
+            .edges
+            .iter()
+            .filter(|edge| {
+                members.contains(&edge.src)
+                    && members.contains(&edge.dst)
+                    && edge.is_valid_at(at)
+                    && rel.map(|r| r == edge.rel).unwrap_or(true)
             })
+            .cloned()
             .collect();
+        edges.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        edges.truncate(limit);
+        Ok(edges)
+    }
 
-        Ok(nodes)
+    async fn neighbors(
+        &self,
+        tenant: Uuid,
+        id: Uuid,
+        filter: &TraversalFilter,
+        hops: u8,
+        limit: usize,
+    ) -> Result<Vec<Neighbor>> {
+        let guard = self.inner.read().await;
+        let Some(graph) = guard.tenants.get(&tenant) else {
+            return Ok(Vec::new());
+        };
+
+        // Breadth-first walk returning what the Postgres backend's recursive query does: a node is
+        // reported at the shallowest depth a cycle-free path reaches it, once per edge leading to
+        // it from a node one hop shallower.
+        let mut visited = HashSet::from([id]);
+        let mut frontier = HashSet::from([id]);
+        let mut neighbors = Vec::new();
+        for depth in 1..=hops {
+            let mut layer: Vec<Step> = graph
+                .steps(filter)
+                .filter(|step| frontier.contains(&step.from) && !visited.contains(&step.to))
+                .collect();
+            if layer.is_empty() {
+                break;
+            }
+            layer.sort_by_key(|step| std::cmp::Reverse(step.edge.created_at));
+
+            frontier = layer.iter().map(|step| step.to).collect();
+            visited.extend(frontier.iter().copied());
+            neighbors.extend(layer.into_iter().filter_map(|step| {
+                graph.nodes.get(&step.to).map(|node| Neighbor {
+                    node: node.clone(),
+                    edge: step.edge.clone(),
+                    depth,
+                })
+            }));
+        }
+
+        neighbors.truncate(limit);
+        Ok(neighbors)
+    }
+
+    async fn shortest_path(
+        &self,
+        tenant: Uuid,
+        from: Uuid,
+        to: Uuid,
+        max_hops: u8,
+        filter: &TraversalFilter,
+    ) -> Result<Option<GraphPath>> {
+        let guard = self.inner.read().await;
+        let Some(graph) = guard.tenants.get(&tenant) else {
+            return Ok(None);
+        };
+        let Some(origin) = graph.nodes.get(&from) else {
+            return Ok(None);
+        };
+
+        // Paths end at the target, so steps leaving it are never useful.
+        let steps: Vec<Step> = graph
+            .steps(filter)
+            .filter(|step| step.from != to && step.edge.weight > 0.0)
+            .collect();
+
+        // Hop-bounded Bellman-Ford: after round `k`, `best` holds the cheapest route using at
+        // most `k` steps to every reachable node, as indices into `steps`.
+        let mut best: HashMap<Uuid, (f64, Vec<usize>)> = HashMap::from([(from, (0.0, Vec::new()))]);
+        for _ in 0..max_hops {
+            let previous = best.clone();
+            for (index, step) in steps.iter().enumerate() {
+                let Some((cost, route)) = previous.get(&step.from) else {
+                    continue;
+                };
+                let candidate = cost + 1.0 / f64::from(step.edge.weight);
+                let improves = match best.get(&step.to) {
+                    Some((current, existing)) => {
+                        candidate < *current
+                            || (candidate == *current && route.len() + 1 < existing.len())
+                    }
+                    None => true,
+                };
+                if improves {
+                    let mut route = route.clone();
+                    route.push(index);
+                    best.insert(step.to, (candidate, route));
+                }
+            }
+        }
+
+        let Some((cost, route)) = best.remove(&to) else {
+            return Ok(None);
+        };
+        let mut path = GraphPath {
+            nodes: vec![origin.clone()],
+            edges: Vec::with_capacity(route.len()),
+            cost,
+        };
+        for index in route {
+            let step = &steps[index];
+            let Some(node) = graph.nodes.get(&step.to) else {
+                return Ok(None);
+            };
+            path.nodes.push(node.clone());
+            path.edges.push(step.edge.clone());
+        }
+        Ok(Some(path))
+    }
+
+    async fn match_pattern(&self, tenant: Uuid, plan: &PatternPlan) -> Result<Vec<PatternRow>> {
+        let guard = self.inner.read().await;
+        Ok(match guard.tenants.get(&tenant) {
+            Some(graph) => PatternMatch::run(graph, plan),
+            None => Vec::new(),
+        })
     }
 }
 
-#[allow(dead_code)]
-#[derive(Default)]
-pub struct InMemoryEmbeddingRepository;
+/// Embeddings kept beside the nodes of an `InMemoryGraphStore`, so like the Postgres table they
+/// only attach to the tenant's own nodes and go away when the node is deleted.
+pub struct InMemoryEmbeddingRepository {
+    graph: Arc<InMemoryGraphStore>,
+}
 
 impl InMemoryEmbeddingRepository {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(graph: Arc<InMemoryGraphStore>) -> Self {
+        Self { graph }
     }
 }
 
 #[async_trait]
 impl EmbeddingRepository for InMemoryEmbeddingRepository {
-    async fn upsert_embedding(&self, _tenant: Uuid, _embedding: NodeEmbedding) -> Result<()> {
+    async fn upsert_embedding(&self, tenant: Uuid, mut embedding: NodeEmbedding) -> Result<()> {
+        embedding.check_dim()?;
+        let mut guard = self.graph.inner.write().await;
+        let Some(graph) = guard
+            .tenants
+            .get_mut(&tenant)
+            .filter(|graph| graph.nodes.contains_key(&embedding.node_id))
+        else {
+            bail!("node {} not found", embedding.node_id);
+        };
+        embedding.tenant_id = tenant;
+        embedding.created_at = Utc::now();
+        graph
+            .embeddings
+            .entry(embedding.model.clone())
+            .or_default()
+            .insert(embedding.node_id, embedding);
         Ok(())
     }
 
-    async fn get_embeddings(&self, _tenant: Uuid, _node_id: Uuid) -> Result<Vec<NodeEmbedding>> {
-        Ok(Vec::new())
+    async fn get_embeddings(&self, tenant: Uuid, node_id: Uuid) -> Result<Vec<NodeEmbedding>> {
+        let guard = self.graph.inner.read().await;
+        let Some(graph) = guard.tenants.get(&tenant) else {
+            return Ok(Vec::new());
+        };
+        let mut embeddings: Vec<NodeEmbedding> = graph
+            .embeddings
+            .values()
+            .filter_map(|nodes| nodes.get(&node_id).cloned())
+            .collect();
+        embeddings.sort_by(|a, b| a.model.cmp(&b.model));
+        Ok(embeddings)
+    }
+
+    async fn search_by_model(
+        &self,
+        tenant: Uuid,
+        model: &str,
+        vector: &[f32],
+        metric: DistanceMetric,
+        k: usize,
+    ) -> Result<Vec<ScoredEmbedding>> {
+        if vector.is_empty() {
+            return Ok(Vec::new());
+        }
+        let guard = self.graph.inner.read().await;
+        let Some(nodes) = guard
+            .tenants
+            .get(&tenant)
+            .and_then(|graph| graph.embeddings.get(model))
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut ranked: Vec<(f64, &NodeEmbedding)> = nodes
+            .values()
+            .filter(|embedding| embedding.vec.len() == vector.len())
+            .map(|embedding| (metric.distance(&embedding.vec, vector), embedding))
+            .collect();
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.node_id.cmp(&b.1.node_id)));
+        Ok(ranked
+            .into_iter()
+            .take(k)
+            .map(|(distance, embedding)| ScoredEmbedding {
+                embedding: embedding.clone(),
+                score: metric.score(distance),
+                distance_metric: metric,
+            })
+            .collect())
     }
 }
 
@@ -352,17 +1072,68 @@ impl EventBus for InMemoryBus {
     }
 }
 
+impl RepositoryBundle {
+    /// Every repository in memory, with one `InMemoryGraphStore` behind nodes, edges and
+    /// embeddings.
+    pub fn in_memory() -> Self {
+        let graph = Arc::new(InMemoryGraphStore::new());
+        Self::new(
+            graph.clone(),
+            graph.clone(),
+            Arc::new(InMemoryEmbeddingRepository::new(graph)),
+            Arc::new(InMemoryOutboxRepository::new()),
+            Arc::new(InMemoryCache),
+            Arc::new(InMemoryBus),
+        )
+    }
+}
+
+#[cfg(test)]
+impl KnowledgeEdge {
+    /// A `rel` edge valid from now, for tests that only care about its endpoints and weight.
+    pub(crate) fn test(src: Uuid, dst: Uuid, weight: f32) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::nil(),
+            src,
+            dst,
+            rel: "rel".into(),
+            weight,
+            props: None,
+            created_at: now,
+            valid_from: now,
+            valid_to: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::InMemoryNodeRepository;
+    use std::sync::Arc;
+
+    use chrono::Duration;
+
+    use super::{InMemoryEmbeddingRepository, InMemoryGraphStore};
     use crate::domain::node::KnowledgeNode;
-    use crate::repository::{NodeRepository, UpsertOutcome};
+    use crate::repository::{
+        DimensionMismatch, Direction, DistanceMetric, EdgeLink, EdgeRepository, EdgeUpdate,
+        EmbeddingRepository, NodeCursor, NodeEmbedding, NodeRepository, SimilarityFilter,
+        TraversalFilter, UpsertOutcome,
+    };
     use serde_json::json;
     use uuid::Uuid;
 
+    fn only(rel: &str) -> TraversalFilter {
+        TraversalFilter {
+            rel: Some(rel.into()),
+            ..Default::default()
+        }
+    }
+
     #[tokio::test]
     async fn upsert_inserts_and_updates_nodes() {
-        let repo = InMemoryNodeRepository::new();
+        let repo = InMemoryGraphStore::new();
         let tenant = Uuid::new_v4();
 
         let node = KnowledgeNode::new(tenant, "note", json!({"title": "hello"}));
@@ -386,26 +1157,785 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn query_by_kind_respects_cursor() {
-        let repo = InMemoryNodeRepository::new();
+    async fn vectors_of_a_tenant_share_one_length() {
+        let repo = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+        let with_vector = |vector: Vec<f32>| {
+            let mut node = KnowledgeNode::new(tenant, "note", json!({}));
+            node.vector = Some(vector);
+            node
+        };
+
+        let mut first = with_vector(vec![1.0, 0.0]);
+        repo.upsert(tenant, first.clone()).await.unwrap();
+        // Replacing the only stored vector may change its length.
+        first.vector = Some(vec![1.0, 0.0, 0.0]);
+        repo.upsert(tenant, first).await.unwrap();
+
+        let err = repo
+            .upsert(tenant, with_vector(vec![1.0, 0.0]))
+            .await
+            .unwrap_err();
+        let mismatch = err.downcast_ref::<DimensionMismatch>().unwrap();
+        assert_eq!((mismatch.expected, mismatch.actual), (3, 2));
+
+        let batch = vec![with_vector(vec![0.0, 1.0, 0.0]), with_vector(vec![1.0])];
+        let err = repo.upsert_batch(tenant, batch).await.unwrap_err();
+        assert!(err.downcast_ref::<DimensionMismatch>().is_some());
+        assert_eq!(
+            repo.query_by_kind(tenant, "note", 10, None, true)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Other tenants pick their own length.
+        let other = Uuid::new_v4();
+        let mut node = KnowledgeNode::new(other, "note", json!({}));
+        node.vector = Some(vec![1.0]);
+        repo.upsert(other, node).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn query_by_kind_pages_with_cursor() {
+        let repo = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+
+        // A batch shares one creation time, so pages must break ties by id.
+        let nodes: Vec<KnowledgeNode> = (0..5)
+            .map(|index| {
+                let mut node = KnowledgeNode::new(tenant, "note", json!({ "index": index }));
+                node.vector = Some(vec![1.0, 0.0]);
+                node
+            })
+            .collect();
+        repo.upsert_batch(tenant, nodes.clone()).await.unwrap();
+        let later = KnowledgeNode::new(tenant, "note", json!({ "index": 5 }));
+        repo.upsert(tenant, later.clone()).await.unwrap();
+
+        let first_page = repo
+            .query_by_kind(tenant, "note", 2, None, true)
+            .await
+            .unwrap();
+        assert!(first_page.iter().all(|node| node.vector.is_some()));
+
+        let mut seen: Vec<Uuid> = first_page.iter().map(|node| node.id).collect();
+        let mut cursor = NodeCursor::after(first_page.last().unwrap());
+        loop {
+            let page = repo
+                .query_by_kind(tenant, "note", 2, Some(cursor), false)
+                .await
+                .unwrap();
+            assert!(page.iter().all(|node| node.vector.is_none()));
+            seen.extend(page.iter().map(|node| node.id));
+            match page.last() {
+                Some(last) if page.len() == 2 => cursor = NodeCursor::after(last),
+                _ => break,
+            }
+        }
+
+        let mut expected: Vec<Uuid> = nodes.iter().map(|node| node.id).collect();
+        expected.sort();
+        expected.push(later.id);
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn node_cursor_round_trips_through_its_token() {
+        let node = KnowledgeNode::new(Uuid::new_v4(), "note", json!({}));
+        let cursor = NodeCursor::after(&node);
+        assert_eq!(NodeCursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(NodeCursor::decode(&node.id.to_string()).is_err());
+    }
+
+    #[tokio::test]
+    async fn delete_removes_node_for_tenant_only() {
+        let repo = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+        let other = Uuid::new_v4();
+
+        let node = KnowledgeNode::new(tenant, "note", json!({"title": "gone"}));
+        repo.upsert(tenant, node.clone()).await.unwrap();
+
+        assert!(repo.delete(other, node.id).await.unwrap().is_none());
+        let removed = repo.delete(tenant, node.id).await.unwrap();
+        assert_eq!(removed.map(|n| n.id), Some(node.id));
+        assert!(repo.get(tenant, node.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn neighbors_walks_multiple_hops_without_revisiting() {
+        let repo = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+        let [a, b, c, d] = [(); 4].map(|_| Uuid::new_v4());
+        for id in [a, b, c, d] {
+            let mut node = KnowledgeNode::new(tenant, "note", json!({ "name": id }));
+            node.id = id;
+            repo.upsert(tenant, node).await.unwrap();
+        }
+
+        repo.link(tenant, a, b, "next", 1.0, None).await.unwrap();
+        repo.link(tenant, b, c, "next", 1.0, None).await.unwrap();
+        repo.link(tenant, c, a, "next", 1.0, None).await.unwrap();
+        repo.link(tenant, a, d, "other", 1.0, None).await.unwrap();
+        repo.link(tenant, d, c, "next", 1.0, None).await.unwrap();
+
+        let direct = repo
+            .neighbors(tenant, a, &TraversalFilter::default(), 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(direct.len(), 2);
+        assert!(direct.iter().all(|n| n.depth == 1));
+        assert!(direct.iter().all(|n| n.node.kind == "note"));
+
+        let walk = repo
+            .neighbors(tenant, a, &TraversalFilter::default(), 4, 10)
+            .await
+            .unwrap();
+        let reached: Vec<_> = walk.iter().map(|n| (n.node.id, n.depth)).collect();
+        assert_eq!(reached.len(), 4, "c is reached via both b and d");
+        assert!(reached.contains(&(c, 2)));
+        assert!(
+            !reached.iter().any(|(id, _)| *id == a),
+            "cycle back to start"
+        );
+
+        let filtered = repo
+            .neighbors(tenant, a, &only("next"), 4, 10)
+            .await
+            .unwrap();
+        let reached: Vec<_> = filtered.iter().map(|n| (n.node.id, n.depth)).collect();
+        assert_eq!(reached, vec![(b, 1), (c, 2)]);
+
+        let limited = repo
+            .neighbors(tenant, a, &TraversalFilter::default(), 4, 3)
+            .await
+            .unwrap();
+        assert_eq!(limited.len(), 3);
+        assert!(limited.windows(2).all(|w| w[0].depth <= w[1].depth));
+    }
+
+    #[tokio::test]
+    async fn deleting_a_node_cascades_to_its_edges() {
+        let repo = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+        let hub = KnowledgeNode::new(tenant, "note", json!({ "key": "hub" }));
+        let spoke = KnowledgeNode::new(tenant, "note", json!({}));
+        repo.upsert(tenant, hub.clone()).await.unwrap();
+        repo.upsert(tenant, spoke.clone()).await.unwrap();
+        repo.link(tenant, hub.id, spoke.id, "has", 1.0, None)
+            .await
+            .unwrap();
+        repo.link(tenant, spoke.id, hub.id, "of", 1.0, None)
+            .await
+            .unwrap();
+
+        let reached = repo
+            .neighbors(tenant, hub.id, &TraversalFilter::default(), 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(reached[0].node.payload_json, spoke.payload_json);
+
+        repo.delete_by_key(tenant, "hub").await.unwrap().unwrap();
+        assert!(repo
+            .neighbors(tenant, spoke.id, &TraversalFilter::default(), 1, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Re-creating the node must not resurrect the old edges.
+        repo.upsert(tenant, hub.clone()).await.unwrap();
+        assert!(repo
+            .neighbors(tenant, hub.id, &TraversalFilter::default(), 1, 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn tenants_cannot_share_nodes_or_edges() {
+        let repo = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let node = KnowledgeNode::new(tenant, "note", json!({}));
+        let peer = KnowledgeNode::new(tenant, "note", json!({}));
+        repo.upsert(tenant, node.clone()).await.unwrap();
+        repo.upsert(tenant, peer.clone()).await.unwrap();
+
+        assert!(repo.upsert(other, node.clone()).await.is_err());
+        assert!(repo
+            .upsert_batch(
+                other,
+                vec![KnowledgeNode::new(other, "note", json!({})), peer.clone()]
+            )
+            .await
+            .is_err());
+        assert!(repo
+            .query_by_kind(other, "note", 10, None, true)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(repo
+            .link(other, node.id, peer.id, "rel", 1.0, None)
+            .await
+            .is_err());
+        repo.link(tenant, node.id, peer.id, "rel", 1.0, None)
+            .await
+            .unwrap();
+        assert!(repo
+            .neighbors(other, node.id, &TraversalFilter::default(), 1, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Once deleted, the id is free again.
+        repo.delete(tenant, node.id).await.unwrap();
+        repo.upsert(other, node).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn shortest_path_prefers_strong_links() {
+        let repo = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+        let [a, b, c, d] = [(); 4].map(|_| Uuid::new_v4());
+        for id in [a, b, c, d] {
+            let mut node = KnowledgeNode::new(tenant, "note", json!({}));
+            node.id = id;
+            repo.upsert(tenant, node).await.unwrap();
+        }
+
+        // Three strong links (cost 0.25 each) beat one weak link (cost 1); the zero-weight edge
+        // back to `a` is never followed.
+        repo.link(tenant, a, d, "cites", 1.0, None).await.unwrap();
+        repo.link(tenant, a, b, "cites", 4.0, None).await.unwrap();
+        repo.link(tenant, b, c, "cites", 4.0, None).await.unwrap();
+        repo.link(tenant, c, d, "mentions", 4.0, None)
+            .await
+            .unwrap();
+        repo.link(tenant, b, a, "cites", 0.0, None).await.unwrap();
+
+        let path = repo
+            .shortest_path(tenant, a, d, 4, &TraversalFilter::default())
+            .await
+            .unwrap()
+            .unwrap();
+        let ids: Vec<_> = path.nodes.iter().map(|node| node.id).collect();
+        assert_eq!(ids, vec![a, b, c, d]);
+        assert_eq!(path.edges.len(), 3);
+        assert!(path
+            .edges
+            .iter()
+            .zip(&ids)
+            .all(|(edge, id)| edge.src == *id));
+        assert_eq!(path.cost, 0.75);
+
+        let short = repo
+            .shortest_path(tenant, a, d, 2, &TraversalFilter::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(short.edges.len(), 1);
+        assert_eq!(short.cost, 1.0);
+
+        let cited = repo
+            .shortest_path(tenant, a, d, 4, &only("cites"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cited.nodes.len(), 2);
+
+        let itself = repo
+            .shortest_path(tenant, a, a, 4, &TraversalFilter::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((itself.nodes.len(), itself.edges.len()), (1, 0));
+
+        assert!(repo
+            .shortest_path(tenant, d, a, 4, &TraversalFilter::default())
+            .await
+            .unwrap()
+            .is_none());
+        assert!(repo
+            .shortest_path(Uuid::new_v4(), a, d, 4, &TraversalFilter::default())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn link_batch_links_all_or_nothing() {
+        let repo = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+        let src = KnowledgeNode::new(tenant, "note", json!({}));
+        let dst = KnowledgeNode::new(tenant, "note", json!({}));
+        repo.upsert_batch(tenant, vec![src.clone(), dst.clone()])
+            .await
+            .unwrap();
+        let link = |dst: Uuid, weight| EdgeLink {
+            src: src.id,
+            dst,
+            rel: "cites".into(),
+            weight,
+            props: None,
+        };
+
+        let linked = repo
+            .link_batch(tenant, vec![link(dst.id, 1.0), link(dst.id, 2.0)])
+            .await
+            .unwrap();
+        assert!(matches!(linked[0].outcome, UpsertOutcome::Created));
+        assert!(matches!(linked[1].outcome, UpsertOutcome::Updated));
+        assert_eq!(linked[0].id, linked[1].id);
+
+        assert!(repo
+            .link_batch(tenant, vec![link(dst.id, 3.0), link(Uuid::new_v4(), 1.0)])
+            .await
+            .is_err());
+        let edge = repo.get_edge(tenant, linked[0].id).await.unwrap().unwrap();
+        assert_eq!(edge.weight, 2.0);
+    }
+
+    #[tokio::test]
+    async fn link_upserts_and_edges_can_be_updated_and_removed() {
+        let repo = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+        let src = KnowledgeNode::new(tenant, "note", json!({}));
+        let dst = KnowledgeNode::new(tenant, "note", json!({}));
+        repo.upsert(tenant, src.clone()).await.unwrap();
+        repo.upsert(tenant, dst.clone()).await.unwrap();
+
+        let first = repo
+            .link(tenant, src.id, dst.id, "cites", 1.0, None)
+            .await
+            .unwrap();
+        assert!(matches!(first.outcome, UpsertOutcome::Created));
+        let again = repo
+            .link(
+                tenant,
+                src.id,
+                dst.id,
+                "cites",
+                0.4,
+                Some(json!({"page": 2})),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(again.outcome, UpsertOutcome::Updated));
+        assert_eq!(again.id, first.id);
+        let other_rel = repo
+            .link(tenant, src.id, dst.id, "mentions", 1.0, None)
+            .await
+            .unwrap();
+        assert_ne!(other_rel.id, first.id);
+
+        let edge = repo.get_edge(tenant, first.id).await.unwrap().unwrap();
+        assert_eq!((edge.weight, edge.props), (0.4, Some(json!({"page": 2}))));
+
+        let update = EdgeUpdate {
+            weight: Some(3.0),
+            ..Default::default()
+        };
+        let updated = repo
+            .update_edge(tenant, first.id, update.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.weight, 3.0);
+        assert_eq!(updated.props, Some(json!({"page": 2})));
+        assert!(repo
+            .update_edge(Uuid::new_v4(), first.id, update)
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(repo
+            .unlink(Uuid::new_v4(), first.id)
+            .await
+            .unwrap()
+            .is_none());
+        let removed = repo.unlink(tenant, first.id).await.unwrap();
+        assert_eq!(removed.map(|edge| edge.id), Some(first.id));
+        assert!(repo.get_edge(tenant, first.id).await.unwrap().is_none());
+        assert_eq!(
+            repo.neighbors(tenant, src.id, &TraversalFilter::default(), 1, 10)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn traversal_follows_the_requested_direction() {
+        let repo = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+        let [paper, capsule, review] = [(); 3].map(|_| Uuid::new_v4());
+        for id in [paper, capsule, review] {
+            let mut node = KnowledgeNode::new(tenant, "note", json!({}));
+            node.id = id;
+            repo.upsert(tenant, node).await.unwrap();
+        }
+        repo.link(tenant, paper, capsule, "cites", 1.0, None)
+            .await
+            .unwrap();
+        repo.link(tenant, review, paper, "cites", 1.0, None)
+            .await
+            .unwrap();
+
+        let direction = |direction| TraversalFilter {
+            rel: Some("cites".into()),
+            direction,
+            as_of: None,
+        };
+        let reached = |neighbors: Vec<crate::repository::Neighbor>| -> Vec<_> {
+            neighbors.iter().map(|n| (n.node.id, n.depth)).collect()
+        };
+
+        let citing = repo
+            .neighbors(tenant, capsule, &direction(Direction::Incoming), 4, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            citing[0].edge.src, paper,
+            "edges keep their stored direction"
+        );
+        assert_eq!(reached(citing), vec![(paper, 1), (review, 2)]);
+
+        let outgoing = repo
+            .neighbors(tenant, capsule, &direction(Direction::Outgoing), 4, 10)
+            .await
+            .unwrap();
+        assert!(outgoing.is_empty());
+
+        let both = repo
+            .neighbors(tenant, paper, &direction(Direction::Both), 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(both.len(), 2);
+
+        let path = repo
+            .shortest_path(tenant, capsule, review, 4, &direction(Direction::Incoming))
+            .await
+            .unwrap()
+            .unwrap();
+        let ids: Vec<_> = path.nodes.iter().map(|node| node.id).collect();
+        assert_eq!(ids, vec![capsule, paper, review]);
+        assert!(repo
+            .shortest_path(tenant, capsule, review, 4, &direction(Direction::Outgoing))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn expired_edges_stay_queryable_as_of_earlier_times() {
+        let repo = InMemoryGraphStore::new();
         let tenant = Uuid::new_v4();
+        let [a, b] = [(); 2].map(|_| Uuid::new_v4());
+        for id in [a, b] {
+            let mut node = KnowledgeNode::new(tenant, "note", json!({}));
+            node.id = id;
+            repo.upsert(tenant, node).await.unwrap();
+        }
+        let first = repo.link(tenant, a, b, "cites", 1.0, None).await.unwrap();
+        let linked = repo.get_edge(tenant, first.id).await.unwrap().unwrap();
+        assert_eq!(linked.valid_from, linked.created_at);
+        assert!(linked.valid_to.is_none());
+
+        let before = linked.valid_from;
+        let end = before + Duration::seconds(10);
+        let expired = repo
+            .expire_edge(tenant, first.id, end)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(expired.valid_to, Some(end));
+        let again = repo
+            .expire_edge(tenant, first.id, end + Duration::seconds(5))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(again.valid_to, Some(end), "expiry keeps the first end");
+        assert!(repo
+            .expire_edge(Uuid::new_v4(), first.id, end)
+            .await
+            .unwrap()
+            .is_none());
+
+        let as_of = |at| TraversalFilter {
+            as_of: Some(at),
+            ..Default::default()
+        };
+        let during = repo
+            .neighbors(tenant, a, &as_of(end - Duration::seconds(1)), 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(during.len(), 1);
+        assert!(repo
+            .neighbors(tenant, a, &as_of(end), 1, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(repo
+            .neighbors(tenant, a, &as_of(before - Duration::seconds(1)), 1, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            repo.list_edges(tenant, None, Some(before), 10)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Linking again after expiry starts a new edge and leaves the old one as history.
+        let relinked = repo.link(tenant, a, b, "cites", 2.0, None).await.unwrap();
+        assert!(matches!(relinked.outcome, UpsertOutcome::Created));
+        assert_ne!(relinked.id, first.id);
+        let current = repo
+            .edges_among(tenant, &[a, b], None, Some(end), 10)
+            .await
+            .unwrap();
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0].id, relinked.id);
+        let backdated = repo
+            .update_edge(
+                tenant,
+                relinked.id,
+                EdgeUpdate {
+                    valid_from: Some(before - Duration::seconds(60)),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(backdated.valid_from, before - Duration::seconds(60));
+        assert_eq!(
+            repo.edges_among(tenant, &[a, b], None, Some(before), 10)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
 
+    fn embedding(node_id: Uuid, model: &str, vec: Vec<f32>) -> NodeEmbedding {
+        NodeEmbedding {
+            node_id,
+            tenant_id: Uuid::nil(),
+            model: model.into(),
+            dim: vec.len() as i32,
+            vec,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn embeddings_are_kept_and_searched_per_model() {
+        let graph = Arc::new(InMemoryGraphStore::new());
+        let repo = InMemoryEmbeddingRepository::new(graph.clone());
+        let tenant = Uuid::new_v4();
         let mut ids = Vec::new();
-        for title in ["a", "b", "c"] {
-            let mut node = KnowledgeNode::new(tenant, "note", json!({"title": title}));
-            node.id = Uuid::new_v4();
+        for _ in 0..2 {
+            let node = KnowledgeNode::new(tenant, "note", json!({}));
             ids.push(node.id);
-            repo.upsert(tenant, node).await.unwrap();
+            graph.upsert(tenant, node).await.unwrap();
         }
+        let (a, b) = (ids[0], ids[1]);
+        for stored in [
+            embedding(a, "small", vec![1.0, 0.0]),
+            embedding(a, "large", vec![0.0, 0.0, 1.0]),
+            embedding(b, "small", vec![0.0, 1.0]),
+            embedding(b, "small", vec![3.0, 4.0]),
+        ] {
+            repo.upsert_embedding(tenant, stored).await.unwrap();
+        }
+
+        let stored = repo.get_embeddings(tenant, a).await.unwrap();
+        let models: Vec<&str> = stored.iter().map(|e| e.model.as_str()).collect();
+        assert_eq!(models, vec!["large", "small"]);
+        assert_eq!(stored[0].tenant_id, tenant);
+        assert_eq!(repo.get_embeddings(tenant, b).await.unwrap().len(), 1);
+
+        // Cosine ignores magnitude: b's replaced vector points closer to the query than a's.
+        let nearest = repo
+            .search_by_model(tenant, "small", &[0.5, 1.0], DistanceMetric::Cosine, 2)
+            .await
+            .unwrap();
+        let ranked: Vec<Uuid> = nearest.iter().map(|hit| hit.embedding.node_id).collect();
+        assert_eq!(ranked, vec![b, a]);
+        let nearest = repo
+            .search_by_model(tenant, "large", &[0.0, 0.0, 2.0], DistanceMetric::Cosine, 5)
+            .await
+            .unwrap();
+        assert_eq!(nearest.len(), 1);
+        assert!((nearest[0].score - 1.0).abs() < 1e-6);
+        assert!(repo
+            .search_by_model(tenant, "small", &[1.0, 0.0, 0.0], DistanceMetric::Cosine, 5)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(repo
+            .search_by_model(
+                Uuid::new_v4(),
+                "small",
+                &[1.0, 0.0],
+                DistanceMetric::Cosine,
+                5
+            )
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Euclidean distance does not: a sits closer to the short query vector.
+        let nearest = repo
+            .search_by_model(tenant, "small", &[0.5, 1.0], DistanceMetric::L2, 2)
+            .await
+            .unwrap();
+        let ranked: Vec<Uuid> = nearest.iter().map(|hit| hit.embedding.node_id).collect();
+        assert_eq!(ranked, vec![a, b]);
+        assert_eq!(nearest[0].distance_metric, DistanceMetric::L2);
+        assert!((nearest[0].score - 1.0 / (1.0 + 1.25f64.sqrt())).abs() < 1e-6);
+
+        let mut mismatched = embedding(a, "small", vec![1.0, 0.0]);
+        mismatched.dim = 3;
+        assert!(repo.upsert_embedding(tenant, mismatched).await.is_err());
+    }
 
-        let first_page = repo.query_by_kind(tenant, "note", 2, None).await.unwrap();
-        assert_eq!(first_page.len(), 2);
+    #[tokio::test]
+    async fn embeddings_belong_to_the_tenants_nodes() {
+        let graph = Arc::new(InMemoryGraphStore::new());
+        let repo = InMemoryEmbeddingRepository::new(graph.clone());
+        let (tenant, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let node = KnowledgeNode::new(tenant, "note", json!({}));
+        graph.upsert(tenant, node.clone()).await.unwrap();
+
+        let err = repo
+            .upsert_embedding(tenant, embedding(Uuid::new_v4(), "small", vec![1.0]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+        assert!(repo
+            .upsert_embedding(other, embedding(node.id, "small", vec![1.0]))
+            .await
+            .is_err());
+        assert!(repo
+            .upsert_embedding(tenant, embedding(node.id, "small", Vec::new()))
+            .await
+            .is_err());
 
-        let cursor = first_page.last().unwrap().id;
-        let second_page = repo
-            .query_by_kind(tenant, "note", 2, Some(cursor))
+        repo.upsert_embedding(tenant, embedding(node.id, "small", vec![1.0]))
             .await
             .unwrap();
-        assert_eq!(second_page.len(), 1);
+        assert!(graph.delete(tenant, node.id).await.unwrap().is_some());
+        assert!(repo
+            .get_embeddings(tenant, node.id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(repo
+            .search_by_model(tenant, "small", &[1.0], DistanceMetric::Cosine, 5)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn similarity_search_compares_only_filtered_vectors() {
+        let store = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+        let mut note = KnowledgeNode::new(tenant, "note", json!({}));
+        note.vector = Some(vec![1.0, 0.0]);
+        let mut legacy = KnowledgeNode::new(tenant, "legacy", json!({}));
+        legacy.vector = Some(vec![1.0, 0.0, 0.0]);
+        store.upsert(tenant, note.clone()).await.unwrap();
+        // Written past the length check, like rows stored before it existed.
+        store
+            .inner
+            .write()
+            .await
+            .store(tenant, legacy, chrono::Utc::now());
+
+        let search = |kind: &str| {
+            let filter = SimilarityFilter {
+                kind: Some(kind.into()),
+                ..Default::default()
+            };
+            let store = &store;
+            async move {
+                store
+                    .search_similar(tenant, &[1.0, 0.0], DistanceMetric::Cosine, &filter, 10)
+                    .await
+            }
+        };
+        let hits = search("note").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].node.id, note.id);
+        let err = search("legacy").await.unwrap_err();
+        assert!(err.downcast_ref::<DimensionMismatch>().is_some());
+    }
+
+    #[tokio::test]
+    async fn similarity_filters_match_like_postgres_containment() {
+        let store = InMemoryGraphStore::new();
+        let tenant = Uuid::new_v4();
+        let mut tagged = KnowledgeNode::new(
+            tenant,
+            "capsule",
+            json!({"answer": {"score": 1, "tags": ["a", "b"]}, "lang": "en"}),
+        );
+        tagged.vector = Some(vec![1.0, 0.0]);
+        tagged.policy = Some(json!({"phi": true, "compliance_tags": ["gdpr", "hipaa"]}));
+        let mut listed = KnowledgeNode::new(tenant, "capsule", json!(["x", "y"]));
+        listed.vector = Some(vec![0.0, 1.0]);
+        store.upsert(tenant, tagged.clone()).await.unwrap();
+        store.upsert(tenant, listed.clone()).await.unwrap();
+
+        let matching = |filter: SimilarityFilter| {
+            let store = &store;
+            async move {
+                store
+                    .search_similar(tenant, &[1.0, 1.0], DistanceMetric::Cosine, &filter, 10)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|hit| hit.node.id)
+                    .collect::<Vec<_>>()
+            }
+        };
+        let payload = |value: serde_json::Value| SimilarityFilter {
+            payload: Some(value),
+            ..Default::default()
+        };
+
+        // Numbers compare by value and arrays match any subset in any order.
+        assert_eq!(
+            matching(payload(json!({"answer": {"score": 1.0, "tags": ["b"]}}))).await,
+            vec![tagged.id]
+        );
+        assert!(matching(payload(json!({"answer": {"tags": ["c"]}})))
+            .await
+            .is_empty());
+        // A top-level array payload contains its scalar elements.
+        assert_eq!(matching(payload(json!("y"))).await, vec![listed.id]);
+
+        // Nodes without a policy flag count as not having it.
+        let phi = |phi: bool| SimilarityFilter {
+            phi: Some(phi),
+            ..Default::default()
+        };
+        assert_eq!(matching(phi(false)).await, vec![listed.id]);
+        assert_eq!(matching(phi(true)).await, vec![tagged.id]);
+        let tags = |tags: &[&str]| SimilarityFilter {
+            compliance_tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        };
+        assert_eq!(matching(tags(&["hipaa", "gdpr"])).await, vec![tagged.id]);
+        assert!(matching(tags(&["gdpr", "sox"])).await.is_empty());
     }
 }
@@ -2,6 +2,7 @@
 // Repository abstractions provide persistence interfaces decoupled from storage backends.
 
 pub mod in_memory;
+pub mod pattern;
 pub mod postgres;
 
 use anyhow::Result;
@@ -9,11 +10,15 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::str::FromStr;
 use std::sync::Arc;
+use thiserror::Error;
 use uuid::Uuid;
 
 use crate::domain::node::KnowledgeNode;
 
+use self::pattern::{PatternPlan, PatternRow};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KnowledgeEdge {
     pub id: Uuid,
@@ -24,6 +29,357 @@ pub struct KnowledgeEdge {
     pub weight: f32,
     pub props: Option<Value>,
     pub created_at: DateTime<Utc>,
+    /// Start of the interval in which the relationship holds; the creation time unless backdated.
+    pub valid_from: DateTime<Utc>,
+    /// End of that interval, set when the edge is expired; `None` while it still holds.
+    pub valid_to: Option<DateTime<Utc>>,
+}
+
+impl KnowledgeEdge {
+    /// Whether the relationship held at `at`: `valid_from <= at < valid_to`.
+    pub fn is_valid_at(&self, at: DateTime<Utc>) -> bool {
+        self.valid_from <= at && self.valid_to.map(|end| at < end).unwrap_or(true)
+    }
+}
+
+/// An edge to create or update through `EdgeRepository::link_batch`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeLink {
+    pub src: Uuid,
+    pub dst: Uuid,
+    pub rel: String,
+    pub weight: f32,
+    pub props: Option<Value>,
+}
+
+/// Result of `EdgeRepository::link`: the id of the edge between the two nodes and whether it was
+/// created or an existing edge with the same relation was updated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedEdge {
+    pub id: Uuid,
+    pub outcome: UpsertOutcome,
+}
+
+/// Changes applied by `EdgeRepository::update_edge`; fields left as `None` keep their value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EdgeUpdate {
+    pub weight: Option<f32>,
+    pub props: Option<Value>,
+    /// Backdates (or postpones) the start of the edge's validity interval.
+    pub valid_from: Option<DateTime<Utc>>,
+}
+
+/// Cluster labels computed for a node by the community detection job. Labels are the smallest
+/// node id in the cluster, so they stay stable across runs while membership does not change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeCommunity {
+    pub node_id: Uuid,
+    /// Weakly connected component.
+    pub component: Uuid,
+    /// Community within the component.
+    pub community: Uuid,
+}
+
+/// Position after the last node of a `query_by_kind` page. Pages are ordered by `created_at`, then
+/// `id`, so nodes created while a client pages through appear at the end instead of shifting
+/// earlier pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl NodeCursor {
+    /// The cursor that continues after `node`.
+    pub fn after(node: &KnowledgeNode) -> Self {
+        Self {
+            created_at: node.created_at,
+            id: node.id,
+        }
+    }
+
+    /// Serializes the cursor into the token handed to API clients.
+    pub fn encode(&self) -> String {
+        format!(
+            "{}_{}",
+            self.created_at
+                .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+            self.id.simple()
+        )
+    }
+
+    pub fn decode(token: &str) -> Result<Self> {
+        let (created_at, id) = token
+            .split_once('_')
+            .ok_or_else(|| anyhow::anyhow!("malformed cursor"))?;
+        Ok(Self {
+            created_at: DateTime::parse_from_rfc3339(created_at)?.with_timezone(&Utc),
+            id: id.parse()?,
+        })
+    }
+}
+
+/// Which way traversals follow edges.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// From `src` to `dst`.
+    #[default]
+    Outgoing,
+    /// From `dst` back to `src`, e.g. to find what cites a node.
+    Incoming,
+    /// Either way.
+    Both,
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Outgoing => "outgoing",
+            Self::Incoming => "incoming",
+            Self::Both => "both",
+        }
+    }
+}
+
+/// How vector similarity is measured. Each maps to a pgvector distance operator, where smaller
+/// means more similar.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceMetric {
+    #[default]
+    Cosine,
+    /// Euclidean distance.
+    L2,
+    /// Larger inner products rank first; suits vectors normalized by the embedding model.
+    #[serde(alias = "dot")]
+    InnerProduct,
+}
+
+impl DistanceMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cosine => "cosine",
+            Self::L2 => "l2",
+            Self::InnerProduct => "inner_product",
+        }
+    }
+
+    /// The pgvector operator; `<#>` yields the negated inner product so ascending order works.
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Cosine => "<=>",
+            Self::L2 => "<->",
+            Self::InnerProduct => "<#>",
+        }
+    }
+
+    /// Turns a distance into the score reported to callers, where higher is more similar under
+    /// every metric: cosine similarity for `Cosine`, the inner product for `InnerProduct`, and
+    /// `1 / (1 + distance)` for `L2`, which is 1 for an exact match.
+    pub fn score(&self, distance: f64) -> f64 {
+        match self {
+            Self::Cosine => 1.0 - distance,
+            Self::L2 => 1.0 / (1.0 + distance),
+            Self::InnerProduct => -distance,
+        }
+    }
+
+    /// Computes what the pgvector operator would for two vectors of equal length. Cosine distance
+    /// is NaN when either vector is all zeros, and NaN sorts last, as in Postgres.
+    pub fn distance(&self, a: &[f32], b: &[f32]) -> f64 {
+        let dot: f64 = a
+            .iter()
+            .zip(b)
+            .map(|(x, y)| f64::from(*x) * f64::from(*y))
+            .sum();
+        match self {
+            Self::Cosine => {
+                let norm = |v: &[f32]| v.iter().map(|x| f64::from(*x).powi(2)).sum::<f64>().sqrt();
+                let norms = norm(a) * norm(b);
+                if norms == 0.0 {
+                    f64::NAN
+                } else {
+                    1.0 - dot / norms
+                }
+            }
+            Self::L2 => a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| (f64::from(*x) - f64::from(*y)).powi(2))
+                .sum::<f64>()
+                .sqrt(),
+            Self::InnerProduct => -dot,
+        }
+    }
+}
+
+impl FromStr for DistanceMetric {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "cosine" => Ok(Self::Cosine),
+            "l2" => Ok(Self::L2),
+            "inner_product" | "dot" => Ok(Self::InnerProduct),
+            other => anyhow::bail!("unknown distance metric '{other}'"),
+        }
+    }
+}
+
+/// A similarity search hit.
+#[derive(Debug, Clone)]
+pub struct ScoredNode {
+    pub node: KnowledgeNode,
+    /// See [`DistanceMetric::score`] for what the value means under each metric.
+    pub score: f64,
+    pub distance_metric: DistanceMetric,
+}
+
+/// An embedding search hit.
+#[derive(Debug, Clone)]
+pub struct ScoredEmbedding {
+    pub embedding: NodeEmbedding,
+    /// See [`DistanceMetric::score`] for what the value means under each metric.
+    pub score: f64,
+    pub distance_metric: DistanceMetric,
+}
+
+/// A full-text search hit. Scores only compare hits of one search: Postgres reports `ts_rank_cd`
+/// and the in-memory store counts matched terms.
+#[derive(Debug, Clone)]
+pub struct TextMatch {
+    pub node: KnowledgeNode,
+    pub score: f64,
+}
+
+/// A vector whose length differs from the stored vectors it would be compared with or sit beside.
+#[derive(Debug, Error)]
+#[error("vector has {actual} values but stored vectors have {expected}")]
+pub struct DimensionMismatch {
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl DimensionMismatch {
+    /// Checks that the vectors of `nodes` all have `expected` values, or, when nothing is stored
+    /// yet, the length of the first of them.
+    pub(crate) fn check(mut expected: Option<usize>, nodes: &[KnowledgeNode]) -> Result<()> {
+        for vector in nodes.iter().filter_map(|node| node.vector.as_ref()) {
+            match expected {
+                Some(expected) if expected != vector.len() => {
+                    return Err(DimensionMismatch {
+                        expected,
+                        actual: vector.len(),
+                    }
+                    .into())
+                }
+                Some(_) => {}
+                None => expected = Some(vector.len()),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Narrows a similarity search to matching nodes before they are ranked. Unset fields match every
+/// node.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimilarityFilter {
+    pub kind: Option<String>,
+    /// Matches `policy.phi`; nodes without the flag count as `false`.
+    pub phi: Option<bool>,
+    /// Matches `policy.pii`; nodes without the flag count as `false`.
+    pub pii: Option<bool>,
+    /// Matches `policy.region` exactly.
+    pub region: Option<String>,
+    /// `policy.compliance_tags` must include every one of these.
+    pub compliance_tags: Vec<String>,
+    /// JSON the payload must contain, in the sense of the Postgres `@>` operator.
+    pub payload: Option<Value>,
+}
+
+impl SimilarityFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Evaluates the filter the way the Postgres search does.
+    pub fn matches(&self, node: &KnowledgeNode) -> bool {
+        let policy = |key: &str| node.policy.as_ref().and_then(|policy| policy.get(key));
+        let flag = |key: &str| policy(key) == Some(&Value::Bool(true));
+
+        self.kind.as_ref().is_none_or(|kind| &node.kind == kind)
+            && self.phi.is_none_or(|phi| flag("phi") == phi)
+            && self.pii.is_none_or(|pii| flag("pii") == pii)
+            && self
+                .region
+                .as_ref()
+                .is_none_or(|region| policy("region").and_then(Value::as_str) == Some(region))
+            && (self.compliance_tags.is_empty()
+                || policy("compliance_tags")
+                    .and_then(Value::as_array)
+                    .is_some_and(|tags| {
+                        self.compliance_tags
+                            .iter()
+                            .all(|tag| tags.iter().any(|t| t.as_str() == Some(tag)))
+                    }))
+            && self.payload.as_ref().is_none_or(|payload| {
+                match (&node.payload_json, payload) {
+                    // At the top level an array may contain a bare scalar.
+                    (Value::Array(items), scalar) if !scalar.is_array() && !scalar.is_object() => {
+                        items.iter().any(|item| json_contains(item, scalar))
+                    }
+                    (value, payload) => json_contains(value, payload),
+                }
+            })
+    }
+}
+
+/// JSONB containment: objects contain the keys of `b` with contained values, arrays contain
+/// every element of `b` somewhere, and scalars must be equal.
+fn json_contains(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => b
+            .iter()
+            .all(|(key, value)| a.get(key).is_some_and(|inner| json_contains(inner, value))),
+        (Value::Array(a), Value::Array(b)) => b
+            .iter()
+            .all(|value| a.iter().any(|inner| json_contains(inner, value))),
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (a, b) => a == b,
+    }
+}
+
+/// Restricts which edges a traversal may follow.
+#[derive(Debug, Clone, Default)]
+pub struct TraversalFilter {
+    /// Only follow edges with this relation.
+    pub rel: Option<String>,
+    pub direction: Direction,
+    /// Only follow edges valid at this time; `None` means now, which hides expired edges.
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+/// A node reached during traversal together with the edge that led to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Neighbor {
+    pub node: KnowledgeNode,
+    pub edge: KnowledgeEdge,
+    /// Hops from the start node; direct neighbours are at depth 1.
+    pub depth: u8,
+}
+
+/// The cheapest route between two nodes. `nodes` starts at the origin and ends at the target;
+/// `edges[i]` connects `nodes[i]` and `nodes[i + 1]`, pointing backwards when the search
+/// followed it against its direction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphPath {
+    pub nodes: Vec<KnowledgeNode>,
+    pub edges: Vec<KnowledgeEdge>,
+    /// Sum of the edge costs along the path, each `1 / weight`.
+    pub cost: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +392,24 @@ pub struct NodeEmbedding {
     pub created_at: DateTime<Utc>,
 }
 
+impl NodeEmbedding {
+    /// Checks that `dim` is positive and matches the vector, as the `node_embeddings` table
+    /// requires.
+    pub(crate) fn check_dim(&self) -> Result<()> {
+        if self.dim <= 0 {
+            anyhow::bail!("embedding dim must be positive, got {}", self.dim);
+        }
+        if self.dim as usize != self.vec.len() {
+            anyhow::bail!(
+                "embedding dim {} does not match its {} values",
+                self.dim,
+                self.vec.len()
+            );
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OutboxKind {
@@ -72,34 +446,82 @@ pub enum UpsertOutcome {
 
 #[async_trait]
 pub trait NodeRepository: Send + Sync {
+    /// Creates or replaces a node. All vectors a store holds have one length (the column size in
+    /// Postgres, the first stored vector of the tenant in memory); a node whose vector differs is
+    /// rejected with [`DimensionMismatch`].
     async fn upsert(&self, tenant: Uuid, node: KnowledgeNode) -> Result<UpsertOutcome>;
 
+    /// Upserts several nodes in one write, returning outcomes in input order. Either every node
+    /// is written or none are. Like `upsert`, fails with [`DimensionMismatch`] when a vector's
+    /// length differs from the stored ones.
+    async fn upsert_batch(
+        &self,
+        tenant: Uuid,
+        nodes: Vec<KnowledgeNode>,
+    ) -> Result<Vec<UpsertOutcome>>;
+
     async fn get(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeNode>>;
 
+    /// Fetches several nodes at once, in the order of `ids`. Missing ids are skipped.
+    async fn get_many(&self, tenant: Uuid, ids: &[Uuid]) -> Result<Vec<KnowledgeNode>>;
+
     async fn get_by_key(&self, tenant: Uuid, key: &str) -> Result<Option<KnowledgeNode>>;
 
+    async fn delete(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeNode>>;
+
     async fn delete_by_key(&self, tenant: Uuid, key: &str) -> Result<Option<KnowledgeNode>>;
 
+    /// Lists nodes of one kind, oldest first with ties broken by id, starting after `cursor`.
+    /// Without `include_vectors` the nodes come back with `vector` unset, which keeps large pages
+    /// small.
     async fn query_by_kind(
         &self,
         tenant: Uuid,
         kind: &str,
         limit: usize,
-        cursor: Option<Uuid>,
+        cursor: Option<NodeCursor>,
+        include_vectors: bool,
     ) -> Result<Vec<KnowledgeNode>>;
 
+    /// The `limit` nodes matching `filter` whose vectors are closest to `vector` under `metric`,
+    /// best first. Fails with [`DimensionMismatch`] when stored vectors have a different length.
     async fn search_similar(
         &self,
         tenant: Uuid,
         vector: &[f32],
+        metric: DistanceMetric,
+        filter: &SimilarityFilter,
         limit: usize,
-    ) -> Result<Vec<KnowledgeNode>>;
+    ) -> Result<Vec<ScoredNode>>;
+
+    /// The `limit` nodes matching `filter` whose payload strings best match the words of `query`,
+    /// best first. Every word must appear; the query is parsed like a web search, so quoted
+    /// phrases, `or` and `-word` work on Postgres.
+    async fn search_text(
+        &self,
+        tenant: Uuid,
+        query: &str,
+        filter: &SimilarityFilter,
+        limit: usize,
+    ) -> Result<Vec<TextMatch>>;
+
+    /// Replaces the tenant's stored cluster labels with `assignments`, recording which
+    /// `algorithm` produced them. Nodes left out of `assignments` end up without labels.
+    async fn replace_communities(
+        &self,
+        tenant: Uuid,
+        algorithm: &str,
+        assignments: &[NodeCommunity],
+    ) -> Result<()>;
 
     async fn health_check(&self) -> Result<()>;
 }
 
 #[async_trait]
 pub trait EdgeRepository: Send + Sync {
+    /// Creates the `rel` edge from `src` to `dst`, or updates the weight and props of the existing
+    /// one. There is at most one unexpired edge per `(tenant, src, dst, rel)`, so the id is stable
+    /// until the edge is expired; linking again after that starts a new edge.
     async fn link(
         &self,
         tenant: Uuid,
@@ -108,23 +530,108 @@ pub trait EdgeRepository: Send + Sync {
         rel: &str,
         weight: f32,
         props: Option<Value>,
-    ) -> Result<()>;
+    ) -> Result<LinkedEdge>;
 
-    async fn neighbors(
+    /// Links several edges in one write, returning results in input order. Either every edge is
+    /// linked or none are; repeated relations update the edge linked earlier in the batch.
+    async fn link_batch(&self, tenant: Uuid, links: Vec<EdgeLink>) -> Result<Vec<LinkedEdge>>;
+
+    /// Removes an edge, returning it when it existed.
+    async fn unlink(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeEdge>>;
+
+    async fn get_edge(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeEdge>>;
+
+    /// Applies `update` to an edge and returns the result, or `None` when the edge is missing.
+    async fn update_edge(
         &self,
         tenant: Uuid,
         id: Uuid,
+        update: EdgeUpdate,
+    ) -> Result<Option<KnowledgeEdge>>;
+
+    /// Ends the edge's validity at `at`, keeping it for as-of queries, and returns it. Expiring
+    /// an expired edge leaves its end unchanged. Returns `None` when the edge is missing.
+    async fn expire_edge(
+        &self,
+        tenant: Uuid,
+        id: Uuid,
+        at: DateTime<Utc>,
+    ) -> Result<Option<KnowledgeEdge>>;
+
+    /// Returns the tenant's edges valid at `as_of` (now when `None`), oldest first, up to
+    /// `limit`; for algorithms that run over the whole graph in process. When `rel` is set, only
+    /// edges with that relation are returned.
+    async fn list_edges(
+        &self,
+        tenant: Uuid,
         rel: Option<&str>,
+        as_of: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeEdge>>;
+
+    /// Returns the edges valid at `as_of` (now when `None`) whose endpoints are both in `ids`,
+    /// oldest first, up to `limit`. When `rel` is set, only edges with that relation are returned.
+    async fn edges_among(
+        &self,
+        tenant: Uuid,
+        ids: &[Uuid],
+        rel: Option<&str>,
+        as_of: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeEdge>>;
+
+    /// Walks edges breadth-first for up to `hops` hops, following only the edges `filter`
+    /// allows at every hop. Each node is reported once, at the depth where it is first reached,
+    /// with every edge that reaches it from the previous depth; edges keep their stored
+    /// direction even when followed backwards. The start node is never reported and cycles are
+    /// not followed. Results are ordered by depth, then newest edge first.
+    async fn neighbors(
+        &self,
+        tenant: Uuid,
+        id: Uuid,
+        filter: &TraversalFilter,
         hops: u8,
         limit: usize,
-    ) -> Result<Vec<KnowledgeNode>>;
+    ) -> Result<Vec<Neighbor>>;
+
+    /// Finds the cheapest path from `from` to `to`, using at most `max_hops` edges that `filter`
+    /// allows. As in activation and community detection a higher weight means a stronger link,
+    /// so each edge costs `1 / weight`; edges without a positive weight are never followed. Ties
+    /// go to the path with fewer hops. Returns `None` when `to` is unreachable.
+    async fn shortest_path(
+        &self,
+        tenant: Uuid,
+        from: Uuid,
+        to: Uuid,
+        max_hops: u8,
+        filter: &TraversalFilter,
+    ) -> Result<Option<GraphPath>>;
+
+    /// Runs a compiled pattern query and returns one row per distinct match of the returned
+    /// columns, ordered by their ids, up to `plan.limit`.
+    async fn match_pattern(&self, tenant: Uuid, plan: &PatternPlan) -> Result<Vec<PatternRow>>;
 }
 
 #[async_trait]
 pub trait EmbeddingRepository: Send + Sync {
+    /// Stores the node's embedding for `embedding.model`, replacing any earlier one for that
+    /// model. Fails when the tenant has no such node, or `dim` is not positive or does not match
+    /// the vector length.
     async fn upsert_embedding(&self, tenant: Uuid, embedding: NodeEmbedding) -> Result<()>;
 
+    /// All of a node's embeddings, ordered by model.
     async fn get_embeddings(&self, tenant: Uuid, node_id: Uuid) -> Result<Vec<NodeEmbedding>>;
+
+    /// The `k` embeddings of `model` closest to `vector` under `metric`, nearest first. Only
+    /// embeddings with the same dimension as `vector` are compared.
+    async fn search_by_model(
+        &self,
+        tenant: Uuid,
+        model: &str,
+        vector: &[f32],
+        metric: DistanceMetric,
+        k: usize,
+    ) -> Result<Vec<ScoredEmbedding>>;
 }
 
 #[async_trait]
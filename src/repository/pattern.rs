@@ -0,0 +1,201 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// Compiled graph pattern queries: the plan each backend executes and the rows it returns.
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use super::{Direction, KnowledgeEdge, NodeCommunity};
+use crate::domain::node::KnowledgeNode;
+
+/// Longest variable-length relationship a pattern may contain.
+pub const MAX_PATTERN_HOPS: u8 = 4;
+
+/// A validated path pattern `(n0)-[h0]-(n1)-[h1]-...(nk)` with its filters. `nodes` always has
+/// one more entry than `hops`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PatternPlan {
+    pub nodes: Vec<NodeStep>,
+    pub hops: Vec<HopStep>,
+    /// All must hold for a match to count.
+    pub predicates: Vec<Predicate>,
+    /// Returned columns, in order. Rows are distinct and sorted by these columns' ids.
+    pub returns: Vec<Slot>,
+    pub limit: usize,
+    /// Only edges valid at this time match; `None` means now.
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeStep {
+    pub kind: Option<String>,
+    /// Earlier node position bound to the same variable, which this one must equal.
+    pub same_as: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HopStep {
+    /// Accepted relations; empty accepts every relation.
+    pub rels: Vec<String>,
+    pub direction: Direction,
+    pub min: u8,
+    pub max: u8,
+}
+
+impl HopStep {
+    /// One outgoing edge of any relation, as written `-->`.
+    pub fn single() -> Self {
+        Self {
+            rels: Vec::new(),
+            direction: Direction::Outgoing,
+            min: 1,
+            max: 1,
+        }
+    }
+
+    /// Single-edge hops bind their edge; variable-length hops only relate their endpoints, and a
+    /// node counts as reached when some path without repeated nodes has an accepted length.
+    pub fn is_single(&self) -> bool {
+        self.min == 1 && self.max == 1
+    }
+
+    pub fn accepts(&self, rel: &str) -> bool {
+        self.rels.is_empty() || self.rels.iter().any(|r| r == rel)
+    }
+}
+
+/// A node position or single-edge hop position in the pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slot {
+    Node(usize),
+    Edge(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Predicate {
+    pub slot: Slot,
+    pub field: Field,
+    pub op: CompareOp,
+    pub value: Value,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Field {
+    Id,
+    /// Node kind.
+    Kind,
+    /// Path into a node's payload.
+    Payload(Vec<String>),
+    /// Node's connected component label from the last community detection run.
+    Component,
+    /// Node's community label from the last community detection run.
+    Community,
+    /// Edge relation.
+    Rel,
+    Weight,
+    /// Path into an edge's props.
+    Props(Vec<String>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "<>",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+
+    /// Compares a stored value with a literal. Numbers compare numerically, ordering operators
+    /// only hold between numbers, and a missing value never matches.
+    pub fn holds(&self, actual: Option<&Value>, expected: &Value) -> bool {
+        let Some(actual) = actual else {
+            return false;
+        };
+        let ordering = match (actual.as_f64(), expected.as_f64()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        };
+        match self {
+            CompareOp::Eq => ordering.map(|o| o.is_eq()).unwrap_or(actual == expected),
+            CompareOp::Ne => ordering.map(|o| o.is_ne()).unwrap_or(actual != expected),
+            CompareOp::Lt => ordering.is_some_and(|o| o.is_lt()),
+            CompareOp::Le => ordering.is_some_and(|o| o.is_le()),
+            CompareOp::Gt => ordering.is_some_and(|o| o.is_gt()),
+            CompareOp::Ge => ordering.is_some_and(|o| o.is_ge()),
+        }
+    }
+}
+
+/// One returned column of a pattern match.
+#[derive(Clone, Debug)]
+pub enum PatternValue {
+    Node(KnowledgeNode),
+    Edge(KnowledgeEdge),
+}
+
+/// The value `field` refers to on a node, if it has one. Cluster labels are stored apart from
+/// the node and are looked up in `community`.
+pub fn node_field(
+    node: &KnowledgeNode,
+    community: Option<&NodeCommunity>,
+    field: &Field,
+) -> Option<Value> {
+    match field {
+        Field::Id => Some(Value::String(node.id.to_string())),
+        Field::Kind => Some(Value::String(node.kind.clone())),
+        Field::Payload(path) => lookup(&node.payload_json, path),
+        Field::Component => community.map(|c| Value::String(c.component.to_string())),
+        Field::Community => community.map(|c| Value::String(c.community.to_string())),
+        _ => None,
+    }
+}
+
+/// The value `field` refers to on an edge, if it has one.
+pub fn edge_field(edge: &KnowledgeEdge, field: &Field) -> Option<Value> {
+    match field {
+        Field::Id => Some(Value::String(edge.id.to_string())),
+        Field::Rel => Some(Value::String(edge.rel.clone())),
+        Field::Weight => Some(f64::from(edge.weight).into()),
+        Field::Props(path) => edge.props.as_ref().and_then(|props| lookup(props, path)),
+        _ => None,
+    }
+}
+
+pub type PatternRow = Vec<PatternValue>;
+
+fn lookup(value: &Value, path: &[String]) -> Option<Value> {
+    path.iter()
+        .try_fold(value, |current, key| current.get(key))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::CompareOp;
+
+    #[test]
+    fn comparisons_follow_the_sql_semantics() {
+        assert!(CompareOp::Eq.holds(Some(&json!(1)), &json!(1.0)));
+        assert!(CompareOp::Eq.holds(Some(&json!("EU")), &json!("EU")));
+        assert!(CompareOp::Ne.holds(Some(&json!("EU")), &json!(1)));
+        assert!(CompareOp::Lt.holds(Some(&json!(1)), &json!(2.5)));
+        assert!(!CompareOp::Lt.holds(Some(&json!("a")), &json!("b")));
+        assert!(!CompareOp::Ne.holds(None, &json!("EU")));
+        assert!(CompareOp::Eq.holds(Some(&json!(null)), &json!(null)));
+    }
+}
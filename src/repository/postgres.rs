@@ -1,27 +1,103 @@
 // SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
 // PostgreSQL-backed implementation of the NodeRepository trait.
 
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde_json::Value;
-use sqlx::postgres::{PgPoolOptions, PgRow};
-use sqlx::{PgPool, Postgres, Row};
+use sqlx::postgres::{PgArguments, PgPoolOptions, PgRow};
+use sqlx::query::Query;
+use sqlx::{Connection, PgConnection, PgPool, Postgres, Row};
 use uuid::Uuid;
 
 use crate::domain::node::KnowledgeNode;
 
+use super::pattern::{CompareOp, Field, PatternPlan, PatternRow, PatternValue, Slot};
 use super::{
-    ArtifactCache, BusSubscription, EdgeRepository, EmbeddingRepository, EventBus, NodeEmbedding,
-    NodeRepository, OutboxEvent, OutboxKind, OutboxRepository, UpsertOutcome,
+    ArtifactCache, BusSubscription, DimensionMismatch, Direction, DistanceMetric, EdgeLink,
+    EdgeRepository, EdgeUpdate, EmbeddingRepository, EventBus, GraphPath, KnowledgeEdge,
+    LinkedEdge, Neighbor, NodeCommunity, NodeCursor, NodeEmbedding, NodeRepository, OutboxEvent,
+    OutboxKind, OutboxRepository, ScoredEmbedding, ScoredNode, SimilarityFilter, TextMatch,
+    TraversalFilter, UpsertOutcome,
 };
 
+/// Query prelude that exposes the edges a traversal may take from the frontier nodes in `$2` as
+/// `steps`, one row per usable direction, with `from_id`/`to_id` oriented the way the walk moves.
+/// Expects the tenant in `$1`, the optional relation in `$3`, the direction name in `$4` and the
+/// optional as-of time in `$5`; only edges valid at that time (now when null) are steps.
+const TRAVERSAL_STEPS: &str = r#"
+    steps AS (
+        SELECT e.*, e.src AS from_id, e.dst AS to_id
+        FROM knowledge_edges e
+        WHERE e.tenant_id = $1
+          AND e.src = ANY($2::uuid[])
+          AND $4 IN ('outgoing', 'both')
+          AND ($3::text IS NULL OR e.rel = $3)
+          AND e.valid_from <= COALESCE($5::timestamptz, now())
+          AND (e.valid_to IS NULL OR e.valid_to > COALESCE($5::timestamptz, now()))
+        UNION ALL
+        SELECT e.*, e.dst AS from_id, e.src AS to_id
+        FROM knowledge_edges e
+        WHERE e.tenant_id = $1
+          AND e.dst = ANY($2::uuid[])
+          AND $4 IN ('incoming', 'both')
+          AND ($3::text IS NULL OR e.rel = $3)
+          AND e.valid_from <= COALESCE($5::timestamptz, now())
+          AND (e.valid_to IS NULL OR e.valid_to > COALESCE($5::timestamptz, now()))
+    )"#;
+
+/// Recursive-query counterpart of [`TRAVERSAL_STEPS`]: the same `steps` without the frontier
+/// restriction, so a recursive walk can join them at every depth. Takes the same parameters; the
+/// walk seeds itself from the start nodes in `$2`.
+const WALK_STEPS: &str = r#"
+    steps AS NOT MATERIALIZED (
+        SELECT e.*, e.src AS from_id, e.dst AS to_id
+        FROM knowledge_edges e
+        WHERE e.tenant_id = $1
+          AND $4 IN ('outgoing', 'both')
+          AND ($3::text IS NULL OR e.rel = $3)
+          AND e.valid_from <= COALESCE($5::timestamptz, now())
+          AND (e.valid_to IS NULL OR e.valid_to > COALESCE($5::timestamptz, now()))
+        UNION ALL
+        SELECT e.*, e.dst AS from_id, e.src AS to_id
+        FROM knowledge_edges e
+        WHERE e.tenant_id = $1
+          AND $4 IN ('incoming', 'both')
+          AND ($3::text IS NULL OR e.rel = $3)
+          AND e.valid_from <= COALESCE($5::timestamptz, now())
+          AND (e.valid_to IS NULL OR e.valid_to > COALESCE($5::timestamptz, now()))
+    )"#;
+
+/// Candidates an HNSW scan keeps per search by default (pgvector's `hnsw.ef_search`); searches
+/// for more results raise it to their limit, up to the maximum pgvector accepts.
+const HNSW_EF_SEARCH: usize = 40;
+const HNSW_MAX_EF_SEARCH: usize = 1000;
+
+/// Binds the parameters [`TRAVERSAL_STEPS`] and [`WALK_STEPS`] expect, walking from `frontier`.
+fn bind_steps<'q>(
+    query: Query<'q, Postgres, PgArguments>,
+    tenant: Uuid,
+    frontier: &[Uuid],
+    filter: &'q TraversalFilter,
+) -> Query<'q, Postgres, PgArguments> {
+    query
+        .bind(tenant)
+        .bind(frontier.to_vec())
+        .bind(filter.rel.as_deref())
+        .bind(filter.direction.as_str())
+        .bind(filter.as_of)
+}
+
 fn map_node_row(row: &PgRow) -> Result<KnowledgeNode> {
     let id: Uuid = row.try_get("id")?;
     let tenant_id: Uuid = row.try_get("tenant_id")?;
     let kind: String = row.try_get("kind")?;
     let payload_json: Value = row.try_get("payload_json")?;
     let provenance: Option<Value> = row.try_get("provenance")?;
+    let vector: Option<Vec<f32>> = row.try_get("vector")?;
     let policy: Option<Value> = row.try_get("policy")?;
     let created_at: DateTime<Utc> = row.try_get("created_at")?;
     let updated_at: DateTime<Utc> = row.try_get("updated_at")?;
@@ -31,16 +107,109 @@ fn map_node_row(row: &PgRow) -> Result<KnowledgeNode> {
         tenant_id,
         kind,
         payload_json,
-        vector: None,
+        vector,
         provenance,
         policy,
         created_at,
         updated_at,
     })
 }
+
+/// Maps edge columns selected with an `edge_` prefix, as produced by joins against nodes.
+fn map_edge_row(row: &PgRow) -> Result<KnowledgeEdge> {
+    Ok(KnowledgeEdge {
+        id: row.try_get("edge_id")?,
+        tenant_id: row.try_get("edge_tenant_id")?,
+        src: row.try_get("edge_src")?,
+        dst: row.try_get("edge_dst")?,
+        rel: row.try_get("edge_rel")?,
+        weight: row.try_get("edge_weight")?,
+        props: row.try_get("edge_props")?,
+        created_at: row.try_get("edge_created_at")?,
+        valid_from: row.try_get("edge_valid_from")?,
+        valid_to: row.try_get("edge_valid_to")?,
+    })
+}
+
+async fn upsert_node_on(conn: &mut PgConnection, node: &KnowledgeNode) -> Result<UpsertOutcome> {
+    let row = sqlx::query(
+        r#"
+        INSERT INTO knowledge_nodes (id, tenant_id, kind, payload_json, vector, provenance, policy)
+        VALUES ($1, $2, $3, $4, $5::real[]::vector, $6, $7)
+        ON CONFLICT (id) DO UPDATE SET
+            kind = EXCLUDED.kind,
+            payload_json = EXCLUDED.payload_json,
+            vector = EXCLUDED.vector,
+            provenance = EXCLUDED.provenance,
+            policy = EXCLUDED.policy,
+            updated_at = now()
+        RETURNING (xmax = 0) AS created
+    "#,
+    )
+    .bind(node.id)
+    .bind(node.tenant_id)
+    .bind(&node.kind)
+    .bind(node.payload_json.clone())
+    .bind(node.vector.clone())
+    .bind(node.provenance.clone())
+    .bind(node.policy.clone())
+    .fetch_one(conn)
+    .await
+    .context("failed to upsert knowledge node")?;
+
+    let created: bool = row.try_get("created")?;
+    Ok(if created {
+        UpsertOutcome::Created
+    } else {
+        UpsertOutcome::Updated
+    })
+}
+
+async fn link_edge_on(
+    conn: &mut PgConnection,
+    tenant: Uuid,
+    link: &EdgeLink,
+) -> Result<LinkedEdge> {
+    let row = sqlx::query(
+        r#"
+        INSERT INTO knowledge_edges (tenant_id, src, dst, rel, weight, props)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (tenant_id, src, dst, rel) WHERE valid_to IS NULL DO UPDATE SET
+            weight = EXCLUDED.weight,
+            props = EXCLUDED.props
+        RETURNING id, (xmax = 0) AS created
+    "#,
+    )
+    .bind(tenant)
+    .bind(link.src)
+    .bind(link.dst)
+    .bind(&link.rel)
+    .bind(link.weight)
+    .bind(link.props.clone())
+    .fetch_one(conn)
+    .await
+    .context("failed to upsert edge")?;
+
+    let id: Uuid = row.try_get("id")?;
+    let created: bool = row.try_get("created")?;
+    Ok(LinkedEdge {
+        id,
+        outcome: if created {
+            UpsertOutcome::Created
+        } else {
+            UpsertOutcome::Updated
+        },
+    })
+}
+
 #[derive(Clone)]
 pub struct PostgresNodeRepository {
     pool: PgPool,
+    /// Size of the `vector` column, read from the catalog on first use; `None` if unconstrained.
+    vector_dims: Arc<OnceLock<Option<usize>>>,
+    /// Whether the installed pgvector (0.8 or later) supports iterative index scans, read from
+    /// the catalog on first use.
+    iterative_scan: Arc<OnceLock<bool>>,
 }
 
 impl PostgresNodeRepository {
@@ -51,17 +220,65 @@ impl PostgresNodeRepository {
             .await
             .context("failed to connect to postgres")?;
 
-        Ok(Self { pool })
+        Ok(Self::from_pool(pool))
     }
 
-    #[cfg(test)]
+    /// Wraps an existing pool, e.g. one opened with `connect_lazy` so startup does not wait for
+    /// the database.
     pub fn from_pool(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            vector_dims: Arc::default(),
+            iterative_scan: Arc::default(),
+        }
     }
 
     pub fn pool(&self) -> PgPool {
         self.pool.clone()
     }
+
+    /// Rejects vectors the column would refuse with an opaque error, so callers can report them.
+    async fn check_dims(&self, conn: &mut PgConnection, nodes: &[KnowledgeNode]) -> Result<()> {
+        let dims = match self.vector_dims.get() {
+            Some(dims) => *dims,
+            None => {
+                // pgvector stores the declared dimension as the type modifier.
+                let typmod: i32 = sqlx::query_scalar(
+                    r#"
+                    SELECT atttypmod
+                    FROM pg_attribute
+                    WHERE attrelid = 'knowledge_nodes'::regclass
+                      AND attname = 'vector'
+                "#,
+                )
+                .fetch_one(&mut *conn)
+                .await
+                .context("failed to read vector column size")?;
+                let dims = usize::try_from(typmod).ok().filter(|dims| *dims > 0);
+                *self.vector_dims.get_or_init(|| dims)
+            }
+        };
+        DimensionMismatch::check(dims, nodes)
+    }
+
+    async fn supports_iterative_scan(&self, conn: &mut PgConnection) -> Result<bool> {
+        if let Some(supported) = self.iterative_scan.get() {
+            return Ok(*supported);
+        }
+        let supported: bool = sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(
+                (SELECT string_to_array(extversion, '.')::int[] >= ARRAY[0, 8]
+                 FROM pg_extension
+                 WHERE extname = 'vector'),
+                false)
+        "#,
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .context("failed to read pgvector version")?;
+        Ok(*self.iterative_scan.get_or_init(|| supported))
+    }
 }
 
 #[async_trait]
@@ -70,39 +287,91 @@ impl NodeRepository for PostgresNodeRepository {
         node.tenant_id = tenant;
         let mut conn = self.pool.acquire().await.context("acquire connection")?;
         set_tenant_on_conn(&mut conn, tenant).await?;
+        self.check_dims(&mut conn, std::slice::from_ref(&node))
+            .await?;
 
-        let provenance = node.provenance.clone();
-        let policy = node.policy.clone();
+        upsert_node_on(&mut conn, &node).await
+    }
 
-        let row = sqlx::query(
+    async fn upsert_batch(
+        &self,
+        tenant: Uuid,
+        nodes: Vec<KnowledgeNode>,
+    ) -> Result<Vec<UpsertOutcome>> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+        self.check_dims(&mut conn, &nodes).await?;
+
+        // One row per id: the last occurrence wins, as if the nodes were upserted in order, and a
+        // single statement cannot update the same row twice.
+        let mut rows: HashMap<Uuid, &KnowledgeNode> = HashMap::with_capacity(nodes.len());
+        for node in &nodes {
+            rows.insert(node.id, node);
+        }
+        let rows: Vec<&KnowledgeNode> = rows.into_values().collect();
+        // Vectors travel in pgvector's text form; a real[][] parameter would need equal lengths.
+        let vectors: Vec<Option<String>> = rows
+            .iter()
+            .map(|node| {
+                node.vector.as_ref().map(|vector| {
+                    let values: Vec<String> = vector.iter().map(f32::to_string).collect();
+                    format!("[{}]", values.join(","))
+                })
+            })
+            .collect();
+
+        let written = sqlx::query(
             r#"
-            INSERT INTO knowledge_nodes (id, tenant_id, kind, payload_json, provenance, policy)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO knowledge_nodes (id, tenant_id, kind, payload_json, vector, provenance, policy)
+            SELECT id, $1, kind, payload_json, vector::vector, provenance, policy
+            FROM UNNEST($2::uuid[], $3::text[], $4::jsonb[], $5::text[], $6::jsonb[], $7::jsonb[])
+                AS batch(id, kind, payload_json, vector, provenance, policy)
             ON CONFLICT (id) DO UPDATE SET
                 kind = EXCLUDED.kind,
                 payload_json = EXCLUDED.payload_json,
+                vector = EXCLUDED.vector,
                 provenance = EXCLUDED.provenance,
                 policy = EXCLUDED.policy,
                 updated_at = now()
-            RETURNING (xmax = 0) AS created
+            RETURNING id, (xmax = 0) AS created
         "#,
         )
-        .bind(node.id)
-        .bind(node.tenant_id)
-        .bind(&node.kind)
-        .bind(node.payload_json.clone())
-        .bind(provenance)
-        .bind(policy)
-        .fetch_one(&mut *conn)
+        .bind(tenant)
+        .bind(rows.iter().map(|node| node.id).collect::<Vec<_>>())
+        .bind(rows.iter().map(|node| node.kind.clone()).collect::<Vec<_>>())
+        .bind(
+            rows.iter()
+                .map(|node| node.payload_json.clone())
+                .collect::<Vec<_>>(),
+        )
+        .bind(vectors)
+        .bind(
+            rows.iter()
+                .map(|node| node.provenance.clone())
+                .collect::<Vec<_>>(),
+        )
+        .bind(rows.iter().map(|node| node.policy.clone()).collect::<Vec<_>>())
+        .fetch_all(&mut *conn)
         .await
-        .context("failed to upsert knowledge node")?;
+        .context("failed to upsert node batch")?;
 
-        let created: bool = row.try_get("created")?;
-        Ok(if created {
-            UpsertOutcome::Created
-        } else {
-            UpsertOutcome::Updated
-        })
+        let mut created = HashSet::new();
+        for row in written {
+            if row.try_get::<bool, _>("created")? {
+                created.insert(row.try_get::<Uuid, _>("id")?);
+            }
+        }
+        // Later occurrences of an id update the row its first occurrence created.
+        Ok(nodes
+            .iter()
+            .map(|node| {
+                if created.remove(&node.id) {
+                    UpsertOutcome::Created
+                } else {
+                    UpsertOutcome::Updated
+                }
+            })
+            .collect())
     }
 
     async fn get(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeNode>> {
@@ -111,7 +380,7 @@ impl NodeRepository for PostgresNodeRepository {
 
         let row = sqlx::query(
             r#"
-            SELECT id, tenant_id, kind, payload_json, provenance, policy, created_at, updated_at
+            SELECT id, tenant_id, kind, payload_json, vector::real[] AS vector, provenance, policy, created_at, updated_at
             FROM knowledge_nodes
             WHERE tenant_id = $1
               AND id = $2
@@ -129,13 +398,35 @@ impl NodeRepository for PostgresNodeRepository {
         }
     }
 
+    async fn get_many(&self, tenant: Uuid, ids: &[Uuid]) -> Result<Vec<KnowledgeNode>> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT n.id, n.tenant_id, n.kind, n.payload_json, n.vector::real[] AS vector, n.provenance, n.policy, n.created_at, n.updated_at
+            FROM unnest($2::uuid[]) WITH ORDINALITY AS p(id, ord)
+            JOIN knowledge_nodes n ON n.id = p.id
+            WHERE n.tenant_id = $1
+            ORDER BY p.ord
+        "#,
+        )
+        .bind(tenant)
+        .bind(ids)
+        .fetch_all(&mut *conn)
+        .await
+        .context("failed to fetch knowledge nodes")?;
+
+        rows.iter().map(map_node_row).collect()
+    }
+
     async fn get_by_key(&self, tenant: Uuid, key: &str) -> Result<Option<KnowledgeNode>> {
         let mut conn = self.pool.acquire().await.context("acquire connection")?;
         set_tenant_on_conn(&mut conn, tenant).await?;
 
         let row = sqlx::query(
             r#"
-            SELECT id, tenant_id, kind, payload_json, provenance, policy, created_at, updated_at
+            SELECT id, tenant_id, kind, payload_json, vector::real[] AS vector, provenance, policy, created_at, updated_at
             FROM knowledge_nodes
             WHERE tenant_id = $1
               AND payload_json ->> 'key' = $2
@@ -155,6 +446,30 @@ impl NodeRepository for PostgresNodeRepository {
         }
     }
 
+    async fn delete(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeNode>> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        let row = sqlx::query(
+            r#"
+            DELETE FROM knowledge_nodes
+            WHERE tenant_id = $1
+              AND id = $2
+            RETURNING id, tenant_id, kind, payload_json, vector::real[] AS vector, provenance, policy, created_at, updated_at
+        "#,
+        )
+        .bind(tenant)
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("failed to delete knowledge node")?;
+
+        match row {
+            Some(row) => Ok(Some(map_node_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
     async fn delete_by_key(&self, tenant: Uuid, key: &str) -> Result<Option<KnowledgeNode>> {
         let mut conn = self.pool.acquire().await.context("acquire connection")?;
         set_tenant_on_conn(&mut conn, tenant).await?;
@@ -164,7 +479,7 @@ impl NodeRepository for PostgresNodeRepository {
             DELETE FROM knowledge_nodes
             WHERE tenant_id = $1
               AND payload_json ->> 'key' = $2
-            RETURNING id, tenant_id, kind, payload_json, provenance, policy, created_at, updated_at
+            RETURNING id, tenant_id, kind, payload_json, vector::real[] AS vector, provenance, policy, created_at, updated_at
         "#,
         )
         .bind(tenant)
@@ -184,26 +499,31 @@ impl NodeRepository for PostgresNodeRepository {
         tenant: Uuid,
         kind: &str,
         limit: usize,
-        cursor: Option<Uuid>,
+        cursor: Option<NodeCursor>,
+        include_vectors: bool,
     ) -> Result<Vec<KnowledgeNode>> {
         let mut conn = self.pool.acquire().await.context("acquire connection")?;
         set_tenant_on_conn(&mut conn, tenant).await?;
 
         let rows = sqlx::query(
             r#"
-            SELECT id, tenant_id, kind, payload_json, provenance, policy, created_at, updated_at
+            SELECT id, tenant_id, kind, payload_json,
+                   CASE WHEN $5 THEN vector::real[] END AS vector,
+                   provenance, policy, created_at, updated_at
             FROM knowledge_nodes
             WHERE tenant_id = $1
               AND kind = $2
-              AND ($3::uuid IS NULL OR id > $3)
-            ORDER BY created_at DESC, id ASC
+              AND ($3::timestamptz IS NULL OR (created_at, id) > ($3, $6))
+            ORDER BY created_at, id
             LIMIT $4
         "#,
         )
         .bind(tenant)
         .bind(kind)
-        .bind(cursor)
+        .bind(cursor.map(|cursor| cursor.created_at))
         .bind(limit as i64)
+        .bind(include_vectors)
+        .bind(cursor.map(|cursor| cursor.id))
         .fetch_all(&mut *conn)
         .await
         .context("failed to query knowledge nodes by kind")?;
@@ -219,10 +539,207 @@ impl NodeRepository for PostgresNodeRepository {
         &self,
         tenant: Uuid,
         vector: &[f32],
+        metric: DistanceMetric,
+        filter: &SimilarityFilter,
         limit: usize,
-    ) -> Result<Vec<KnowledgeNode>> {
-        let _ = (tenant, vector, limit); // vector search pending pgvector integration.
-        Ok(Vec::new())
+    ) -> Result<Vec<ScoredNode>> {
+        if vector.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        // The column has a fixed dimension, so any one stored vector gives the size of all.
+        let stored: Option<i32> = sqlx::query_scalar(
+            r#"
+            SELECT vector_dims(vector)
+            FROM knowledge_nodes
+            WHERE tenant_id = $1
+              AND vector IS NOT NULL
+            LIMIT 1
+        "#,
+        )
+        .bind(tenant)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("failed to read stored vector dimension")?;
+        if let Some(expected) = stored.map(|dims| dims as usize) {
+            if expected != vector.len() {
+                return Err(DimensionMismatch {
+                    expected,
+                    actual: vector.len(),
+                }
+                .into());
+            }
+        }
+
+        let predicates = filter_predicates(filter);
+
+        // The vector indexes are shared by every tenant, so `tenant_id` only filters the
+        // neighbours an index scan returns. pgvector 0.8 can keep scanning until enough of them
+        // pass; older versions would silently return fewer rows, so there every search ranks the
+        // tenant's nodes exactly.
+        let iterative_scan = self.supports_iterative_scan(&mut conn).await?;
+        let mut tx = conn.begin().await.context("begin similarity search")?;
+
+        // Unfiltered searches order by the operator expression so the planner can use the vector
+        // index built for the metric's operator class. That index would apply a filter only to
+        // the neighbours it had already found, so filtered searches first collect the matching
+        // nodes, through the kind and payload indexes, and rank all of them.
+        let source = if predicates.is_empty() && iterative_scan {
+            sqlx::query(
+                "SELECT set_config('hnsw.iterative_scan', 'strict_order', true),
+                        set_config('hnsw.ef_search', $1, true)",
+            )
+            .bind(limit.clamp(HNSW_EF_SEARCH, HNSW_MAX_EF_SEARCH).to_string())
+            .execute(&mut *tx)
+            .await
+            .context("failed to configure vector index scan")?;
+            "knowledge_nodes".to_string()
+        } else {
+            let mut predicates = predicates;
+            predicates.insert(0, "vector IS NOT NULL");
+            format!(
+                r#"(
+                SELECT *
+                FROM knowledge_nodes
+                WHERE tenant_id = $1
+                  AND {}
+                OFFSET 0
+            ) AS candidates"#,
+                predicates.join("\n                  AND ")
+            )
+        };
+        // Ordering by anything besides the operator expression would keep the planner off the
+        // vector index, so equal distances are broken by id once the rows are back.
+        let sql = format!(
+            r#"
+            SELECT id, tenant_id, kind, payload_json, vector::real[] AS vector, provenance, policy, created_at, updated_at,
+                   vector {op} $2::real[]::vector AS distance
+            FROM {source}
+            WHERE tenant_id = $1
+              AND vector IS NOT NULL
+            ORDER BY vector {op} $2::real[]::vector
+            LIMIT $3
+        "#,
+            op = metric.as_sql()
+        );
+        let query = sqlx::query(&sql)
+            .bind(tenant)
+            .bind(vector)
+            .bind(limit as i64);
+        let rows = bind_filter(query, filter)
+            .fetch_all(&mut *tx)
+            .await
+            .context("failed to search similar knowledge nodes")?;
+        tx.commit().await.context("commit similarity search")?;
+
+        let mut ranked = rows
+            .iter()
+            .map(|row| {
+                let distance: Option<f64> = row.try_get("distance")?;
+                Ok((distance.unwrap_or(f64::NAN), map_node_row(row)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.id.cmp(&b.1.id)));
+        Ok(ranked
+            .into_iter()
+            .map(|(distance, node)| ScoredNode {
+                node,
+                score: metric.score(distance),
+                distance_metric: metric,
+            })
+            .collect())
+    }
+
+    async fn search_text(
+        &self,
+        tenant: Uuid,
+        query: &str,
+        filter: &SimilarityFilter,
+        limit: usize,
+    ) -> Result<Vec<TextMatch>> {
+        if query.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        let predicates: String = filter_predicates(filter)
+            .iter()
+            .map(|predicate| format!("\n              AND {predicate}"))
+            .collect();
+        let sql = format!(
+            r#"
+            SELECT id, tenant_id, kind, payload_json, vector::real[] AS vector, provenance, policy, created_at, updated_at,
+                   ts_rank_cd(search_tsv, query)::float8 AS rank
+            FROM knowledge_nodes, websearch_to_tsquery('english', $2) AS query
+            WHERE tenant_id = $1
+              AND search_tsv @@ query{predicates}
+            ORDER BY rank DESC, id
+            LIMIT $3
+        "#
+        );
+        let query = sqlx::query(&sql)
+            .bind(tenant)
+            .bind(query)
+            .bind(limit as i64);
+        let rows = bind_filter(query, filter)
+            .fetch_all(&mut *conn)
+            .await
+            .context("failed to search knowledge node text")?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(TextMatch {
+                    node: map_node_row(row)?,
+                    score: row.try_get("rank")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn replace_communities(
+        &self,
+        tenant: Uuid,
+        algorithm: &str,
+        assignments: &[NodeCommunity],
+    ) -> Result<()> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        let node_ids: Vec<Uuid> = assignments.iter().map(|a| a.node_id).collect();
+        let components: Vec<Uuid> = assignments.iter().map(|a| a.component).collect();
+        let communities: Vec<Uuid> = assignments.iter().map(|a| a.community).collect();
+
+        let mut tx = conn.begin().await.context("begin community update")?;
+        sqlx::query("DELETE FROM node_communities WHERE tenant_id = $1")
+            .bind(tenant)
+            .execute(&mut *tx)
+            .await
+            .context("failed to clear communities")?;
+        // Joining on the nodes skips assignments for nodes deleted since the job read the graph.
+        sqlx::query(
+            r#"
+            INSERT INTO node_communities (node_id, tenant_id, component, community, algorithm)
+            SELECT a.node_id, $1, a.component, a.community, $5
+            FROM unnest($2::uuid[], $3::uuid[], $4::uuid[]) AS a(node_id, component, community)
+            JOIN knowledge_nodes n ON n.id = a.node_id AND n.tenant_id = $1
+        "#,
+        )
+        .bind(tenant)
+        .bind(&node_ids)
+        .bind(&components)
+        .bind(&communities)
+        .bind(algorithm)
+        .execute(&mut *tx)
+        .await
+        .context("failed to store communities")?;
+        tx.commit().await.context("commit community update")?;
+
+        Ok(())
     }
 
     async fn health_check(&self) -> Result<()> {
@@ -234,6 +751,45 @@ impl NodeRepository for PostgresNodeRepository {
     }
 }
 
+/// Predicates for the fields of `filter` that are set, over `knowledge_nodes` columns. They refer
+/// to the filter values as `$4` to `$9`, which [`bind_filter`] binds after three other parameters.
+fn filter_predicates(filter: &SimilarityFilter) -> Vec<&'static str> {
+    let mut predicates = Vec::new();
+    if filter.kind.is_some() {
+        predicates.push("kind = $4");
+    }
+    if filter.phi.is_some() {
+        predicates.push("COALESCE(policy -> 'phi' = 'true'::jsonb, false) = $5");
+    }
+    if filter.pii.is_some() {
+        predicates.push("COALESCE(policy -> 'pii' = 'true'::jsonb, false) = $6");
+    }
+    if filter.region.is_some() {
+        predicates.push("policy ->> 'region' = $7");
+    }
+    if !filter.compliance_tags.is_empty() {
+        predicates.push("policy -> 'compliance_tags' @> to_jsonb($8::text[])");
+    }
+    if filter.payload.is_some() {
+        predicates.push("payload_json @> $9");
+    }
+    predicates
+}
+
+/// Binds every filter value, set or not, so the placeholders of [`filter_predicates`] line up.
+fn bind_filter<'q>(
+    query: Query<'q, Postgres, PgArguments>,
+    filter: &'q SimilarityFilter,
+) -> Query<'q, Postgres, PgArguments> {
+    query
+        .bind(filter.kind.as_deref())
+        .bind(filter.phi)
+        .bind(filter.pii)
+        .bind(filter.region.as_deref())
+        .bind(&filter.compliance_tags)
+        .bind(&filter.payload)
+}
+
 pub async fn set_tenant_on_conn(
     conn: &mut sqlx::pool::PoolConnection<Postgres>,
     tenant: Uuid,
@@ -246,6 +802,209 @@ pub async fn set_tenant_on_conn(
         .map(|_| ())
 }
 
+/// Parameters of a compiled pattern query, bound after the tenant in `$1`.
+enum PatternArg {
+    Text(String),
+    Texts(Vec<String>),
+    Json(Value),
+    Float(f64),
+    Time(Option<DateTime<Utc>>),
+}
+
+#[derive(Default)]
+struct PatternSql {
+    args: Vec<PatternArg>,
+}
+
+impl PatternSql {
+    /// Registers `arg` and returns its placeholder.
+    fn param(&mut self, arg: PatternArg) -> String {
+        self.args.push(arg);
+        format!("${}", self.args.len() + 1)
+    }
+
+    /// Compiles `plan` into one query selecting the ids of the returned columns as `c0, c1, ...`.
+    /// Single hops join `knowledge_edges` directly. Variable-length hops join a recursive
+    /// `reach{i}` relation of paths without repeated nodes, seeded from every node of the hop's
+    /// left kind, so a reached node may appear at several depths; `DISTINCT` folds those. Edges
+    /// of either kind of hop must be valid at the plan's as-of time.
+    fn compile(mut self, plan: &PatternPlan) -> (String, Vec<PatternArg>) {
+        let at = self.param(PatternArg::Time(plan.as_of));
+        let at = format!("COALESCE({at}::timestamptz, now())");
+        let valid = |alias: &str| {
+            format!(
+                " AND {alias}valid_from <= {at} AND ({alias}valid_to IS NULL OR {alias}valid_to > {at})"
+            )
+        };
+        let mut ctes = Vec::new();
+        let mut from = String::from("knowledge_nodes n0");
+        let mut conditions = vec!["n0.tenant_id = $1".to_string()];
+
+        for (i, step) in plan.nodes.iter().enumerate() {
+            if let Some(kind) = &step.kind {
+                let kind = self.param(PatternArg::Text(kind.clone()));
+                conditions.push(format!("n{i}.kind = {kind}"));
+            }
+            if let Some(first) = step.same_as {
+                conditions.push(format!("n{i}.id = n{first}.id"));
+            }
+        }
+
+        for (i, hop) in plan.hops.iter().enumerate() {
+            let (left, right) = (format!("n{i}"), format!("n{}", i + 1));
+            let rels = (!hop.rels.is_empty())
+                .then(|| self.param(PatternArg::Texts(hop.rels.clone())))
+                .map(|rels| format!(" AND rel = ANY({rels}::text[])"))
+                .unwrap_or_default();
+
+            if hop.is_single() {
+                let rels =
+                    rels.replace("rel =", &format!("e{i}.rel =")) + &valid(&format!("e{i}."));
+                from.push_str(&match hop.direction {
+                    Direction::Both => format!(
+                        "\n JOIN knowledge_edges e{i} ON e{i}.tenant_id = $1 \
+                         AND {left}.id IN (e{i}.src, e{i}.dst){rels}\
+                         \n JOIN knowledge_nodes {right} ON {right}.tenant_id = $1 \
+                         AND ((e{i}.src = {left}.id AND {right}.id = e{i}.dst) \
+                         OR (e{i}.dst = {left}.id AND {right}.id = e{i}.src))"
+                    ),
+                    direction => {
+                        let (near, far) = match direction {
+                            Direction::Incoming => ("dst", "src"),
+                            _ => ("src", "dst"),
+                        };
+                        format!(
+                            "\n JOIN knowledge_edges e{i} ON e{i}.tenant_id = $1 \
+                             AND e{i}.{near} = {left}.id{rels}\
+                             \n JOIN knowledge_nodes {right} ON {right}.tenant_id = $1 \
+                             AND {right}.id = e{i}.{far}"
+                        )
+                    }
+                });
+                continue;
+            }
+
+            let rels = rels + &valid("");
+            let forward = format!(
+                "SELECT src AS from_id, dst AS to_id FROM knowledge_edges \
+                 WHERE tenant_id = $1{rels}"
+            );
+            let backward = format!(
+                "SELECT dst AS from_id, src AS to_id FROM knowledge_edges \
+                 WHERE tenant_id = $1{rels}"
+            );
+            let steps = match hop.direction {
+                Direction::Outgoing => forward,
+                Direction::Incoming => backward,
+                Direction::Both => format!("{forward} UNION ALL {backward}"),
+            };
+            let seeds = match &plan.nodes[i].kind {
+                Some(kind) => format!(" AND kind = {}", self.param(PatternArg::Text(kind.clone()))),
+                None => String::new(),
+            };
+            ctes.push(format!(
+                "reach{i}(origin, node, depth, path) AS (\
+                 \n SELECT id, id, 0, ARRAY[id] FROM knowledge_nodes WHERE tenant_id = $1{seeds}\
+                 \n UNION ALL\
+                 \n SELECT r.origin, s.to_id, r.depth + 1, r.path || s.to_id\
+                 \n FROM reach{i} r JOIN ({steps}) s ON s.from_id = r.node\
+                 \n WHERE r.depth < {max} AND NOT s.to_id = ANY(r.path))",
+                max = hop.max,
+            ));
+            from.push_str(&format!(
+                "\n JOIN reach{i} w{i} ON w{i}.origin = {left}.id AND w{i}.depth >= {min}\
+                 \n JOIN knowledge_nodes {right} ON {right}.tenant_id = $1 AND {right}.id = w{i}.node",
+                min = hop.min,
+            ));
+        }
+
+        for predicate in &plan.predicates {
+            let alias = match predicate.slot {
+                Slot::Node(i) => format!("n{i}"),
+                Slot::Edge(i) => format!("e{i}"),
+            };
+            let op = predicate.op.as_sql();
+            let text = || PatternArg::Text(predicate.value.as_str().unwrap_or_default().into());
+            let float = || PatternArg::Float(predicate.value.as_f64().unwrap_or_default());
+            conditions.push(match &predicate.field {
+                Field::Id => format!("{alias}.id::text {op} {}", self.param(text())),
+                Field::Kind => format!("{alias}.kind {op} {}", self.param(text())),
+                Field::Component | Field::Community => {
+                    let column = match predicate.field {
+                        Field::Component => "component",
+                        _ => "community",
+                    };
+                    format!(
+                        "(SELECT c.{column}::text FROM node_communities c \
+                         WHERE c.node_id = {alias}.id) {op} {}",
+                        self.param(text())
+                    )
+                }
+                Field::Rel => format!("{alias}.rel {op} {}", self.param(text())),
+                Field::Weight => {
+                    format!(
+                        "{alias}.weight::float8 {op} {}::float8",
+                        self.param(float())
+                    )
+                }
+                Field::Payload(path) | Field::Props(path) => {
+                    let column = match predicate.field {
+                        Field::Payload(_) => "payload_json",
+                        _ => "props",
+                    };
+                    let path = self.param(PatternArg::Texts(path.clone()));
+                    let value = format!("{alias}.{column} #> {path}::text[]");
+                    match predicate.op {
+                        CompareOp::Eq | CompareOp::Ne => {
+                            let json = self.param(PatternArg::Json(predicate.value.clone()));
+                            format!("{value} {op} {json}::jsonb")
+                        }
+                        _ => format!(
+                            "CASE WHEN jsonb_typeof({value}) = 'number' \
+                             THEN ({alias}.{column} #>> {path}::text[])::float8 {op} {}::float8 \
+                             ELSE false END",
+                            self.param(float())
+                        ),
+                    }
+                }
+            });
+        }
+
+        let columns: Vec<String> = plan
+            .returns
+            .iter()
+            .enumerate()
+            .map(|(k, slot)| match slot {
+                Slot::Node(i) => format!("n{i}.id AS c{k}"),
+                Slot::Edge(i) => format!("e{i}.id AS c{k}"),
+            })
+            .collect();
+        let order: Vec<String> = (0..columns.len()).map(|k| format!("c{k}")).collect();
+        let with = if ctes.is_empty() {
+            String::new()
+        } else {
+            format!("WITH RECURSIVE {}\n", ctes.join(",\n"))
+        };
+        let sql = format!(
+            "{with}SELECT DISTINCT {}\nFROM {from}\nWHERE {}\nORDER BY {}\nLIMIT {}",
+            columns.join(", "),
+            conditions.join("\n  AND "),
+            order.join(", "),
+            plan.limit,
+        );
+        (sql, self.args)
+    }
+}
+
+/// Cheapest route found so far from the origin of a shortest-path search.
+#[derive(Clone, Default)]
+struct Route {
+    cost: f64,
+    edge_ids: Vec<Uuid>,
+    /// Nodes after the origin, one per edge.
+    hop_nodes: Vec<Uuid>,
+}
+
 #[derive(Clone)]
 pub struct PostgresEdgeRepository {
     pool: PgPool,
@@ -267,93 +1026,632 @@ impl EdgeRepository for PostgresEdgeRepository {
         rel: &str,
         weight: f32,
         props: Option<serde_json::Value>,
-    ) -> Result<()> {
+    ) -> Result<LinkedEdge> {
         let mut conn = self.pool.acquire().await.context("acquire connection")?;
         set_tenant_on_conn(&mut conn, tenant).await?;
 
-        sqlx::query(
+        let link = EdgeLink {
+            src,
+            dst,
+            rel: rel.to_string(),
+            weight,
+            props,
+        };
+        link_edge_on(&mut conn, tenant, &link).await
+    }
+
+    async fn link_batch(&self, tenant: Uuid, links: Vec<EdgeLink>) -> Result<Vec<LinkedEdge>> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        // Edges go one statement at a time so a relation repeated in the batch updates the edge
+        // its first occurrence created, which a multi-row upsert cannot do.
+        let mut tx = conn.begin().await.context("begin edge batch")?;
+        let mut linked = Vec::with_capacity(links.len());
+        for link in &links {
+            linked.push(link_edge_on(&mut tx, tenant, link).await?);
+        }
+        tx.commit().await.context("commit edge batch")?;
+
+        Ok(linked)
+    }
+
+    async fn unlink(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeEdge>> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        let row = sqlx::query(
+            r#"
+            DELETE FROM knowledge_edges
+            WHERE tenant_id = $1
+              AND id = $2
+            RETURNING id AS edge_id, tenant_id AS edge_tenant_id, src AS edge_src, dst AS edge_dst,
+                      rel AS edge_rel, weight AS edge_weight, props AS edge_props,
+                      created_at AS edge_created_at, valid_from AS edge_valid_from,
+                      valid_to AS edge_valid_to
+        "#,
+        )
+        .bind(tenant)
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("failed to delete edge")?;
+
+        row.as_ref().map(map_edge_row).transpose()
+    }
+
+    async fn get_edge(&self, tenant: Uuid, id: Uuid) -> Result<Option<KnowledgeEdge>> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        let row = sqlx::query(
             r#"
-            INSERT INTO knowledge_edges (tenant_id, src, dst, rel, weight, props)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            SELECT id AS edge_id, tenant_id AS edge_tenant_id, src AS edge_src, dst AS edge_dst,
+                   rel AS edge_rel, weight AS edge_weight, props AS edge_props,
+                   created_at AS edge_created_at, valid_from AS edge_valid_from,
+                   valid_to AS edge_valid_to
+            FROM knowledge_edges
+            WHERE tenant_id = $1
+              AND id = $2
         "#,
         )
         .bind(tenant)
-        .bind(src)
-        .bind(dst)
-        .bind(rel)
-        .bind(weight)
-        .bind(props)
-        .execute(&mut *conn)
+        .bind(id)
+        .fetch_optional(&mut *conn)
         .await
-        .context("failed to insert edge")
-        .map(|_| ())
+        .context("failed to fetch edge")?;
+
+        row.as_ref().map(map_edge_row).transpose()
     }
 
-    async fn neighbors(
+    async fn update_edge(
+        &self,
+        tenant: Uuid,
+        id: Uuid,
+        update: EdgeUpdate,
+    ) -> Result<Option<KnowledgeEdge>> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        let row = sqlx::query(
+            r#"
+            UPDATE knowledge_edges SET
+                weight = COALESCE($3, weight),
+                props = COALESCE($4, props),
+                valid_from = COALESCE($5, valid_from)
+            WHERE tenant_id = $1
+              AND id = $2
+            RETURNING id AS edge_id, tenant_id AS edge_tenant_id, src AS edge_src, dst AS edge_dst,
+                      rel AS edge_rel, weight AS edge_weight, props AS edge_props,
+                      created_at AS edge_created_at, valid_from AS edge_valid_from,
+                      valid_to AS edge_valid_to
+        "#,
+        )
+        .bind(tenant)
+        .bind(id)
+        .bind(update.weight)
+        .bind(update.props)
+        .bind(update.valid_from)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("failed to update edge")?;
+
+        row.as_ref().map(map_edge_row).transpose()
+    }
+
+    async fn expire_edge(
         &self,
         tenant: Uuid,
         id: Uuid,
+        at: DateTime<Utc>,
+    ) -> Result<Option<KnowledgeEdge>> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        let row = sqlx::query(
+            r#"
+            UPDATE knowledge_edges SET
+                valid_to = COALESCE(valid_to, $3)
+            WHERE tenant_id = $1
+              AND id = $2
+            RETURNING id AS edge_id, tenant_id AS edge_tenant_id, src AS edge_src, dst AS edge_dst,
+                      rel AS edge_rel, weight AS edge_weight, props AS edge_props,
+                      created_at AS edge_created_at, valid_from AS edge_valid_from,
+                      valid_to AS edge_valid_to
+        "#,
+        )
+        .bind(tenant)
+        .bind(id)
+        .bind(at)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("failed to expire edge")?;
+
+        row.as_ref().map(map_edge_row).transpose()
+    }
+
+    async fn list_edges(
+        &self,
+        tenant: Uuid,
         rel: Option<&str>,
-        hops: u8,
+        as_of: Option<DateTime<Utc>>,
         limit: usize,
-    ) -> Result<Vec<KnowledgeNode>> {
+    ) -> Result<Vec<KnowledgeEdge>> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id AS edge_id, tenant_id AS edge_tenant_id, src AS edge_src, dst AS edge_dst,
+                   rel AS edge_rel, weight AS edge_weight, props AS edge_props,
+                   created_at AS edge_created_at, valid_from AS edge_valid_from,
+                   valid_to AS edge_valid_to
+            FROM knowledge_edges
+            WHERE tenant_id = $1
+              AND ($2::text IS NULL OR rel = $2)
+              AND valid_from <= COALESCE($4::timestamptz, now())
+              AND (valid_to IS NULL OR valid_to > COALESCE($4::timestamptz, now()))
+            ORDER BY created_at, id
+            LIMIT $3
+        "#,
+        )
+        .bind(tenant)
+        .bind(rel)
+        .bind(limit as i64)
+        .bind(as_of)
+        .fetch_all(&mut *conn)
+        .await
+        .context("failed to list edges")?;
+
+        rows.iter().map(map_edge_row).collect()
+    }
+
+    async fn edges_among(
+        &self,
+        tenant: Uuid,
+        ids: &[Uuid],
+        rel: Option<&str>,
+        as_of: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeEdge>> {
         let mut conn = self.pool.acquire().await.context("acquire connection")?;
         set_tenant_on_conn(&mut conn, tenant).await?;
 
         let rows = sqlx::query(
             r#"
-            SELECT n.id, n.tenant_id, n.kind, n.payload_json, n.provenance, n.policy, n.created_at, n.updated_at
-            FROM knowledge_edges e
-            JOIN knowledge_nodes n ON n.id = e.dst
-            WHERE e.tenant_id = $1
-              AND e.src = $2
-              AND ($3::text IS NULL OR e.rel = $3)
-            ORDER BY e.created_at DESC
+            SELECT id AS edge_id, tenant_id AS edge_tenant_id, src AS edge_src, dst AS edge_dst,
+                   rel AS edge_rel, weight AS edge_weight, props AS edge_props,
+                   created_at AS edge_created_at, valid_from AS edge_valid_from,
+                   valid_to AS edge_valid_to
+            FROM knowledge_edges
+            WHERE tenant_id = $1
+              AND src = ANY($2)
+              AND dst = ANY($2)
+              AND ($3::text IS NULL OR rel = $3)
+              AND valid_from <= COALESCE($5::timestamptz, now())
+              AND (valid_to IS NULL OR valid_to > COALESCE($5::timestamptz, now()))
+            ORDER BY created_at, id
             LIMIT $4
         "#,
         )
         .bind(tenant)
-        .bind(id)
+        .bind(ids)
         .bind(rel)
         .bind(limit as i64)
+        .bind(as_of)
+        .fetch_all(&mut *conn)
+        .await
+        .context("failed to fetch edges among nodes")?;
+
+        rows.iter().map(map_edge_row).collect()
+    }
+
+    async fn neighbors(
+        &self,
+        tenant: Uuid,
+        id: Uuid,
+        filter: &TraversalFilter,
+        hops: u8,
+        limit: usize,
+    ) -> Result<Vec<Neighbor>> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        // `walk` enumerates cycle-free paths from the start node; `reached` keeps the shallowest
+        // depth per node so the final join only returns edges that advance the frontier.
+        let rows = bind_steps(
+            sqlx::query(&format!(
+                r#"
+                WITH RECURSIVE {WALK_STEPS},
+                walk(node_id, depth, path) AS (
+                    SELECT start, 0, ARRAY[start]
+                    FROM unnest($2::uuid[]) AS start
+                    UNION ALL
+                    SELECT s.to_id, w.depth + 1, w.path || s.to_id
+                    FROM walk w
+                    JOIN steps s ON s.from_id = w.node_id
+                    WHERE w.depth < $6
+                      AND NOT s.to_id = ANY(w.path)
+                ),
+                reached AS (
+                    SELECT DISTINCT ON (node_id) node_id, depth
+                    FROM walk
+                    ORDER BY node_id, depth
+                )
+                SELECT n.id, n.tenant_id, n.kind, n.payload_json, n.vector::real[] AS vector, n.provenance, n.policy, n.created_at, n.updated_at,
+                       e.id AS edge_id, e.tenant_id AS edge_tenant_id, e.src AS edge_src, e.dst AS edge_dst,
+                       e.rel AS edge_rel, e.weight AS edge_weight, e.props AS edge_props,
+                       e.created_at AS edge_created_at, e.valid_from AS edge_valid_from,
+                       e.valid_to AS edge_valid_to, d.depth
+                FROM reached d
+                JOIN reached p ON p.depth = d.depth - 1
+                JOIN steps e ON e.from_id = p.node_id AND e.to_id = d.node_id
+                JOIN knowledge_nodes n ON n.id = d.node_id
+                WHERE d.depth > 0
+                ORDER BY d.depth, e.created_at DESC
+                LIMIT $7
+            "#
+            )),
+            tenant,
+            &[id],
+            filter,
+        )
+        .bind(i32::from(hops))
+        .bind(limit as i64)
         .fetch_all(&mut *conn)
         .await
         .context("failed to fetch neighbors")?;
 
-        let mut nodes = Vec::with_capacity(rows.len());
+        let mut neighbors = Vec::with_capacity(rows.len());
         for row in rows {
-            nodes.push(map_node_row(&row)?);
+            let depth: i32 = row.try_get("depth")?;
+            neighbors.push(Neighbor {
+                node: map_node_row(&row)?,
+                edge: map_edge_row(&row)?,
+                depth: depth as u8,
+            });
+        }
+        Ok(neighbors)
+    }
+
+    async fn shortest_path(
+        &self,
+        tenant: Uuid,
+        from: Uuid,
+        to: Uuid,
+        max_hops: u8,
+        filter: &TraversalFilter,
+    ) -> Result<Option<GraphPath>> {
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        // Hop-bounded Bellman-Ford, one query per hop: each hop relaxes the steps leaving the
+        // nodes whose cheapest route improved on the previous hop, so a node is carried forward
+        // once per hop instead of once per path. Routes ending at the target are not extended.
+        let sql = format!(
+            r#"
+            WITH {TRAVERSAL_STEPS}
+            SELECT id, from_id, to_id, weight
+            FROM steps
+            WHERE weight > 0
+        "#
+        );
+        let mut best: HashMap<Uuid, Route> = HashMap::from([(from, Route::default())]);
+        let mut frontier: HashMap<Uuid, Route> = best.clone();
+        for _ in 0..max_hops {
+            frontier.remove(&to);
+            if frontier.is_empty() {
+                break;
+            }
+            let ids: Vec<Uuid> = frontier.keys().copied().collect();
+            let rows = bind_steps(sqlx::query(&sql), tenant, &ids, filter)
+                .fetch_all(&mut *conn)
+                .await
+                .context("failed to search shortest path")?;
+
+            let mut improved: HashMap<Uuid, Route> = HashMap::new();
+            for row in rows {
+                let from_id: Uuid = row.try_get("from_id")?;
+                let to_id: Uuid = row.try_get("to_id")?;
+                let weight: f32 = row.try_get("weight")?;
+                let route = &frontier[&from_id];
+                let cost = route.cost + 1.0 / f64::from(weight);
+                let current = improved.get(&to_id).or_else(|| best.get(&to_id));
+                if current.is_none_or(|current| cost < current.cost) {
+                    let mut extended = route.clone();
+                    extended.cost = cost;
+                    extended.edge_ids.push(row.try_get("id")?);
+                    extended.hop_nodes.push(to_id);
+                    improved.insert(to_id, extended);
+                }
+            }
+            best.extend(improved.clone());
+            frontier = improved;
+        }
+
+        let Some(Route {
+            cost,
+            edge_ids,
+            hop_nodes,
+        }) = best.remove(&to)
+        else {
+            return Ok(None);
+        };
+
+        let origin = sqlx::query(
+            r#"
+            SELECT id, tenant_id, kind, payload_json, vector::real[] AS vector, provenance, policy, created_at, updated_at
+            FROM knowledge_nodes
+            WHERE tenant_id = $1
+              AND id = $2
+        "#,
+        )
+        .bind(tenant)
+        .bind(from)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("failed to fetch path origin")?;
+        let Some(origin) = origin else {
+            return Ok(None);
+        };
+
+        let hops = sqlx::query(
+            r#"
+            SELECT n.id, n.tenant_id, n.kind, n.payload_json, n.vector::real[] AS vector, n.provenance, n.policy, n.created_at, n.updated_at,
+                   e.id AS edge_id, e.tenant_id AS edge_tenant_id, e.src AS edge_src, e.dst AS edge_dst,
+                   e.rel AS edge_rel, e.weight AS edge_weight, e.props AS edge_props,
+                   e.created_at AS edge_created_at, e.valid_from AS edge_valid_from,
+                   e.valid_to AS edge_valid_to
+            FROM unnest($2::uuid[], $3::uuid[]) WITH ORDINALITY AS p(edge_id, node_id, ord)
+            JOIN knowledge_edges e ON e.id = p.edge_id AND e.tenant_id = $1
+            JOIN knowledge_nodes n ON n.id = p.node_id
+            ORDER BY p.ord
+        "#,
+        )
+        .bind(tenant)
+        .bind(&edge_ids)
+        .bind(&hop_nodes)
+        .fetch_all(&mut *conn)
+        .await
+        .context("failed to fetch path hops")?;
+
+        let mut path = GraphPath {
+            nodes: vec![map_node_row(&origin)?],
+            edges: Vec::with_capacity(hops.len()),
+            cost,
+        };
+        for row in hops {
+            path.nodes.push(map_node_row(&row)?);
+            path.edges.push(map_edge_row(&row)?);
+        }
+        Ok(Some(path))
+    }
+
+    async fn match_pattern(&self, tenant: Uuid, plan: &PatternPlan) -> Result<Vec<PatternRow>> {
+        let (sql, args) = PatternSql::default().compile(plan);
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        let mut query = sqlx::query(&sql).bind(tenant);
+        for arg in args {
+            query = match arg {
+                PatternArg::Text(value) => query.bind(value),
+                PatternArg::Texts(values) => query.bind(values),
+                PatternArg::Json(value) => query.bind(value),
+                PatternArg::Float(value) => query.bind(value),
+                PatternArg::Time(value) => query.bind(value),
+            };
         }
-        let _ = hops; // multi-hop traversal planned via recursive CTEs.
-        Ok(nodes)
+        let rows = query
+            .fetch_all(&mut *conn)
+            .await
+            .context("failed to run pattern query")?;
+        let matches = rows
+            .iter()
+            .map(|row| {
+                (0..plan.returns.len())
+                    .map(|k| row.try_get::<Uuid, _>(k))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (mut node_ids, mut edge_ids) = (Vec::new(), Vec::new());
+        for ids in &matches {
+            for (id, slot) in ids.iter().zip(&plan.returns) {
+                match slot {
+                    Slot::Node(_) => node_ids.push(*id),
+                    Slot::Edge(_) => edge_ids.push(*id),
+                }
+            }
+        }
+
+        let nodes = sqlx::query(
+            r#"
+            SELECT id, tenant_id, kind, payload_json, vector::real[] AS vector, provenance, policy, created_at, updated_at
+            FROM knowledge_nodes
+            WHERE tenant_id = $1 AND id = ANY($2)
+        "#,
+        )
+        .bind(tenant)
+        .bind(&node_ids)
+        .fetch_all(&mut *conn)
+        .await
+        .context("failed to fetch matched nodes")?
+        .iter()
+        .map(|row| map_node_row(row).map(|node| (node.id, node)))
+        .collect::<Result<HashMap<_, _>>>()?;
+        let edges = sqlx::query(
+            r#"
+            SELECT id AS edge_id, tenant_id AS edge_tenant_id, src AS edge_src, dst AS edge_dst,
+                   rel AS edge_rel, weight AS edge_weight, props AS edge_props,
+                   created_at AS edge_created_at, valid_from AS edge_valid_from,
+                   valid_to AS edge_valid_to
+            FROM knowledge_edges
+            WHERE tenant_id = $1 AND id = ANY($2)
+        "#,
+        )
+        .bind(tenant)
+        .bind(&edge_ids)
+        .fetch_all(&mut *conn)
+        .await
+        .context("failed to fetch matched edges")?
+        .iter()
+        .map(|row| map_edge_row(row).map(|edge| (edge.id, edge)))
+        .collect::<Result<HashMap<_, _>>>()?;
+
+        // Rows whose elements were deleted between the two statements are dropped.
+        Ok(matches
+            .into_iter()
+            .filter_map(|ids| {
+                ids.iter()
+                    .zip(&plan.returns)
+                    .map(|(id, slot)| match slot {
+                        Slot::Node(_) => nodes.get(id).cloned().map(PatternValue::Node),
+                        Slot::Edge(_) => edges.get(id).cloned().map(PatternValue::Edge),
+                    })
+                    .collect()
+            })
+            .collect())
     }
 }
 
-#[allow(dead_code)]
 #[derive(Clone)]
 pub struct PostgresEmbeddingRepository {
-    _pool: PgPool,
+    pool: PgPool,
 }
 
 impl PostgresEmbeddingRepository {
     pub fn new(pool: PgPool) -> Self {
-        Self { _pool: pool }
+        Self { pool }
     }
 }
 
 #[async_trait]
 impl EmbeddingRepository for PostgresEmbeddingRepository {
     async fn upsert_embedding(&self, tenant: Uuid, embedding: NodeEmbedding) -> Result<()> {
-        let _ = (tenant, embedding);
-        // TODO: implement pgvector-backed storage for embeddings.
+        embedding.check_dim()?;
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        // Selecting from the tenant's nodes keeps embeddings from attaching to another tenant's
+        // node, which the foreign key alone would allow.
+        let stored = sqlx::query(
+            r#"
+            INSERT INTO node_embeddings (node_id, tenant_id, model, dim, vec)
+            SELECT n.id, n.tenant_id, $3, $4, $5::real[]::vector
+            FROM knowledge_nodes n
+            WHERE n.tenant_id = $1
+              AND n.id = $2
+            ON CONFLICT (node_id, model) DO UPDATE SET
+                dim = EXCLUDED.dim,
+                vec = EXCLUDED.vec,
+                created_at = now()
+        "#,
+        )
+        .bind(tenant)
+        .bind(embedding.node_id)
+        .bind(&embedding.model)
+        .bind(embedding.dim)
+        .bind(&embedding.vec)
+        .execute(&mut *conn)
+        .await
+        .context("failed to upsert node embedding")?;
+
+        if stored.rows_affected() == 0 {
+            anyhow::bail!("node {} not found", embedding.node_id);
+        }
         Ok(())
     }
 
     async fn get_embeddings(&self, tenant: Uuid, node_id: Uuid) -> Result<Vec<NodeEmbedding>> {
-        let _ = (tenant, node_id);
-        // TODO: fetch embeddings once pgvector integration is wired.
-        Ok(Vec::new())
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT node_id, tenant_id, model, dim, vec::real[] AS vec, created_at
+            FROM node_embeddings
+            WHERE tenant_id = $1
+              AND node_id = $2
+            ORDER BY model
+        "#,
+        )
+        .bind(tenant)
+        .bind(node_id)
+        .fetch_all(&mut *conn)
+        .await
+        .context("failed to fetch node embeddings")?;
+
+        rows.iter().map(map_embedding_row).collect()
     }
+
+    async fn search_by_model(
+        &self,
+        tenant: Uuid,
+        model: &str,
+        vector: &[f32],
+        metric: DistanceMetric,
+        k: usize,
+    ) -> Result<Vec<ScoredEmbedding>> {
+        if vector.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self.pool.acquire().await.context("acquire connection")?;
+        set_tenant_on_conn(&mut conn, tenant).await?;
+
+        // As in `search_similar`, equal distances are broken by node id once the rows are back.
+        let sql = format!(
+            r#"
+            SELECT node_id, tenant_id, model, dim, vec::real[] AS vec, created_at,
+                   vec {op} $3::real[]::vector AS distance
+            FROM node_embeddings
+            WHERE tenant_id = $1
+              AND model = $2
+              AND dim = $4
+            ORDER BY vec {op} $3::real[]::vector
+            LIMIT $5
+        "#,
+            op = metric.as_sql()
+        );
+        let rows = sqlx::query(&sql)
+            .bind(tenant)
+            .bind(model)
+            .bind(vector)
+            .bind(vector.len() as i32)
+            .bind(k as i64)
+            .fetch_all(&mut *conn)
+            .await
+            .context("failed to search node embeddings")?;
+
+        let mut ranked = rows
+            .iter()
+            .map(|row| {
+                let distance: Option<f64> = row.try_get("distance")?;
+                Ok((distance.unwrap_or(f64::NAN), map_embedding_row(row)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.node_id.cmp(&b.1.node_id)));
+        Ok(ranked
+            .into_iter()
+            .map(|(distance, embedding)| ScoredEmbedding {
+                embedding,
+                score: metric.score(distance),
+                distance_metric: metric,
+            })
+            .collect())
+    }
+}
+
+fn map_embedding_row(row: &PgRow) -> Result<NodeEmbedding> {
+    Ok(NodeEmbedding {
+        node_id: row.try_get("node_id")?,
+        tenant_id: row.try_get("tenant_id")?,
+        model: row.try_get("model")?,
+        dim: row.try_get("dim")?,
+        vec: row.try_get("vec")?,
+        created_at: row.try_get("created_at")?,
+    })
 }
 
 #[allow(dead_code)]
@@ -1,38 +1,182 @@
 // SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
 // This gRPC service exposes the platform contract and will evolve with persistence and policy logic.
 
+mod bulk;
+mod capsule;
+mod health;
+
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use tonic::{Request, Response, Status};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::service::Interceptor;
+use tonic::{Request, Response, Status, Streaming};
 use uuid::Uuid;
 
+use self::capsule::CapsuleServiceImpl;
 use crate::config::AppConfig;
 use crate::domain::node::KnowledgeNode;
+use crate::graph::activation::{
+    activate, ActivationSpec, DEFAULT_ACTIVATION_HOPS, DEFAULT_DECAY, DEFAULT_VECTOR_SEEDS,
+    MAX_ACTIVATION_HOPS,
+};
+use crate::graph::query::{run_query, QueryError};
+use crate::graph::subgraph::{
+    extract_subgraph, SubgraphSpec, DEFAULT_MAX_EDGES, DEFAULT_MAX_NODES, MAX_EDGES_LIMIT,
+    MAX_NODES_LIMIT,
+};
+use crate::pb::convert::{datetime, edge_to_proto, node_to_proto, struct_to_json};
+use crate::pb::synagraph::v1 as pb;
+use crate::pb::synagraph::v1::capsule_service_server::CapsuleServiceServer;
 use crate::pb::synagraph::v1::graph_service_server::{GraphService, GraphServiceServer};
-use crate::pb::synagraph::v1::{PingRequest, PingResponse, UpsertNodeRequest, UpsertNodeResponse};
-use crate::repository::UpsertOutcome;
+use crate::pb::synagraph::v1::query_value::Value as QueryCell;
+use crate::pb::synagraph::v1::{
+    ActivateRequest, ActivateResponse, ActivatedNode, BulkUpsertNodesRequest,
+    BulkUpsertNodesResponse, DeleteNodeRequest, DeleteNodeResponse, Edge, ExpireEdgeRequest,
+    ExpireEdgeResponse, GetEdgeRequest, GetEdgeResponse, GetNeighborsRequest, GetNeighborsResponse,
+    GetNodeRequest, GetNodeResponse, GetSubgraphRequest, GetSubgraphResponse, LinkNodesRequest,
+    LinkNodesResponse, PingRequest, PingResponse, QueryNodesByKindRequest,
+    QueryNodesByKindResponse, QueryRow, QueryValue, RunQueryRequest, RunQueryResponse, ScoredNode,
+    SearchSimilarRequest, SearchSimilarResponse, ShortestPathRequest, ShortestPathResponse,
+    UnlinkNodesRequest, UnlinkNodesResponse, UpdateEdgeRequest, UpdateEdgeResponse,
+    UpsertNodeRequest, UpsertNodeResponse,
+};
+use crate::pb::FILE_DESCRIPTOR_SET;
+use crate::repository::pattern::PatternValue;
+use crate::repository::{
+    DimensionMismatch, Direction, DistanceMetric, EdgeLink, EdgeUpdate, KnowledgeEdge, LinkedEdge,
+    NodeCursor, SimilarityFilter, TraversalFilter, UpsertOutcome,
+};
 use crate::state::AppContext;
 
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+const DEFAULT_EDGE_WEIGHT: f32 = 1.0;
+const MAX_TRAVERSAL_HOPS: u32 = 4;
+
+/// Metadata key carrying the caller's tenant, as a slug from `TENANT_SLUGS` or a known tenant UUID.
+pub const TENANT_METADATA_KEY: &str = "x-synagraph-tenant";
+
 pub async fn serve(cfg: AppConfig, ctx: AppContext) -> Result<()> {
     let addr: SocketAddr = cfg.grpc_addr;
-    let svc = GraphServiceImpl::new(cfg.clone(), ctx);
+    let tenants = TenantInterceptor::new(&cfg);
+    let capsules = CapsuleServiceImpl::new(&cfg, ctx.clone());
+    let svc = GraphServiceImpl::new(cfg.clone(), ctx.clone());
+
+    let (reporter, health) = tonic_health::server::health_reporter();
+    health::track_storage_health(ctx.clone(), reporter).await;
+    let reflection = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build()
+        .context("failed to build grpc reflection service")?;
 
     tracing::info!(%addr, "grpc server listening");
 
     tonic::transport::Server::builder()
-        .add_service(GraphServiceServer::new(svc))
+        .add_service(health)
+        .add_service(reflection)
+        .add_service(GraphServiceServer::with_interceptor(svc, tenants.clone()))
+        .add_service(CapsuleServiceServer::with_interceptor(capsules, tenants))
         .serve(addr)
         .await
         .context("grpc server error")
 }
 
+/// Tenant resolved for a request by [`TenantInterceptor`], stored in the request extensions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RequestTenant(Uuid);
+
+/// Slug the caller named in the tenant header, kept so capsule policies can be checked against it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct RequestTenantSlug(String);
+
+/// Resolves the `x-synagraph-tenant` header into a [`RequestTenant`]. Only the configured default
+/// tenant and tenants named in `TENANT_SLUGS` are accepted, by UUID or slug. Requests without the
+/// header run against the default tenant when `grpc_default_tenant_fallback` is set and are
+/// rejected otherwise.
+#[derive(Clone)]
+struct TenantInterceptor {
+    default_tenant: Uuid,
+    default_fallback: bool,
+    slugs: Arc<HashMap<String, Uuid>>,
+}
+
+impl TenantInterceptor {
+    fn new(cfg: &AppConfig) -> Self {
+        Self {
+            default_tenant: cfg.default_tenant_id,
+            default_fallback: cfg.grpc_default_tenant_fallback,
+            slugs: Arc::new(cfg.tenant_slugs.clone()),
+        }
+    }
+
+    /// Returns the tenant id and, when the header named a slug, the slug itself.
+    fn resolve<'a>(&self, raw: &'a str) -> Option<(Uuid, Option<&'a str>)> {
+        let raw = raw.trim();
+        if let Ok(id) = Uuid::parse_str(raw) {
+            let known = id == self.default_tenant || self.slugs.values().any(|slug| *slug == id);
+            return known.then_some((id, None));
+        }
+        self.slugs.get(raw).map(|id| (*id, Some(raw)))
+    }
+}
+
+impl Interceptor for TenantInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let (tenant, slug) = match request.metadata().get(TENANT_METADATA_KEY) {
+            None if self.default_fallback => (self.default_tenant, None),
+            None => {
+                return Err(Status::unauthenticated(format!(
+                    "{TENANT_METADATA_KEY} header is required"
+                )))
+            }
+            Some(value) => {
+                let raw = value.to_str().map_err(|_| {
+                    Status::invalid_argument(format!("{TENANT_METADATA_KEY} must be ASCII"))
+                })?;
+                let (tenant, slug) = self.resolve(raw).ok_or_else(|| {
+                    tracing::warn!(tenant = raw, "rejecting request for unknown tenant");
+                    Status::permission_denied(format!("unknown tenant '{raw}'"))
+                })?;
+                (tenant, slug.map(|slug| RequestTenantSlug(slug.to_string())))
+            }
+        };
+
+        request.extensions_mut().insert(RequestTenant(tenant));
+        if let Some(slug) = slug {
+            request.extensions_mut().insert(slug);
+        }
+        Ok(request)
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn request_tenant<T>(request: &Request<T>) -> Result<Uuid, Status> {
+    request
+        .extensions()
+        .get::<RequestTenant>()
+        .map(|tenant| tenant.0)
+        .ok_or_else(|| Status::internal("tenant context missing from request"))
+}
+
+fn request_tenant_slug<T>(request: &Request<T>) -> Option<&str> {
+    request
+        .extensions()
+        .get::<RequestTenantSlug>()
+        .map(|slug| slug.0.as_str())
+}
+
 #[derive(Clone)]
 struct GraphServiceImpl {
     service_name: String,
     version: String,
+    /// Similarity metric for requests that leave it unspecified.
+    default_metric: DistanceMetric,
     ctx: AppContext,
-    default_tenant: Uuid,
 }
 
 impl GraphServiceImpl {
@@ -40,8 +184,19 @@ impl GraphServiceImpl {
         Self {
             service_name: cfg.service_name,
             version: cfg.version,
+            default_metric: cfg.vector_distance_metric,
             ctx,
-            default_tenant: cfg.default_tenant_id,
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn distance_metric(&self, value: i32) -> Result<DistanceMetric, Status> {
+        match pb::DistanceMetric::try_from(value) {
+            Ok(pb::DistanceMetric::Unspecified) => Ok(self.default_metric),
+            Ok(pb::DistanceMetric::Cosine) => Ok(DistanceMetric::Cosine),
+            Ok(pb::DistanceMetric::L2) => Ok(DistanceMetric::L2),
+            Ok(pb::DistanceMetric::Dot) => Ok(DistanceMetric::InnerProduct),
+            Err(_) => Err(Status::invalid_argument("unknown distance_metric")),
         }
     }
 }
@@ -67,19 +222,11 @@ impl GraphService for GraphServiceImpl {
         &self,
         request: Request<UpsertNodeRequest>,
     ) -> Result<Response<UpsertNodeResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
         let payload = request.into_inner();
         tracing::debug!(service = %self.service_name, kind = %payload.kind, "processing upsert_node");
-        let json_payload = parse_payload(&payload.payload_json)?;
-
-        let tenant_id = self.default_tenant;
-        let mut node = KnowledgeNode::new(tenant_id, payload.kind, json_payload);
-        let node_id = if payload.node_id.is_empty() {
-            node.id
-        } else {
-            Uuid::parse_str(&payload.node_id)
-                .map_err(|_| Status::invalid_argument("node_id must be a UUID"))?
-        };
-        node.id = node_id;
+        let node = node_from_request(tenant_id, payload)?;
+        let node_id = node.id;
 
         let outcome = self
             .ctx
@@ -87,10 +234,7 @@ impl GraphService for GraphServiceImpl {
             .nodes
             .upsert(tenant_id, node.clone())
             .await
-            .map_err(|err| {
-                tracing::error!(?err, "node upsert failed");
-                Status::internal("failed to persist node")
-            })?;
+            .map_err(persist_error)?;
 
         self.ctx.dashboard.record_store(
             tenant_id,
@@ -106,120 +250,1896 @@ impl GraphService for GraphServiceImpl {
 
         Ok(Response::new(response))
     }
-}
 
-fn parse_payload(raw: &str) -> Result<serde_json::Value, Status> {
-    if raw.trim().is_empty() {
-        return Ok(serde_json::Value::Null);
+    async fn get_node(
+        &self,
+        request: Request<GetNodeRequest>,
+    ) -> Result<Response<GetNodeResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let node_id = parse_uuid("node_id", &request.into_inner().node_id)?;
+
+        let node = self
+            .ctx
+            .repos
+            .nodes
+            .get(tenant_id, node_id)
+            .await
+            .map_err(|err| storage_error("node lookup", err))?;
+
+        self.ctx
+            .dashboard
+            .record_lookup(tenant_id, node_id, node.is_some());
+
+        let node = node.ok_or_else(|| Status::not_found(format!("node {node_id} not found")))?;
+        Ok(Response::new(GetNodeResponse {
+            node: Some(node_to_proto(&node)),
+        }))
     }
 
-    serde_json::from_str(raw)
-        .map_err(|err| Status::invalid_argument(format!("payload_json is not valid JSON: {}", err)))
-}
+    async fn delete_node(
+        &self,
+        request: Request<DeleteNodeRequest>,
+    ) -> Result<Response<DeleteNodeResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let node_id = parse_uuid("node_id", &request.into_inner().node_id)?;
 
-#[cfg(test)]
-mod tests {
-    use super::{parse_payload, GraphServiceImpl};
-    use crate::config::AppConfig;
-    use crate::pb::synagraph::v1::graph_service_server::GraphService;
-    use crate::pb::synagraph::v1::UpsertNodeRequest;
-    use crate::repository::in_memory::{
-        InMemoryBus, InMemoryCache, InMemoryEdgeRepository, InMemoryEmbeddingRepository,
-        InMemoryNodeRepository, InMemoryOutboxRepository,
-    };
-    use crate::repository::RepositoryBundle;
-    use crate::state::{AppContext, DashboardHandle};
-    use std::collections::HashMap;
-    use std::sync::Arc;
-    use tonic::Request;
-    use uuid::Uuid;
+        let node = self
+            .ctx
+            .repos
+            .nodes
+            .delete(tenant_id, node_id)
+            .await
+            .map_err(|err| storage_error("node delete", err))?
+            .ok_or_else(|| Status::not_found(format!("node {node_id} not found")))?;
 
-    #[test]
-    fn parses_valid_json() {
-        let payload = parse_payload("{\"foo\":1}").expect("valid json");
-        assert_eq!(payload["foo"], 1);
+        self.ctx
+            .dashboard
+            .record_purge(tenant_id, serde_json::json!({ "node_id": node_id }));
+
+        Ok(Response::new(DeleteNodeResponse {
+            node: Some(node_to_proto(&node)),
+        }))
     }
 
-    #[test]
-    fn empty_payload_defaults_to_null() {
-        let payload = parse_payload("   ").expect("empty json");
-        assert!(payload.is_null());
+    async fn query_nodes_by_kind(
+        &self,
+        request: Request<QueryNodesByKindRequest>,
+    ) -> Result<Response<QueryNodesByKindResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let req = request.into_inner();
+        if req.kind.trim().is_empty() {
+            return Err(Status::invalid_argument("kind is required"));
+        }
+        let limit = page_size(req.limit);
+        let cursor = if req.cursor.is_empty() {
+            None
+        } else {
+            Some(
+                NodeCursor::decode(&req.cursor)
+                    .map_err(|_| Status::invalid_argument("cursor is invalid"))?,
+            )
+        };
+
+        let nodes = self
+            .ctx
+            .repos
+            .nodes
+            .query_by_kind(tenant_id, &req.kind, limit, cursor, !req.omit_vectors)
+            .await
+            .map_err(|err| storage_error("node query", err))?;
+
+        let next_cursor = match nodes.last() {
+            Some(last) if nodes.len() == limit => NodeCursor::after(last).encode(),
+            _ => String::new(),
+        };
+
+        Ok(Response::new(QueryNodesByKindResponse {
+            nodes: nodes.iter().map(node_to_proto).collect(),
+            next_cursor,
+        }))
     }
 
-    #[test]
-    fn invalid_json_errors() {
-        let err = parse_payload("not-json").unwrap_err();
-        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    async fn search_similar(
+        &self,
+        request: Request<SearchSimilarRequest>,
+    ) -> Result<Response<SearchSimilarResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let req = request.into_inner();
+        if req.vector.is_empty() {
+            return Err(Status::invalid_argument("vector must not be empty"));
+        }
+        if req.vector.iter().any(|value| !value.is_finite()) {
+            return Err(Status::invalid_argument(
+                "vector must contain finite values",
+            ));
+        }
+
+        let metric = self.distance_metric(req.distance_metric)?;
+
+        let hits = self
+            .ctx
+            .repos
+            .nodes
+            .search_similar(
+                tenant_id,
+                &req.vector,
+                metric,
+                &similarity_filter(req.filter),
+                page_size(req.limit),
+            )
+            .await
+            .map_err(|err| vector_error("similarity search", err))?;
+
+        Ok(Response::new(SearchSimilarResponse {
+            hits: hits
+                .iter()
+                .map(|hit| ScoredNode {
+                    node: Some(node_to_proto(&hit.node)),
+                    score: hit.score,
+                })
+                .collect(),
+            distance_metric: metric_to_proto(metric) as i32,
+        }))
     }
 
-    #[tokio::test]
-    async fn upsert_node_persists_and_updates_records() {
-        let tenant = Uuid::new_v4();
-        let cfg = AppConfig {
-            http_addr: "127.0.0.1:0".parse().unwrap(),
-            grpc_addr: "127.0.0.1:0".parse().unwrap(),
-            service_name: "synagraph".into(),
-            version: "0.1.0-test".into(),
-            database_url: None,
-            default_tenant_id: tenant,
-            scedge_base_url: None,
-            scedge_event_bus_enabled: false,
-            scedge_event_bus_subject: "scedge:events".into(),
-            tenant_slugs: HashMap::new(),
+    async fn link_nodes(
+        &self,
+        request: Request<LinkNodesRequest>,
+    ) -> Result<Response<LinkNodesResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let link = link_from_request(request.into_inner())?;
+        let linked = self.link(tenant_id, link).await?;
+
+        Ok(Response::new(LinkNodesResponse {
+            edge_id: linked.id.to_string(),
+            created: matches!(linked.outcome, UpsertOutcome::Created),
+        }))
+    }
+
+    async fn get_edge(
+        &self,
+        request: Request<GetEdgeRequest>,
+    ) -> Result<Response<GetEdgeResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let edge_id = parse_uuid("edge_id", &request.into_inner().edge_id)?;
+
+        let edge = self.fetch_edge(tenant_id, edge_id).await?;
+
+        Ok(Response::new(GetEdgeResponse {
+            edge: Some(edge_to_proto(&edge)),
+        }))
+    }
+
+    async fn update_edge(
+        &self,
+        request: Request<UpdateEdgeRequest>,
+    ) -> Result<Response<UpdateEdgeResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let req = request.into_inner();
+        let edge_id = parse_uuid("edge_id", &req.edge_id)?;
+        if req.weight.is_some_and(|weight| !weight.is_finite()) {
+            return Err(Status::invalid_argument("weight must be finite"));
+        }
+        let update = EdgeUpdate {
+            weight: req.weight,
+            props: edge_props(req.props.as_ref(), &req.props_json)?,
+            valid_from: parse_time("valid_from", req.valid_from.as_ref())?,
         };
+        if let Some(valid_from) = update.valid_from {
+            let edge = self.fetch_edge(tenant_id, edge_id).await?;
+            if edge.valid_to.is_some_and(|end| end < valid_from) {
+                return Err(Status::invalid_argument(
+                    "valid_from must not be after the edge expired",
+                ));
+            }
+        }
 
-        let repos = RepositoryBundle::new(
-            Arc::new(InMemoryNodeRepository::new()),
-            Arc::new(InMemoryEdgeRepository::new()),
-            Arc::new(InMemoryEmbeddingRepository::new()),
-            Arc::new(InMemoryOutboxRepository::new()),
-            Arc::new(InMemoryCache::default()),
-            Arc::new(InMemoryBus::default()),
-        );
-        let dashboard = DashboardHandle::new();
-        let scedge = crate::scedge::ScedgeBridge::new(None);
-        let ctx = AppContext::new(repos.clone(), dashboard, scedge);
-        let service = GraphServiceImpl::new(cfg.clone(), ctx.clone());
+        let edge = self
+            .ctx
+            .repos
+            .edges
+            .update_edge(tenant_id, edge_id, update)
+            .await
+            .map_err(|err| storage_error("edge update", err))?
+            .ok_or_else(|| edge_not_found(edge_id))?;
 
-        let response = service
-            .upsert_node(Request::new(UpsertNodeRequest {
-                node_id: String::new(),
-                kind: "note".into(),
-                payload_json: "{\"title\":\"first\"}".into(),
-            }))
+        Ok(Response::new(UpdateEdgeResponse {
+            edge: Some(edge_to_proto(&edge)),
+        }))
+    }
+
+    async fn unlink_nodes(
+        &self,
+        request: Request<UnlinkNodesRequest>,
+    ) -> Result<Response<UnlinkNodesResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let edge_id = parse_uuid("edge_id", &request.into_inner().edge_id)?;
+
+        let edge = self
+            .ctx
+            .repos
+            .edges
+            .unlink(tenant_id, edge_id)
             .await
-            .expect("upsert succeeds")
-            .into_inner();
+            .map_err(|err| storage_error("edge delete", err))?
+            .ok_or_else(|| edge_not_found(edge_id))?;
 
-        assert!(response.created);
-        let node_id = Uuid::parse_str(&response.node_id).expect("valid uuid");
+        Ok(Response::new(UnlinkNodesResponse {
+            edge: Some(edge_to_proto(&edge)),
+        }))
+    }
 
-        let stored = ctx
+    async fn expire_edge(
+        &self,
+        request: Request<ExpireEdgeRequest>,
+    ) -> Result<Response<ExpireEdgeResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let req = request.into_inner();
+        let edge_id = parse_uuid("edge_id", &req.edge_id)?;
+        let at = parse_time("at", req.at.as_ref())?.unwrap_or_else(chrono::Utc::now);
+
+        let edge = self.fetch_edge(tenant_id, edge_id).await?;
+        if edge.valid_to.is_none() && at < edge.valid_from {
+            return Err(Status::invalid_argument(
+                "at must not be before the edge became valid",
+            ));
+        }
+        let edge = self
+            .ctx
             .repos
-            .nodes
-            .get(tenant, node_id)
+            .edges
+            .expire_edge(tenant_id, edge_id, at)
             .await
-            .expect("get succeeds");
-        assert!(stored.is_some());
+            .map_err(|err| storage_error("edge expiry", err))?
+            .ok_or_else(|| edge_not_found(edge_id))?;
 
-        let response_update = service
-            .upsert_node(Request::new(UpsertNodeRequest {
-                node_id: response.node_id.clone(),
-                kind: "note".into(),
-                payload_json: "{\"title\":\"updated\"}".into(),
-            }))
+        Ok(Response::new(ExpireEdgeResponse {
+            edge: Some(edge_to_proto(&edge)),
+        }))
+    }
+
+    type BulkUpsertNodesStream = ReceiverStream<Result<BulkUpsertNodesResponse, Status>>;
+
+    async fn bulk_upsert_nodes(
+        &self,
+        request: Request<Streaming<BulkUpsertNodesRequest>>,
+    ) -> Result<Response<Self::BulkUpsertNodesStream>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let (tx, rx) = mpsc::channel(bulk::RESPONSE_BUFFER);
+        tokio::spawn(bulk::run(self.clone(), tenant_id, request.into_inner(), tx));
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn get_neighbors(
+        &self,
+        request: Request<GetNeighborsRequest>,
+    ) -> Result<Response<GetNeighborsResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let req = request.into_inner();
+        let node_id = parse_uuid("node_id", &req.node_id)?;
+        let hops = match req.hops {
+            0 => 1,
+            n if n > MAX_TRAVERSAL_HOPS => {
+                return Err(Status::invalid_argument(format!(
+                    "hops must not exceed {MAX_TRAVERSAL_HOPS}"
+                )))
+            }
+            n => n as u8,
+        };
+        let filter = traversal_filter(req.rel, req.direction, req.as_of.as_ref())?;
+
+        self.ensure_node_exists(tenant_id, node_id).await?;
+
+        let neighbors = self
+            .ctx
+            .repos
+            .edges
+            .neighbors(tenant_id, node_id, &filter, hops, page_size(req.limit))
             .await
-            .expect("upsert succeeds")
-            .into_inner();
+            .map_err(|err| storage_error("neighbor traversal", err))?;
 
-        assert!(!response_update.created);
-        let stored_updated = ctx
+        let mut seen = HashSet::new();
+        let mut response = GetNeighborsResponse::default();
+        for neighbor in &neighbors {
+            if seen.insert(neighbor.node.id) {
+                response.nodes.push(node_to_proto(&neighbor.node));
+            }
+            response.edges.push(Edge {
+                depth: u32::from(neighbor.depth),
+                ..edge_to_proto(&neighbor.edge)
+            });
+        }
+
+        Ok(Response::new(response))
+    }
+
+    async fn shortest_path(
+        &self,
+        request: Request<ShortestPathRequest>,
+    ) -> Result<Response<ShortestPathResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let req = request.into_inner();
+        let from = parse_uuid("from_id", &req.from_id)?;
+        let to = parse_uuid("to_id", &req.to_id)?;
+        let max_hops = match req.max_hops {
+            0 => MAX_TRAVERSAL_HOPS as u8,
+            n if n > MAX_TRAVERSAL_HOPS => {
+                return Err(Status::invalid_argument(format!(
+                    "max_hops must not exceed {MAX_TRAVERSAL_HOPS}"
+                )))
+            }
+            n => n as u8,
+        };
+        let filter = traversal_filter(req.rel, req.direction, req.as_of.as_ref())?;
+
+        self.ensure_node_exists(tenant_id, from).await?;
+        self.ensure_node_exists(tenant_id, to).await?;
+
+        let path = self
+            .ctx
+            .repos
+            .edges
+            .shortest_path(tenant_id, from, to, max_hops, &filter)
+            .await
+            .map_err(|err| storage_error("shortest path", err))?;
+
+        let response = match path {
+            Some(path) => ShortestPathResponse {
+                found: true,
+                nodes: path.nodes.iter().map(node_to_proto).collect(),
+                edges: path.edges.iter().map(edge_to_proto).collect(),
+                cost: path.cost,
+            },
+            None => ShortestPathResponse::default(),
+        };
+        Ok(Response::new(response))
+    }
+
+    async fn get_subgraph(
+        &self,
+        request: Request<GetSubgraphRequest>,
+    ) -> Result<Response<GetSubgraphResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let req = request.into_inner();
+        if req.seed_ids.is_empty() {
+            return Err(Status::invalid_argument("seed_ids must not be empty"));
+        }
+        #[allow(clippy::result_large_err)]
+        let seeds = req
+            .seed_ids
+            .iter()
+            .map(|id| parse_uuid("seed_ids", id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let hops = match req.hops.unwrap_or(1) {
+            n if n > MAX_TRAVERSAL_HOPS => {
+                return Err(Status::invalid_argument(format!(
+                    "hops must not exceed {MAX_TRAVERSAL_HOPS}"
+                )))
+            }
+            n => n as u8,
+        };
+        let spec = SubgraphSpec {
+            seeds,
+            hops,
+            filter: traversal_filter(req.rel, req.direction, req.as_of.as_ref())?,
+            kinds: req.kinds,
+            max_nodes: match req.max_nodes as usize {
+                0 => DEFAULT_MAX_NODES,
+                n => n.min(MAX_NODES_LIMIT),
+            },
+            max_edges: match req.max_edges as usize {
+                0 => DEFAULT_MAX_EDGES,
+                n => n.min(MAX_EDGES_LIMIT),
+            },
+        };
+
+        let subgraph = extract_subgraph(&self.ctx.repos, tenant_id, &spec)
+            .await
+            .map_err(|err| storage_error("subgraph export", err))?;
+
+        Ok(Response::new(GetSubgraphResponse {
+            nodes: subgraph.nodes.iter().map(node_to_proto).collect(),
+            edges: subgraph.edges.iter().map(edge_to_proto).collect(),
+            truncated: subgraph.truncated,
+        }))
+    }
+
+    async fn activate(
+        &self,
+        request: Request<ActivateRequest>,
+    ) -> Result<Response<ActivateResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let req = request.into_inner();
+        if req.seed_ids.is_empty() && req.vector.is_empty() {
+            return Err(Status::invalid_argument("seed_ids or vector is required"));
+        }
+        if req.vector.iter().any(|value| !value.is_finite()) {
+            return Err(Status::invalid_argument(
+                "vector must contain finite values",
+            ));
+        }
+        #[allow(clippy::result_large_err)]
+        let seeds = req
+            .seed_ids
+            .iter()
+            .map(|id| parse_uuid("seed_ids", id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let hops = match req.hops {
+            0 => DEFAULT_ACTIVATION_HOPS,
+            n if n > u32::from(MAX_ACTIVATION_HOPS) => {
+                return Err(Status::invalid_argument(format!(
+                    "hops must not exceed {MAX_ACTIVATION_HOPS}"
+                )))
+            }
+            n => n as u8,
+        };
+        let decay = match req.decay {
+            0.0 => DEFAULT_DECAY,
+            d if d > 0.0 && d < 1.0 => d,
+            _ => return Err(Status::invalid_argument("decay must be between 0 and 1")),
+        };
+        let spec = ActivationSpec {
+            seeds,
+            vector: Some(req.vector).filter(|vector| !vector.is_empty()),
+            vector_seeds: match req.vector_seeds {
+                0 => DEFAULT_VECTOR_SEEDS,
+                n => page_size(n),
+            },
+            metric: self.distance_metric(req.distance_metric)?,
+            hops,
+            decay,
+            filter: traversal_filter(req.rel, req.direction, req.as_of.as_ref())?,
+            limit: page_size(req.limit),
+            include_seeds: req.include_seeds,
+        };
+
+        let activation = activate(&self.ctx.repos, tenant_id, &spec)
+            .await
+            .map_err(|err| vector_error("activation", err))?;
+
+        Ok(Response::new(ActivateResponse {
+            nodes: activation
+                .nodes
+                .iter()
+                .map(|activated| ActivatedNode {
+                    node: Some(node_to_proto(&activated.node)),
+                    score: activated.score,
+                })
+                .collect(),
+            truncated: activation.truncated,
+        }))
+    }
+
+    async fn run_query(
+        &self,
+        request: Request<RunQueryRequest>,
+    ) -> Result<Response<RunQueryResponse>, Status> {
+        let tenant_id = request_tenant(&request)?;
+        let req = request.into_inner();
+        let as_of = parse_time("as_of", req.as_of.as_ref())?;
+        let result = match run_query(&self.ctx.repos, tenant_id, &req.query, as_of).await {
+            Ok(result) => result,
+            Err(QueryError::Invalid(message)) => return Err(Status::invalid_argument(message)),
+            Err(QueryError::Storage(err)) => return Err(storage_error("pattern query", err)),
+        };
+
+        let rows = result
+            .rows
+            .iter()
+            .map(|row| QueryRow {
+                values: row
+                    .iter()
+                    .map(|value| QueryValue {
+                        value: Some(match value {
+                            PatternValue::Node(node) => QueryCell::Node(node_to_proto(node)),
+                            PatternValue::Edge(edge) => QueryCell::Edge(edge_to_proto(edge)),
+                        }),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Response::new(RunQueryResponse {
+            columns: result.columns,
+            rows,
+        }))
+    }
+}
+
+impl GraphServiceImpl {
+    async fn link(&self, tenant: Uuid, link: EdgeLink) -> Result<LinkedEdge, Status> {
+        self.ensure_node_exists(tenant, link.src).await?;
+        self.ensure_node_exists(tenant, link.dst).await?;
+
+        self.ctx
+            .repos
+            .edges
+            .link(
+                tenant,
+                link.src,
+                link.dst,
+                &link.rel,
+                link.weight,
+                link.props,
+            )
+            .await
+            .map_err(|err| storage_error("edge link", err))
+    }
+
+    async fn ensure_node_exists(&self, tenant: Uuid, id: Uuid) -> Result<(), Status> {
+        let found = self
+            .ctx
             .repos
             .nodes
-            .get(tenant, node_id)
+            .get(tenant, id)
             .await
-            .expect("get succeeds")
-            .expect("node exists");
-        assert_eq!(stored_updated.payload_json["title"], "updated");
+            .map_err(|err| storage_error("node lookup", err))?;
+        match found {
+            Some(_) => Ok(()),
+            None => Err(Status::not_found(format!("node {id} not found"))),
+        }
+    }
+
+    async fn fetch_edge(&self, tenant: Uuid, id: Uuid) -> Result<KnowledgeEdge, Status> {
+        self.ctx
+            .repos
+            .edges
+            .get_edge(tenant, id)
+            .await
+            .map_err(|err| storage_error("edge lookup", err))?
+            .ok_or_else(|| edge_not_found(id))
+    }
+}
+
+/// Builds the node described by an `UpsertNodeRequest`, generating an id when none is given.
+#[allow(clippy::result_large_err)]
+fn node_from_request(tenant_id: Uuid, payload: UpsertNodeRequest) -> Result<KnowledgeNode, Status> {
+    let json_payload = match (&payload.payload, payload.payload_json.trim().is_empty()) {
+        (Some(_), false) => {
+            return Err(Status::invalid_argument(
+                "set either payload or payload_json, not both",
+            ))
+        }
+        (Some(structured), true) => struct_to_json(structured),
+        (None, _) => parse_payload(&payload.payload_json)?,
+    };
+    let vector = parse_vector(payload.vector)?;
+
+    let mut node = KnowledgeNode::new(tenant_id, payload.kind, json_payload);
+    node.vector = vector;
+    node.provenance = payload.provenance.as_ref().map(struct_to_json);
+    node.policy = payload.policy.as_ref().map(struct_to_json);
+    if !payload.node_id.is_empty() {
+        node.id = Uuid::parse_str(&payload.node_id)
+            .map_err(|_| Status::invalid_argument("node_id must be a UUID"))?;
+    }
+    Ok(node)
+}
+
+/// Validates the arguments of a `LinkNodesRequest`.
+#[allow(clippy::result_large_err)]
+fn link_from_request(req: LinkNodesRequest) -> Result<EdgeLink, Status> {
+    let src = parse_uuid("src_id", &req.src_id)?;
+    let dst = parse_uuid("dst_id", &req.dst_id)?;
+    if req.rel.trim().is_empty() {
+        return Err(Status::invalid_argument("rel is required"));
+    }
+    let weight = req.weight.unwrap_or(DEFAULT_EDGE_WEIGHT);
+    if !weight.is_finite() {
+        return Err(Status::invalid_argument("weight must be finite"));
+    }
+    let props = edge_props(req.props.as_ref(), &req.props_json)?;
+
+    Ok(EdgeLink {
+        src,
+        dst,
+        rel: req.rel,
+        weight,
+        props,
+    })
+}
+
+/// Reads edge props from either the structured or the JSON-encoded field.
+#[allow(clippy::result_large_err)]
+fn edge_props(
+    structured: Option<&prost_types::Struct>,
+    props_json: &str,
+) -> Result<Option<serde_json::Value>, Status> {
+    match (structured, props_json.trim().is_empty()) {
+        (Some(_), false) => Err(Status::invalid_argument(
+            "set either props or props_json, not both",
+        )),
+        (Some(structured), true) => Ok(Some(struct_to_json(structured))),
+        (None, _) => parse_props(props_json),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn traversal_filter(
+    rel: String,
+    direction: i32,
+    as_of: Option<&prost_types::Timestamp>,
+) -> Result<TraversalFilter, Status> {
+    let direction = match pb::Direction::try_from(direction) {
+        Ok(pb::Direction::Unspecified | pb::Direction::Outgoing) => Direction::Outgoing,
+        Ok(pb::Direction::Incoming) => Direction::Incoming,
+        Ok(pb::Direction::Both) => Direction::Both,
+        Err(_) => return Err(Status::invalid_argument("unknown direction")),
+    };
+    Ok(TraversalFilter {
+        rel: (!rel.is_empty()).then_some(rel),
+        direction,
+        as_of: parse_time("as_of", as_of)?,
+    })
+}
+
+fn similarity_filter(filter: Option<pb::SimilarityFilter>) -> SimilarityFilter {
+    let Some(filter) = filter else {
+        return SimilarityFilter::default();
+    };
+    let non_empty = |value: String| (!value.is_empty()).then_some(value);
+    SimilarityFilter {
+        kind: non_empty(filter.kind),
+        phi: filter.phi,
+        pii: filter.pii,
+        region: non_empty(filter.region),
+        compliance_tags: filter.compliance_tags,
+        payload: filter.payload.as_ref().map(struct_to_json),
+    }
+}
+
+fn metric_to_proto(metric: DistanceMetric) -> pb::DistanceMetric {
+    match metric {
+        DistanceMetric::Cosine => pb::DistanceMetric::Cosine,
+        DistanceMetric::L2 => pb::DistanceMetric::L2,
+        DistanceMetric::InnerProduct => pb::DistanceMetric::Dot,
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_time(
+    field: &str,
+    value: Option<&prost_types::Timestamp>,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, Status> {
+    value
+        .map(|value| {
+            datetime(value)
+                .ok_or_else(|| Status::invalid_argument(format!("{field} is out of range")))
+        })
+        .transpose()
+}
+
+fn edge_not_found(id: Uuid) -> Status {
+    Status::not_found(format!("edge {id} not found"))
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_uuid(field: &str, raw: &str) -> Result<Uuid, Status> {
+    if raw.is_empty() {
+        return Err(Status::invalid_argument(format!("{field} is required")));
+    }
+    Uuid::parse_str(raw).map_err(|_| Status::invalid_argument(format!("{field} must be a UUID")))
+}
+
+fn page_size(requested: u32) -> usize {
+    match requested as usize {
+        0 => DEFAULT_PAGE_SIZE,
+        n => n.min(MAX_PAGE_SIZE),
+    }
+}
+
+fn storage_error(operation: &str, err: anyhow::Error) -> Status {
+    tracing::error!(?err, operation, "repository call failed");
+    Status::internal(format!("{operation} failed"))
+}
+
+/// Like [`storage_error`], but a vector of the wrong length is the caller's mistake.
+fn vector_error(operation: &str, err: anyhow::Error) -> Status {
+    match err.downcast_ref::<DimensionMismatch>() {
+        Some(mismatch) => Status::invalid_argument(mismatch.to_string()),
+        None => storage_error(operation, err),
+    }
+}
+
+/// Maps a failed node write, reporting vectors of the wrong length as invalid arguments.
+pub(super) fn persist_error(err: anyhow::Error) -> Status {
+    match err.downcast_ref::<DimensionMismatch>() {
+        Some(mismatch) => Status::invalid_argument(mismatch.to_string()),
+        None => {
+            tracing::error!(?err, "node upsert failed");
+            Status::internal("failed to persist node")
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_payload(raw: &str) -> Result<serde_json::Value, Status> {
+    if raw.trim().is_empty() {
+        return Ok(serde_json::Value::Null);
+    }
+
+    serde_json::from_str(raw)
+        .map_err(|err| Status::invalid_argument(format!("payload_json is not valid JSON: {}", err)))
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_vector(vector: Vec<f32>) -> Result<Option<Vec<f32>>, Status> {
+    if vector.is_empty() {
+        return Ok(None);
+    }
+    if vector.iter().any(|value| !value.is_finite()) {
+        return Err(Status::invalid_argument(
+            "vector must contain finite values",
+        ));
+    }
+    Ok(Some(vector))
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_props(raw: &str) -> Result<Option<serde_json::Value>, Status> {
+    if raw.trim().is_empty() {
+        return Ok(None);
+    }
+
+    serde_json::from_str(raw)
+        .map(Some)
+        .map_err(|err| Status::invalid_argument(format!("props_json is not valid JSON: {}", err)))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::{
+        page_size, parse_payload, request_tenant_slug, GraphServiceImpl, RequestTenant,
+        TenantInterceptor, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, MAX_TRAVERSAL_HOPS,
+        TENANT_METADATA_KEY,
+    };
+    use crate::config::AppConfig;
+    use crate::domain::node::KnowledgeNode;
+    use crate::pb::convert::{json_to_struct, struct_to_json, timestamp};
+    use crate::pb::synagraph::v1 as pb;
+    use crate::pb::synagraph::v1::graph_service_server::GraphService;
+    use crate::pb::synagraph::v1::query_value::Value;
+    use crate::pb::synagraph::v1::{
+        ActivateRequest, DeleteNodeRequest, Direction, ExpireEdgeRequest, GetEdgeRequest,
+        GetNeighborsRequest, GetNodeRequest, GetSubgraphRequest, LinkNodesRequest,
+        QueryNodesByKindRequest, QueryValue, RunQueryRequest, SearchSimilarRequest,
+        ShortestPathRequest, UnlinkNodesRequest, UpdateEdgeRequest, UpsertNodeRequest,
+    };
+    use crate::repository::{DistanceMetric, RepositoryBundle};
+    use crate::state::{AppContext, DashboardHandle};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use tonic::service::Interceptor;
+    use tonic::{Code, Request};
+    use uuid::Uuid;
+
+    pub(super) fn sample_config(tenant: Uuid) -> AppConfig {
+        AppConfig {
+            http_addr: "127.0.0.1:0".parse().unwrap(),
+            grpc_addr: "127.0.0.1:0".parse().unwrap(),
+            service_name: "synagraph".into(),
+            version: "0.1.0-test".into(),
+            database_url: None,
+            default_tenant_id: tenant,
+            scedge_base_url: None,
+            scedge_event_bus_enabled: false,
+            scedge_event_bus_subject: "scedge:events".into(),
+            tenant_slugs: HashMap::new(),
+            grpc_default_tenant_fallback: true,
+            vector_distance_metric: DistanceMetric::default(),
+        }
+    }
+
+    pub(super) fn tenant_request<T>(tenant: Uuid, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.extensions_mut().insert(RequestTenant(tenant));
+        request
+    }
+
+    fn sample_service(tenant: Uuid) -> (GraphServiceImpl, AppContext) {
+        let ctx = sample_context();
+        (
+            GraphServiceImpl::new(sample_config(tenant), ctx.clone()),
+            ctx,
+        )
+    }
+
+    pub(super) fn sample_context() -> AppContext {
+        let repos = RepositoryBundle::in_memory();
+        let dashboard = DashboardHandle::new();
+        let scedge = crate::scedge::ScedgeBridge::new(None);
+        AppContext::new(repos, dashboard, scedge)
+    }
+
+    #[test]
+    fn parses_valid_json() {
+        let payload = parse_payload("{\"foo\":1}").expect("valid json");
+        assert_eq!(payload["foo"], 1);
+    }
+
+    #[test]
+    fn empty_payload_defaults_to_null() {
+        let payload = parse_payload("   ").expect("empty json");
+        assert!(payload.is_null());
+    }
+
+    #[test]
+    fn invalid_json_errors() {
+        let err = parse_payload("not-json").unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn page_size_applies_default_and_cap() {
+        assert_eq!(page_size(0), DEFAULT_PAGE_SIZE);
+        assert_eq!(page_size(10), 10);
+        assert_eq!(page_size(u32::MAX), MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn tenant_interceptor_accepts_only_configured_tenants() {
+        let default_tenant = Uuid::new_v4();
+        let acme = Uuid::new_v4();
+        let mut cfg = sample_config(default_tenant);
+        cfg.tenant_slugs.insert("acme".into(), acme);
+        let mut interceptor = TenantInterceptor::new(&cfg);
+
+        #[allow(clippy::result_large_err)]
+        let resolve = |interceptor: &mut TenantInterceptor, header: Option<&str>| {
+            let mut request = Request::new(());
+            if let Some(value) = header {
+                request
+                    .metadata_mut()
+                    .insert(TENANT_METADATA_KEY, value.parse().unwrap());
+            }
+            interceptor
+                .call(request)
+                .map(|req| req.extensions().get::<RequestTenant>().copied())
+        };
+
+        assert_eq!(
+            resolve(&mut interceptor, None).unwrap(),
+            Some(RequestTenant(default_tenant))
+        );
+        assert_eq!(
+            resolve(&mut interceptor, Some("acme")).unwrap(),
+            Some(RequestTenant(acme))
+        );
+        assert_eq!(
+            resolve(&mut interceptor, Some(&acme.to_string())).unwrap(),
+            Some(RequestTenant(acme))
+        );
+        assert_eq!(
+            resolve(&mut interceptor, Some(&default_tenant.to_string())).unwrap(),
+            Some(RequestTenant(default_tenant))
+        );
+
+        let err = resolve(&mut interceptor, Some("globex")).unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+        let err = resolve(&mut interceptor, Some(&Uuid::new_v4().to_string())).unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+
+        cfg.grpc_default_tenant_fallback = false;
+        let mut strict = TenantInterceptor::new(&cfg);
+        let err = resolve(&mut strict, None).unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        assert_eq!(
+            resolve(&mut strict, Some("acme")).unwrap(),
+            Some(RequestTenant(acme))
+        );
+    }
+
+    #[test]
+    fn tenant_interceptor_keeps_slug_only_when_named() {
+        let acme = Uuid::new_v4();
+        let mut cfg = sample_config(Uuid::new_v4());
+        cfg.tenant_slugs.insert("acme".into(), acme);
+        let mut interceptor = TenantInterceptor::new(&cfg);
+
+        let mut by_slug = Request::new(());
+        by_slug
+            .metadata_mut()
+            .insert(TENANT_METADATA_KEY, "acme".parse().unwrap());
+        let by_slug = interceptor.call(by_slug).unwrap();
+        assert_eq!(request_tenant_slug(&by_slug), Some("acme"));
+
+        let mut by_id = Request::new(());
+        by_id
+            .metadata_mut()
+            .insert(TENANT_METADATA_KEY, acme.to_string().parse().unwrap());
+        let by_id = interceptor.call(by_id).unwrap();
+        assert_eq!(request_tenant_slug(&by_id), None);
+    }
+
+    #[tokio::test]
+    async fn handlers_require_resolved_tenant() {
+        let (service, _) = sample_service(Uuid::new_v4());
+        let err = service
+            .get_node(Request::new(GetNodeRequest {
+                node_id: Uuid::new_v4().to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Internal);
+    }
+
+    #[tokio::test]
+    async fn upsert_node_persists_and_updates_records() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        let response = service
+            .upsert_node(tenant_request(
+                tenant,
+                UpsertNodeRequest {
+                    node_id: String::new(),
+                    kind: "note".into(),
+                    payload_json: "{\"title\":\"first\"}".into(),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .expect("upsert succeeds")
+            .into_inner();
+
+        assert!(response.created);
+        let node_id = Uuid::parse_str(&response.node_id).expect("valid uuid");
+
+        let stored = ctx
+            .repos
+            .nodes
+            .get(tenant, node_id)
+            .await
+            .expect("get succeeds");
+        assert!(stored.is_some());
+
+        let response_update = service
+            .upsert_node(tenant_request(
+                tenant,
+                UpsertNodeRequest {
+                    node_id: response.node_id.clone(),
+                    kind: "note".into(),
+                    payload_json: "{\"title\":\"updated\"}".into(),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .expect("upsert succeeds")
+            .into_inner();
+
+        assert!(!response_update.created);
+        let stored_updated = ctx
+            .repos
+            .nodes
+            .get(tenant, node_id)
+            .await
+            .expect("get succeeds")
+            .expect("node exists");
+        assert_eq!(stored_updated.payload_json["title"], "updated");
+    }
+
+    #[tokio::test]
+    async fn get_and_delete_node_report_not_found() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        let node = KnowledgeNode::new(tenant, "note", json!({"title": "kept"}));
+        ctx.repos.nodes.upsert(tenant, node.clone()).await.unwrap();
+
+        let fetched = service
+            .get_node(tenant_request(
+                tenant,
+                GetNodeRequest {
+                    node_id: node.id.to_string(),
+                },
+            ))
+            .await
+            .expect("get succeeds")
+            .into_inner()
+            .node
+            .expect("node present");
+        assert_eq!(fetched.node_id, node.id.to_string());
+        assert_eq!(fetched.kind, "note");
+
+        let deleted = service
+            .delete_node(tenant_request(
+                tenant,
+                DeleteNodeRequest {
+                    node_id: node.id.to_string(),
+                },
+            ))
+            .await
+            .expect("delete succeeds")
+            .into_inner();
+        assert!(deleted.node.is_some());
+
+        let err = service
+            .get_node(tenant_request(
+                tenant,
+                GetNodeRequest {
+                    node_id: node.id.to_string(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+
+        let err = service
+            .delete_node(tenant_request(
+                tenant,
+                DeleteNodeRequest {
+                    node_id: node.id.to_string(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+
+        let err = service
+            .get_node(tenant_request(
+                tenant,
+                GetNodeRequest {
+                    node_id: "not-a-uuid".into(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn query_nodes_by_kind_pages_with_cursor() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        for title in ["a", "b", "c"] {
+            let mut node = KnowledgeNode::new(tenant, "note", json!({ "title": title }));
+            node.vector = Some(vec![0.5, 0.5]);
+            ctx.repos.nodes.upsert(tenant, node).await.unwrap();
+        }
+
+        let first = service
+            .query_nodes_by_kind(tenant_request(
+                tenant,
+                QueryNodesByKindRequest {
+                    kind: "note".into(),
+                    limit: 2,
+                    cursor: String::new(),
+                    omit_vectors: false,
+                },
+            ))
+            .await
+            .expect("query succeeds")
+            .into_inner();
+        assert_eq!(first.nodes.len(), 2);
+        assert_eq!(first.nodes[0].vector, vec![0.5, 0.5]);
+        assert!(!first.next_cursor.is_empty());
+
+        let second = service
+            .query_nodes_by_kind(tenant_request(
+                tenant,
+                QueryNodesByKindRequest {
+                    kind: "note".into(),
+                    limit: 2,
+                    cursor: first.next_cursor,
+                    omit_vectors: true,
+                },
+            ))
+            .await
+            .expect("query succeeds")
+            .into_inner();
+        assert_eq!(second.nodes.len(), 1);
+        assert!(second.nodes[0].vector.is_empty());
+        assert!(second.next_cursor.is_empty());
+
+        let err = service
+            .query_nodes_by_kind(tenant_request(
+                tenant,
+                QueryNodesByKindRequest {
+                    kind: "note".into(),
+                    limit: 2,
+                    cursor: Uuid::new_v4().to_string(),
+                    omit_vectors: false,
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+
+        let err = service
+            .query_nodes_by_kind(tenant_request(
+                tenant,
+                QueryNodesByKindRequest {
+                    kind: String::new(),
+                    limit: 0,
+                    cursor: String::new(),
+                    omit_vectors: false,
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn search_similar_scores_by_the_requested_metric() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        let mut near = KnowledgeNode::new(tenant, "note", json!({"title": "near"}));
+        near.vector = Some(vec![1.0, 0.0]);
+        let mut far = KnowledgeNode::new(tenant, "note", json!({"title": "far"}));
+        far.vector = Some(vec![0.0, 1.0]);
+        let mut long = KnowledgeNode::new(tenant, "note", json!({"title": "long"}));
+        long.vector = Some(vec![3.0, 3.0]);
+        ctx.repos.nodes.upsert(tenant, near.clone()).await.unwrap();
+        ctx.repos.nodes.upsert(tenant, far).await.unwrap();
+        ctx.repos.nodes.upsert(tenant, long.clone()).await.unwrap();
+
+        let search = |vector: Vec<f32>, metric: pb::DistanceMetric| {
+            service.search_similar(tenant_request(
+                tenant,
+                SearchSimilarRequest {
+                    vector,
+                    limit: 1,
+                    distance_metric: metric as i32,
+                    filter: None,
+                },
+            ))
+        };
+
+        let hit_id = |response: &pb::SearchSimilarResponse| {
+            response.hits[0].node.as_ref().unwrap().node_id.clone()
+        };
+
+        // Cosine, the configured default, ignores magnitude; the inner product does not.
+        let response = search(vec![1.0, 0.0], pb::DistanceMetric::Unspecified)
+            .await
+            .expect("search succeeds")
+            .into_inner();
+        assert_eq!(response.hits.len(), 1);
+        assert_eq!(hit_id(&response), near.id.to_string());
+        assert_eq!(response.hits[0].score, 1.0);
+        assert_eq!(response.distance_metric, pb::DistanceMetric::Cosine as i32);
+
+        let response = search(vec![1.0, 0.0], pb::DistanceMetric::Dot)
+            .await
+            .expect("search succeeds")
+            .into_inner();
+        assert_eq!(hit_id(&response), long.id.to_string());
+        assert_eq!(response.hits[0].score, 3.0);
+
+        // L2 reports 1 / (1 + distance), so closer still scores higher.
+        let response = search(vec![2.0, 0.0], pb::DistanceMetric::L2)
+            .await
+            .expect("search succeeds")
+            .into_inner();
+        assert_eq!(hit_id(&response), near.id.to_string());
+        assert_eq!(response.hits[0].score, 0.5);
+
+        for (vector, metric) in [
+            (Vec::new(), pb::DistanceMetric::Unspecified),
+            (vec![1.0, 0.0, 0.0], pb::DistanceMetric::L2),
+        ] {
+            let err = search(vector, metric).await.unwrap_err();
+            assert_eq!(err.code(), Code::InvalidArgument);
+        }
+    }
+
+    #[tokio::test]
+    async fn search_similar_ranks_only_nodes_matching_the_filter() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        let mut note = KnowledgeNode::new(tenant, "note", json!({"title": "note"}));
+        note.vector = Some(vec![1.0, 0.0]);
+        let mut capsule = KnowledgeNode::new(tenant, "capsule", json!({"topic": "billing"}));
+        capsule.vector = Some(vec![0.0, 1.0]);
+        capsule.policy = Some(json!({"phi": false, "region": "eu", "compliance_tags": ["gdpr"]}));
+        let mut sensitive = KnowledgeNode::new(tenant, "capsule", json!({"topic": "billing"}));
+        sensitive.vector = Some(vec![1.0, 0.1]);
+        sensitive.policy = Some(json!({"phi": true, "region": "eu"}));
+        for node in [note, capsule.clone(), sensitive] {
+            ctx.repos.nodes.upsert(tenant, node).await.unwrap();
+        }
+
+        let response = service
+            .search_similar(tenant_request(
+                tenant,
+                SearchSimilarRequest {
+                    vector: vec![1.0, 0.0],
+                    limit: 0,
+                    distance_metric: 0,
+                    filter: Some(pb::SimilarityFilter {
+                        kind: "capsule".into(),
+                        phi: Some(false),
+                        region: "eu".into(),
+                        compliance_tags: vec!["gdpr".into()],
+                        payload: json_to_struct(&json!({"topic": "billing"})),
+                        ..Default::default()
+                    }),
+                },
+            ))
+            .await
+            .expect("search succeeds")
+            .into_inner();
+        let ids: Vec<String> = response
+            .hits
+            .iter()
+            .map(|hit| hit.node.as_ref().unwrap().node_id.clone())
+            .collect();
+        assert_eq!(ids, vec![capsule.id.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn link_nodes_and_get_neighbors_return_edges() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        let source = KnowledgeNode::new(tenant, "note", json!({"title": "source"}));
+        let target = KnowledgeNode::new(tenant, "note", json!({"title": "target"}));
+        ctx.repos
+            .nodes
+            .upsert(tenant, source.clone())
+            .await
+            .unwrap();
+        ctx.repos
+            .nodes
+            .upsert(tenant, target.clone())
+            .await
+            .unwrap();
+
+        let linked = service
+            .link_nodes(tenant_request(
+                tenant,
+                LinkNodesRequest {
+                    src_id: source.id.to_string(),
+                    dst_id: target.id.to_string(),
+                    rel: "cites".into(),
+                    weight: Some(0.5),
+                    props_json: "{\"page\":3}".into(),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .expect("link succeeds")
+            .into_inner();
+        assert!(Uuid::parse_str(&linked.edge_id).is_ok());
+
+        let response = service
+            .get_neighbors(tenant_request(
+                tenant,
+                GetNeighborsRequest {
+                    node_id: source.id.to_string(),
+                    rel: "cites".into(),
+                    hops: 1,
+                    limit: 10,
+                    ..Default::default()
+                },
+            ))
+            .await
+            .expect("neighbors succeeds")
+            .into_inner();
+        assert_eq!(response.nodes.len(), 1);
+        assert_eq!(response.nodes[0].node_id, target.id.to_string());
+        assert_eq!(response.edges.len(), 1);
+        assert_eq!(response.edges[0].edge_id, linked.edge_id);
+        assert_eq!(response.edges[0].weight, 0.5);
+        assert_eq!(response.edges[0].props_json, "{\"page\":3}");
+
+        let incoming = service
+            .get_neighbors(tenant_request(
+                tenant,
+                GetNeighborsRequest {
+                    node_id: target.id.to_string(),
+                    direction: Direction::Incoming as i32,
+                    ..Default::default()
+                },
+            ))
+            .await
+            .expect("incoming neighbors succeeds")
+            .into_inner();
+        assert_eq!(incoming.nodes[0].node_id, source.id.to_string());
+
+        let unknown = service
+            .get_neighbors(tenant_request(
+                tenant,
+                GetNeighborsRequest {
+                    node_id: target.id.to_string(),
+                    direction: 42,
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(unknown.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn edges_can_be_relinked_updated_and_unlinked() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+        let source = KnowledgeNode::new(tenant, "note", json!({}));
+        let target = KnowledgeNode::new(tenant, "note", json!({}));
+        for node in [&source, &target] {
+            ctx.repos.nodes.upsert(tenant, node.clone()).await.unwrap();
+        }
+        let link = || {
+            tenant_request(
+                tenant,
+                LinkNodesRequest {
+                    src_id: source.id.to_string(),
+                    dst_id: target.id.to_string(),
+                    rel: "cites".into(),
+                    ..Default::default()
+                },
+            )
+        };
+
+        let first = service.link_nodes(link()).await.unwrap().into_inner();
+        let second = service.link_nodes(link()).await.unwrap().into_inner();
+        assert!(first.created);
+        assert!(!second.created);
+        assert_eq!(first.edge_id, second.edge_id);
+
+        let updated = service
+            .update_edge(tenant_request(
+                tenant,
+                UpdateEdgeRequest {
+                    edge_id: first.edge_id.clone(),
+                    weight: Some(0.25),
+                    props_json: "{\"page\":7}".into(),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .edge
+            .unwrap();
+        assert_eq!(updated.weight, 0.25);
+        assert_eq!(updated.props_json, "{\"page\":7}");
+
+        let fetched = service
+            .get_edge(tenant_request(
+                tenant,
+                GetEdgeRequest {
+                    edge_id: first.edge_id.clone(),
+                },
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .edge
+            .unwrap();
+        assert_eq!(fetched.weight, 0.25);
+
+        let unlink = || {
+            tenant_request(
+                tenant,
+                UnlinkNodesRequest {
+                    edge_id: first.edge_id.clone(),
+                },
+            )
+        };
+        let removed = service.unlink_nodes(unlink()).await.unwrap().into_inner();
+        assert_eq!(removed.edge.unwrap().edge_id, first.edge_id);
+        let gone = service.unlink_nodes(unlink()).await.unwrap_err();
+        assert_eq!(gone.code(), Code::NotFound);
+
+        let bad_weight = service
+            .update_edge(tenant_request(
+                tenant,
+                UpdateEdgeRequest {
+                    edge_id: first.edge_id.clone(),
+                    weight: Some(f32::NAN),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(bad_weight.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn expired_edges_are_only_followed_as_of_earlier_times() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+        let source = KnowledgeNode::new(tenant, "note", json!({}));
+        let target = KnowledgeNode::new(tenant, "note", json!({}));
+        for node in [&source, &target] {
+            ctx.repos.nodes.upsert(tenant, node.clone()).await.unwrap();
+        }
+        let linked = service
+            .link_nodes(tenant_request(
+                tenant,
+                LinkNodesRequest {
+                    src_id: source.id.to_string(),
+                    dst_id: target.id.to_string(),
+                    rel: "cites".into(),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        let edge = ctx
+            .repos
+            .edges
+            .get_edge(tenant, linked.edge_id.parse().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let expire = |at: chrono::DateTime<chrono::Utc>| {
+            tenant_request(
+                tenant,
+                ExpireEdgeRequest {
+                    edge_id: linked.edge_id.clone(),
+                    at: Some(timestamp(at)),
+                },
+            )
+        };
+        let early = service
+            .expire_edge(expire(edge.valid_from - chrono::Duration::seconds(1)))
+            .await
+            .unwrap_err();
+        assert_eq!(early.code(), Code::InvalidArgument);
+        let end = edge.valid_from + chrono::Duration::seconds(30);
+        let expired = service
+            .expire_edge(expire(end))
+            .await
+            .unwrap()
+            .into_inner()
+            .edge
+            .unwrap();
+        assert_eq!(expired.valid_to, Some(timestamp(end)));
+
+        let neighbors = |as_of: Option<chrono::DateTime<chrono::Utc>>| {
+            tenant_request(
+                tenant,
+                GetNeighborsRequest {
+                    node_id: source.id.to_string(),
+                    as_of: as_of.map(timestamp),
+                    ..Default::default()
+                },
+            )
+        };
+        let before_end = service
+            .get_neighbors(neighbors(Some(end - chrono::Duration::seconds(1))))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(before_end.edges.len(), 1);
+        let at_end = service
+            .get_neighbors(neighbors(Some(end)))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(at_end.edges.is_empty());
+
+        let missing = service
+            .expire_edge(tenant_request(
+                tenant,
+                ExpireEdgeRequest {
+                    edge_id: Uuid::new_v4().to_string(),
+                    at: None,
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn shortest_path_returns_ordered_hops() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        let nodes: Vec<_> = (0..3)
+            .map(|step| KnowledgeNode::new(tenant, "note", json!({ "step": step })))
+            .collect();
+        for node in &nodes {
+            ctx.repos.nodes.upsert(tenant, node.clone()).await.unwrap();
+        }
+        for pair in nodes.windows(2) {
+            ctx.repos
+                .edges
+                .link(tenant, pair[0].id, pair[1].id, "next", 0.5, None)
+                .await
+                .unwrap();
+        }
+
+        let request = |from: &KnowledgeNode, to: &KnowledgeNode, max_hops| {
+            tenant_request(
+                tenant,
+                ShortestPathRequest {
+                    from_id: from.id.to_string(),
+                    to_id: to.id.to_string(),
+                    max_hops,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let path = service
+            .shortest_path(request(&nodes[0], &nodes[2], 0))
+            .await
+            .expect("path succeeds")
+            .into_inner();
+        assert!(path.found);
+        let ids: Vec<_> = path.nodes.iter().map(|node| node.node_id.clone()).collect();
+        let expected: Vec<_> = nodes.iter().map(|node| node.id.to_string()).collect();
+        assert_eq!(ids, expected);
+        assert_eq!(path.edges.len(), 2);
+        assert_eq!(path.cost, 4.0);
+
+        let too_short = service
+            .shortest_path(request(&nodes[0], &nodes[2], 1))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!too_short.found);
+        assert!(too_short.nodes.is_empty());
+
+        let too_long = service
+            .shortest_path(request(&nodes[0], &nodes[2], MAX_TRAVERSAL_HOPS + 1))
+            .await
+            .unwrap_err();
+        assert_eq!(too_long.code(), Code::InvalidArgument);
+
+        let stranger = KnowledgeNode::new(tenant, "note", json!({}));
+        let missing = service
+            .shortest_path(request(&nodes[0], &stranger, 0))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn get_subgraph_returns_induced_edges_and_caps() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        let nodes: Vec<_> = ["topic", "note", "person"]
+            .into_iter()
+            .map(|kind| KnowledgeNode::new(tenant, kind, json!({})))
+            .collect();
+        for node in &nodes {
+            ctx.repos.nodes.upsert(tenant, node.clone()).await.unwrap();
+        }
+        for (src, dst) in [(0, 1), (0, 2), (1, 2)] {
+            ctx.repos
+                .edges
+                .link(tenant, nodes[src].id, nodes[dst].id, "about", 1.0, None)
+                .await
+                .unwrap();
+        }
+
+        let request = |kinds: Vec<String>, max_nodes| {
+            tenant_request(
+                tenant,
+                GetSubgraphRequest {
+                    seed_ids: vec![nodes[0].id.to_string()],
+                    kinds,
+                    max_nodes,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let full = service
+            .get_subgraph(request(Vec::new(), 0))
+            .await
+            .expect("subgraph succeeds")
+            .into_inner();
+        assert_eq!(full.nodes.len(), 3);
+        assert_eq!(full.edges.len(), 3);
+        assert!(!full.truncated);
+
+        let notes = service
+            .get_subgraph(request(vec!["note".into()], 0))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(notes.nodes.len(), 2);
+        assert_eq!(notes.edges.len(), 1);
+
+        let capped = service
+            .get_subgraph(request(Vec::new(), 1))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(capped.nodes.len(), 1);
+        assert!(capped.edges.is_empty());
+        assert!(capped.truncated);
+
+        let invalid = service
+            .get_subgraph(tenant_request(tenant, GetSubgraphRequest::default()))
+            .await
+            .unwrap_err();
+        assert_eq!(invalid.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn activate_ranks_reached_nodes_and_validates_decay() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        let nodes: Vec<_> = (0..4)
+            .map(|step| KnowledgeNode::new(tenant, "note", json!({ "step": step })))
+            .collect();
+        for node in &nodes {
+            ctx.repos.nodes.upsert(tenant, node.clone()).await.unwrap();
+        }
+        for (dst, weight) in [(1, 3.0), (2, 1.0)] {
+            ctx.repos
+                .edges
+                .link(tenant, nodes[0].id, nodes[dst].id, "rel", weight, None)
+                .await
+                .unwrap();
+        }
+
+        let request = |decay, include_seeds| {
+            tenant_request(
+                tenant,
+                ActivateRequest {
+                    seed_ids: vec![nodes[0].id.to_string()],
+                    decay,
+                    include_seeds,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let ranked = service
+            .activate(request(0.0, false))
+            .await
+            .expect("activation succeeds")
+            .into_inner();
+        let ids: Vec<_> = ranked
+            .nodes
+            .iter()
+            .map(|n| n.node.as_ref().unwrap().node_id.clone())
+            .collect();
+        assert_eq!(ids, vec![nodes[1].id.to_string(), nodes[2].id.to_string()]);
+        assert_eq!(ranked.nodes[0].score, 0.375);
+
+        let with_seed = service
+            .activate(request(0.8, true))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(with_seed.nodes.len(), 3);
+        assert_eq!(with_seed.nodes[0].score, 1.0);
+
+        let invalid = service.activate(request(1.5, false)).await.unwrap_err();
+        assert_eq!(invalid.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn run_query_returns_nodes_and_edges_per_column() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        let source = KnowledgeNode::new(tenant, "source", json!({ "region": "EU" }));
+        let capsule = KnowledgeNode::new(tenant, "capsule", json!({}));
+        for node in [&source, &capsule] {
+            ctx.repos.nodes.upsert(tenant, node.clone()).await.unwrap();
+        }
+        ctx.repos
+            .edges
+            .link(tenant, capsule.id, source.id, "derived_from", 1.0, None)
+            .await
+            .unwrap();
+
+        let request = |query: &str| {
+            tenant_request(
+                tenant,
+                RunQueryRequest {
+                    query: query.into(),
+                    as_of: None,
+                },
+            )
+        };
+
+        let result = service
+            .run_query(request(
+                "MATCH (c:capsule)-[r]->(s) WHERE s.payload.region = 'EU' RETURN c, r",
+            ))
+            .await
+            .expect("query succeeds")
+            .into_inner();
+        assert_eq!(result.columns, vec!["c", "r"]);
+        assert_eq!(result.rows.len(), 1);
+        match &result.rows[0].values[..] {
+            [QueryValue {
+                value: Some(Value::Node(node)),
+            }, QueryValue {
+                value: Some(Value::Edge(edge)),
+            }] => {
+                assert_eq!(node.node_id, capsule.id.to_string());
+                assert_eq!(edge.rel, "derived_from");
+            }
+            other => panic!("unexpected row {other:?}"),
+        }
+
+        let invalid = service
+            .run_query(request("MATCH (c) WHERE c.weight = 1 RETURN c"))
+            .await
+            .unwrap_err();
+        assert_eq!(invalid.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn link_nodes_validates_arguments() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        let source = KnowledgeNode::new(tenant, "note", json!({}));
+        ctx.repos
+            .nodes
+            .upsert(tenant, source.clone())
+            .await
+            .unwrap();
+
+        let missing_target = service
+            .link_nodes(tenant_request(
+                tenant,
+                LinkNodesRequest {
+                    src_id: source.id.to_string(),
+                    dst_id: Uuid::new_v4().to_string(),
+                    rel: "cites".into(),
+                    weight: None,
+                    props_json: String::new(),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(missing_target.code(), Code::NotFound);
+
+        let missing_rel = service
+            .link_nodes(tenant_request(
+                tenant,
+                LinkNodesRequest {
+                    src_id: source.id.to_string(),
+                    dst_id: source.id.to_string(),
+                    rel: " ".into(),
+                    weight: None,
+                    props_json: String::new(),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(missing_rel.code(), Code::InvalidArgument);
+
+        let too_many_hops = service
+            .get_neighbors(tenant_request(
+                tenant,
+                GetNeighborsRequest {
+                    node_id: source.id.to_string(),
+                    rel: String::new(),
+                    hops: 99,
+                    limit: 0,
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(too_many_hops.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn upsert_node_accepts_structured_fields() {
+        let tenant = Uuid::new_v4();
+        let (service, ctx) = sample_service(tenant);
+
+        let response = service
+            .upsert_node(tenant_request(
+                tenant,
+                UpsertNodeRequest {
+                    kind: "note".into(),
+                    payload: json_to_struct(&json!({"title": "typed", "pages": 3})),
+                    vector: vec![0.25, 0.75],
+                    provenance: json_to_struct(&json!({"source": "crawler"})),
+                    policy: json_to_struct(&json!({"pii": false})),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .expect("upsert succeeds")
+            .into_inner();
+        let node_id = Uuid::parse_str(&response.node_id).unwrap();
+
+        let stored = ctx
+            .repos
+            .nodes
+            .get(tenant, node_id)
+            .await
+            .unwrap()
+            .expect("node exists");
+        assert_eq!(stored.payload_json, json!({"title": "typed", "pages": 3}));
+        assert_eq!(stored.vector, Some(vec![0.25, 0.75]));
+        assert_eq!(stored.provenance, Some(json!({"source": "crawler"})));
+        assert_eq!(stored.policy, Some(json!({"pii": false})));
+
+        let fetched = service
+            .get_node(tenant_request(
+                tenant,
+                GetNodeRequest {
+                    node_id: response.node_id,
+                },
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .node
+            .unwrap();
+        assert_eq!(
+            struct_to_json(&fetched.payload.unwrap()),
+            json!({"title": "typed", "pages": 3})
+        );
+        assert_eq!(fetched.vector, vec![0.25, 0.75]);
+        assert!(fetched.provenance.is_some());
+    }
+
+    #[tokio::test]
+    async fn upsert_node_rejects_conflicting_payload_fields() {
+        let tenant = Uuid::new_v4();
+        let (service, _) = sample_service(tenant);
+
+        let err = service
+            .upsert_node(tenant_request(
+                tenant,
+                UpsertNodeRequest {
+                    kind: "note".into(),
+                    payload_json: "{}".into(),
+                    payload: json_to_struct(&json!({})),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+
+        let err = service
+            .upsert_node(tenant_request(
+                tenant,
+                UpsertNodeRequest {
+                    kind: "note".into(),
+                    vector: vec![f32::NAN],
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn upsert_node_rejects_vectors_of_another_length() {
+        let tenant = Uuid::new_v4();
+        let (service, _) = sample_service(tenant);
+        let upsert = |vector: Vec<f32>| {
+            service.upsert_node(tenant_request(
+                tenant,
+                UpsertNodeRequest {
+                    kind: "note".into(),
+                    vector,
+                    ..Default::default()
+                },
+            ))
+        };
+
+        upsert(vec![1.0, 0.0]).await.unwrap();
+        let err = upsert(vec![1.0, 0.0, 0.0]).await.unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert_eq!(
+            err.message(),
+            "vector has 3 values but stored vectors have 2"
+        );
     }
 }
@@ -0,0 +1,525 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// Streaming bulk loader behind `BulkUpsertNodes`: batches node and edge writes, one outcome per
+// item.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tonic::{Status, Streaming};
+use uuid::Uuid;
+
+use super::{link_from_request, node_from_request, persist_error, storage_error, GraphServiceImpl};
+use crate::domain::node::KnowledgeNode;
+use crate::pb::synagraph::v1::bulk_upsert_nodes_request::Item;
+use crate::pb::synagraph::v1::bulk_upsert_nodes_response::Outcome;
+use crate::pb::synagraph::v1::{BulkUpsertNodesRequest, BulkUpsertNodesResponse};
+use crate::repository::{EdgeLink, UpsertOutcome};
+
+/// Maximum number of items, nodes and edges together, written per flush.
+const BATCH_SIZE: usize = 256;
+/// How long a partial batch waits for more items before it is written anyway.
+const BATCH_LINGER: Duration = Duration::from_millis(10);
+/// Responses buffered ahead of a slow reader before the loader stops pulling requests.
+pub(super) const RESPONSE_BUFFER: usize = 256;
+
+type ResponseSender = mpsc::Sender<Result<BulkUpsertNodesResponse, Status>>;
+
+/// Drives one `BulkUpsertNodes` stream to completion. Responses go through a bounded channel, so
+/// a client that stops reading results also stops the server from reading further requests,
+/// which in turn applies HTTP/2 flow control to the sender.
+pub(super) async fn run(
+    service: GraphServiceImpl,
+    tenant: Uuid,
+    mut inbound: Streaming<BulkUpsertNodesRequest>,
+    tx: ResponseSender,
+) {
+    let mut loader = BulkUpsert::new(service, tenant);
+    let mut index = 0;
+
+    loop {
+        let next = if loader.has_pending() {
+            match tokio::time::timeout(BATCH_LINGER, inbound.message()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    let ready = loader.flush().await;
+                    if !send_all(&tx, ready).await {
+                        break;
+                    }
+                    continue;
+                }
+            }
+        } else {
+            inbound.message().await
+        };
+
+        match next {
+            Ok(Some(item)) => {
+                let ready = loader.push(index, item).await;
+                index += 1;
+                if !send_all(&tx, ready).await {
+                    break;
+                }
+            }
+            Ok(None) => {
+                let ready = loader.flush().await;
+                send_all(&tx, ready).await;
+                break;
+            }
+            Err(status) => {
+                tracing::warn!(?status, "bulk upsert stream ended with an error");
+                let ready = loader.flush().await;
+                if send_all(&tx, ready).await {
+                    let _ = tx.send(Err(status)).await;
+                }
+                break;
+            }
+        }
+    }
+
+    loader.finish();
+}
+
+/// Returns `false` once the client has gone away.
+async fn send_all(tx: &ResponseSender, responses: Vec<BulkUpsertNodesResponse>) -> bool {
+    for response in responses {
+        if tx.send(Ok(response)).await.is_err() {
+            tracing::debug!("bulk upsert client disconnected");
+            return false;
+        }
+    }
+    true
+}
+
+/// Accumulates nodes and edges into batches while preserving request order in the emitted
+/// responses.
+struct BulkUpsert {
+    service: GraphServiceImpl,
+    tenant: Uuid,
+    /// Nodes to write, plus items rejected before reaching storage.
+    nodes: Vec<(u64, Result<KnowledgeNode, Status>)>,
+    edges: Vec<(u64, Result<EdgeLink, Status>)>,
+    created: u64,
+    updated: u64,
+    failed: u64,
+}
+
+impl BulkUpsert {
+    fn new(service: GraphServiceImpl, tenant: Uuid) -> Self {
+        Self {
+            service,
+            tenant,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            created: 0,
+            updated: 0,
+            failed: 0,
+        }
+    }
+
+    fn has_pending(&self) -> bool {
+        !self.nodes.is_empty() || !self.edges.is_empty()
+    }
+
+    /// Accepts the item at `index` and returns any responses that became ready. Edges wait in
+    /// their own batch, which is written after the node batch so they can reference nodes sent
+    /// earlier in the same stream.
+    async fn push(
+        &mut self,
+        index: u64,
+        request: BulkUpsertNodesRequest,
+    ) -> Vec<BulkUpsertNodesResponse> {
+        match request.item {
+            Some(Item::Node(node)) => {
+                let node = node_from_request(self.tenant, node);
+                self.nodes.push((index, node));
+            }
+            Some(Item::Edge(edge)) => self.edges.push((index, link_from_request(edge))),
+            None => {
+                let missing = Status::invalid_argument("item is required");
+                self.nodes.push((index, Err(missing)));
+            }
+        }
+        if self.nodes.len() + self.edges.len() >= BATCH_SIZE {
+            self.flush().await
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Writes the pending nodes, then the pending edges, and returns the responses in request
+    /// order.
+    async fn flush(&mut self) -> Vec<BulkUpsertNodesResponse> {
+        let mut ready = self.flush_nodes().await;
+        ready.extend(self.flush_edges().await);
+        ready.sort_by_key(|response| response.index);
+        ready
+    }
+
+    /// Writes the node batch. When the batch write fails, nodes are retried one at a time so only
+    /// the offending records are reported as errors.
+    async fn flush_nodes(&mut self) -> Vec<BulkUpsertNodesResponse> {
+        let pending = std::mem::take(&mut self.nodes);
+        let nodes: Vec<KnowledgeNode> = pending
+            .iter()
+            .filter_map(|(_, node)| node.as_ref().ok().cloned())
+            .collect();
+
+        let mut outcomes = if nodes.is_empty() {
+            Vec::new()
+        } else {
+            let repos = &self.service.ctx.repos;
+            match repos.nodes.upsert_batch(self.tenant, nodes.clone()).await {
+                Ok(outcomes) => outcomes.into_iter().map(Ok).collect(),
+                Err(err) => {
+                    tracing::warn!(?err, "bulk node batch failed; retrying individually");
+                    let mut outcomes = Vec::with_capacity(nodes.len());
+                    for node in nodes {
+                        let outcome = repos.nodes.upsert(self.tenant, node).await;
+                        outcomes.push(outcome.map_err(persist_error));
+                    }
+                    outcomes
+                }
+            }
+        }
+        .into_iter();
+
+        pending
+            .into_iter()
+            .map(|(index, node)| {
+                #[allow(clippy::result_large_err)]
+                let result = node.and_then(|node| {
+                    outcomes
+                        .next()
+                        .expect("one outcome per valid node")
+                        .map(|outcome| (node.id, outcome))
+                });
+                match result {
+                    Ok((id, outcome)) => self.written(index, id, outcome),
+                    Err(status) => self.failed(index, &status),
+                }
+            })
+            .collect()
+    }
+
+    /// Writes the edge batch. Edges naming a node that does not exist are rejected up front with
+    /// one lookup for the whole batch; if the batch write still fails, edges are retried one at a
+    /// time.
+    async fn flush_edges(&mut self) -> Vec<BulkUpsertNodesResponse> {
+        let mut pending = std::mem::take(&mut self.edges);
+        let repos = &self.service.ctx.repos;
+
+        let mut endpoints: Vec<Uuid> = pending
+            .iter()
+            .filter_map(|(_, link)| link.as_ref().ok())
+            .flat_map(|link| [link.src, link.dst])
+            .collect();
+        endpoints.sort();
+        endpoints.dedup();
+        if !endpoints.is_empty() {
+            let found = match repos.nodes.get_many(self.tenant, &endpoints).await {
+                Ok(found) => found,
+                Err(err) => {
+                    let status = storage_error("node lookup", err);
+                    return pending
+                        .into_iter()
+                        .map(|(index, _)| self.failed(index, &status))
+                        .collect();
+                }
+            };
+            let found: HashSet<Uuid> = found.into_iter().map(|node| node.id).collect();
+            for (_, link) in &mut pending {
+                let missing = match link {
+                    Ok(link) => [link.src, link.dst]
+                        .into_iter()
+                        .find(|id| !found.contains(id)),
+                    Err(_) => None,
+                };
+                if let Some(id) = missing {
+                    *link = Err(Status::not_found(format!("node {id} not found")));
+                }
+            }
+        }
+
+        let links: Vec<EdgeLink> = pending
+            .iter()
+            .filter_map(|(_, link)| link.as_ref().ok().cloned())
+            .collect();
+        let mut linked = if links.is_empty() {
+            Vec::new()
+        } else {
+            match repos.edges.link_batch(self.tenant, links.clone()).await {
+                Ok(linked) => linked.into_iter().map(Ok).collect(),
+                Err(err) => {
+                    tracing::warn!(?err, "bulk edge batch failed; retrying individually");
+                    let mut linked = Vec::with_capacity(links.len());
+                    for link in links {
+                        linked.push(self.service.link(self.tenant, link).await);
+                    }
+                    linked
+                }
+            }
+        }
+        .into_iter();
+
+        pending
+            .into_iter()
+            .map(|(index, link)| {
+                #[allow(clippy::result_large_err)]
+                let result = link.and_then(|_| linked.next().expect("one result per valid edge"));
+                match result {
+                    Ok(linked) => self.written(index, linked.id, linked.outcome),
+                    Err(status) => self.failed(index, &status),
+                }
+            })
+            .collect()
+    }
+
+    fn written(&mut self, index: u64, id: Uuid, outcome: UpsertOutcome) -> BulkUpsertNodesResponse {
+        let outcome = match outcome {
+            UpsertOutcome::Created => {
+                self.created += 1;
+                Outcome::Created
+            }
+            UpsertOutcome::Updated => {
+                self.updated += 1;
+                Outcome::Updated
+            }
+        };
+        BulkUpsertNodesResponse {
+            index,
+            outcome: outcome as i32,
+            id: id.to_string(),
+            error: String::new(),
+        }
+    }
+
+    fn failed(&mut self, index: u64, status: &Status) -> BulkUpsertNodesResponse {
+        self.failed += 1;
+        BulkUpsertNodesResponse {
+            index,
+            outcome: Outcome::Error as i32,
+            id: String::new(),
+            error: status.message().to_string(),
+        }
+    }
+
+    fn finish(self) {
+        tracing::info!(
+            tenant = %self.tenant,
+            created = self.created,
+            updated = self.updated,
+            failed = self.failed,
+            "bulk upsert finished"
+        );
+        self.service.ctx.dashboard.record_bulk_store(
+            self.tenant,
+            self.created,
+            self.updated,
+            self.failed,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BulkUpsert, BATCH_SIZE};
+    use crate::domain::node::KnowledgeNode;
+    use crate::pb::synagraph::v1::bulk_upsert_nodes_request::Item;
+    use crate::pb::synagraph::v1::bulk_upsert_nodes_response::Outcome;
+    use crate::pb::synagraph::v1::{BulkUpsertNodesRequest, LinkNodesRequest, UpsertNodeRequest};
+    use crate::repository::TraversalFilter;
+    use crate::server::grpc::tests::{sample_config, sample_context};
+    use crate::server::grpc::GraphServiceImpl;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn loader() -> (BulkUpsert, Uuid) {
+        let tenant = Uuid::new_v4();
+        let service = GraphServiceImpl::new(sample_config(tenant), sample_context());
+        (BulkUpsert::new(service, tenant), tenant)
+    }
+
+    fn node_item(node_id: &str, payload_json: &str) -> BulkUpsertNodesRequest {
+        BulkUpsertNodesRequest {
+            item: Some(Item::Node(UpsertNodeRequest {
+                node_id: node_id.into(),
+                kind: "note".into(),
+                payload_json: payload_json.into(),
+                ..Default::default()
+            })),
+        }
+    }
+
+    #[tokio::test]
+    async fn batches_nodes_and_reports_bad_records_in_order() {
+        let (mut loader, tenant) = loader();
+        let existing = KnowledgeNode::new(tenant, "note", json!({}));
+        loader
+            .service
+            .ctx
+            .repos
+            .nodes
+            .upsert(tenant, existing.clone())
+            .await
+            .unwrap();
+
+        assert!(loader.push(0, node_item("", "{}")).await.is_empty());
+        assert!(loader.push(1, node_item("", "not-json")).await.is_empty());
+        assert!(loader
+            .push(2, node_item(&existing.id.to_string(), "{\"v\":2}"))
+            .await
+            .is_empty());
+
+        let responses = loader.flush().await;
+        let outcomes: Vec<_> = responses
+            .iter()
+            .map(|response| (response.index, response.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (0, Outcome::Created as i32),
+                (1, Outcome::Error as i32),
+                (2, Outcome::Updated as i32),
+            ]
+        );
+        assert!(responses[1].error.contains("not valid JSON"));
+        assert_eq!(responses[2].id, existing.id.to_string());
+        assert_eq!((loader.created, loader.updated, loader.failed), (1, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn vectors_of_another_length_fail_only_their_record() {
+        let (mut loader, _) = loader();
+        let node = |vector: Vec<f32>| BulkUpsertNodesRequest {
+            item: Some(Item::Node(UpsertNodeRequest {
+                kind: "note".into(),
+                vector,
+                ..Default::default()
+            })),
+        };
+
+        assert!(loader.push(0, node(vec![1.0, 0.0])).await.is_empty());
+        assert!(loader.push(1, node(vec![1.0])).await.is_empty());
+        assert!(loader.push(2, node(vec![0.0, 1.0])).await.is_empty());
+
+        let responses = loader.flush().await;
+        let outcomes: Vec<_> = responses
+            .iter()
+            .map(|response| (response.index, response.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (0, Outcome::Created as i32),
+                (1, Outcome::Error as i32),
+                (2, Outcome::Created as i32),
+            ]
+        );
+        assert_eq!(
+            responses[1].error,
+            "vector has 1 values but stored vectors have 2"
+        );
+    }
+
+    #[tokio::test]
+    async fn full_batch_flushes_immediately() {
+        let (mut loader, _) = loader();
+        for index in 0..BATCH_SIZE as u64 - 1 {
+            assert!(loader.push(index, node_item("", "{}")).await.is_empty());
+        }
+        let responses = loader
+            .push(BATCH_SIZE as u64 - 1, node_item("", "{}"))
+            .await;
+        assert_eq!(responses.len(), BATCH_SIZE);
+        assert!(!loader.has_pending());
+    }
+
+    #[tokio::test]
+    async fn edges_are_batched_after_the_nodes_they_reference() {
+        let (mut loader, tenant) = loader();
+        let src = Uuid::new_v4().to_string();
+        let dst = Uuid::new_v4().to_string();
+        let edge = |dst_id: &str| BulkUpsertNodesRequest {
+            item: Some(Item::Edge(LinkNodesRequest {
+                src_id: src.clone(),
+                dst_id: dst_id.into(),
+                rel: "cites".into(),
+                ..Default::default()
+            })),
+        };
+
+        assert!(loader.push(0, node_item(&src, "{}")).await.is_empty());
+        assert!(loader.push(1, edge(&dst)).await.is_empty());
+        // The edge's target arrives after it, but still in the same batch.
+        assert!(loader.push(2, node_item(&dst, "{}")).await.is_empty());
+        assert!(loader
+            .push(3, edge(&Uuid::new_v4().to_string()))
+            .await
+            .is_empty());
+        assert!(loader
+            .push(4, BulkUpsertNodesRequest { item: None })
+            .await
+            .is_empty());
+        assert_eq!((loader.nodes.len(), loader.edges.len()), (3, 2));
+
+        let responses = loader.flush().await;
+        let outcomes: Vec<_> = responses
+            .iter()
+            .map(|response| (response.index, response.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (0, Outcome::Created as i32),
+                (1, Outcome::Created as i32),
+                (2, Outcome::Created as i32),
+                (3, Outcome::Error as i32),
+                (4, Outcome::Error as i32),
+            ]
+        );
+        assert!(responses[3].error.contains("not found"));
+
+        let neighbors = loader
+            .service
+            .ctx
+            .repos
+            .edges
+            .neighbors(
+                tenant,
+                Uuid::parse_str(&src).unwrap(),
+                &TraversalFilter::default(),
+                1,
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(neighbors.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn repeated_edges_in_a_batch_update_the_first() {
+        let (mut loader, _) = loader();
+        let src = Uuid::new_v4().to_string();
+        let dst = Uuid::new_v4().to_string();
+        loader.push(0, node_item(&src, "{}")).await;
+        loader.push(1, node_item(&dst, "{}")).await;
+        for index in 2..4 {
+            let edge = BulkUpsertNodesRequest {
+                item: Some(Item::Edge(LinkNodesRequest {
+                    src_id: src.clone(),
+                    dst_id: dst.clone(),
+                    rel: "cites".into(),
+                    ..Default::default()
+                })),
+            };
+            loader.push(index, edge).await;
+        }
+
+        let responses = loader.flush().await;
+        assert_eq!(responses[2].outcome, Outcome::Created as i32);
+        assert_eq!(responses[3].outcome, Outcome::Updated as i32);
+        assert_eq!(responses[2].id, responses[3].id);
+    }
+}
@@ -0,0 +1,304 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// CapsuleService exposes the Scedge capsule contract over gRPC, sharing storage and event
+// semantics with the HTTP `/ingest/capsule`, `/lookup`, and `/capsules/purge` endpoints.
+
+use tonic::{Request, Response, Status};
+
+use super::{request_tenant, request_tenant_slug, storage_error};
+use crate::config::AppConfig;
+use crate::domain::capsule::{self, CapsuleError, CapsuleEvents};
+use crate::pb::convert::{capsule_lookup_to_proto, capsule_request_from_proto};
+use crate::pb::synagraph::v1::capsule_service_server::CapsuleService;
+use crate::pb::synagraph::v1::{
+    LookupCapsuleRequest, LookupCapsuleResponse, PurgeCapsulesRequest, PurgeCapsulesResponse,
+    StoreCapsuleRequest, StoreCapsuleResponse,
+};
+use crate::repository::UpsertOutcome;
+use crate::state::AppContext;
+
+#[derive(Clone)]
+pub(super) struct CapsuleServiceImpl {
+    ctx: AppContext,
+    events: Option<CapsuleEvents>,
+}
+
+impl CapsuleServiceImpl {
+    pub(super) fn new(cfg: &AppConfig, ctx: AppContext) -> Self {
+        let events = CapsuleEvents::from_config(cfg, ctx.repos.bus.clone());
+        Self { ctx, events }
+    }
+}
+
+#[tonic::async_trait]
+impl CapsuleService for CapsuleServiceImpl {
+    async fn store_capsule(
+        &self,
+        request: Request<StoreCapsuleRequest>,
+    ) -> Result<Response<StoreCapsuleResponse>, Status> {
+        let tenant = request_tenant(&request)?;
+        let slug = request_tenant_slug(&request).map(str::to_owned);
+        let req = request.into_inner();
+
+        if req.key.trim().is_empty() {
+            return Err(Status::invalid_argument("key is required"));
+        }
+        if req.artifact.is_none() {
+            return Err(Status::invalid_argument("artifact is required"));
+        }
+
+        let receipt = capsule::store_capsule(
+            &self.ctx.repos,
+            self.events.as_ref(),
+            tenant,
+            slug.as_deref(),
+            capsule_request_from_proto(req),
+        )
+        .await
+        .map_err(|err| match err {
+            CapsuleError::Invalid(message) => Status::invalid_argument(message),
+            CapsuleError::Storage(err) => storage_error("capsule store", err),
+        })?;
+
+        Ok(Response::new(StoreCapsuleResponse {
+            created: matches!(receipt.outcome, UpsertOutcome::Created),
+            key: receipt.key,
+            hash: receipt.hash,
+            tenant: receipt.tenant,
+        }))
+    }
+
+    async fn lookup_capsule(
+        &self,
+        request: Request<LookupCapsuleRequest>,
+    ) -> Result<Response<LookupCapsuleResponse>, Status> {
+        let tenant = request_tenant(&request)?;
+        let slug = request_tenant_slug(&request);
+        let key = &request.get_ref().key;
+
+        if key.trim().is_empty() {
+            return Err(Status::invalid_argument("key is required"));
+        }
+
+        let capsule = capsule::lookup_capsule(&self.ctx.repos, tenant, slug, key)
+            .await
+            .map_err(|err| storage_error("capsule lookup", err))?
+            .ok_or_else(|| Status::not_found("cache miss"))?;
+
+        Ok(Response::new(capsule_lookup_to_proto(&capsule)))
+    }
+
+    async fn purge_capsules(
+        &self,
+        request: Request<PurgeCapsulesRequest>,
+    ) -> Result<Response<PurgeCapsulesResponse>, Status> {
+        let tenant = request_tenant(&request)?;
+        let req = request.into_inner();
+
+        if req.keys.iter().all(|key| key.trim().is_empty()) {
+            return Err(Status::invalid_argument(
+                "keys must contain at least one key",
+            ));
+        }
+
+        let receipt =
+            capsule::purge_capsules(&self.ctx.repos, self.events.as_ref(), tenant, &req.keys)
+                .await
+                .map_err(|err| storage_error("capsule purge", err))?;
+
+        Ok(Response::new(PurgeCapsulesResponse {
+            purged: receipt.purged,
+            revoked_hashes: receipt.revoked_hashes,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CapsuleServiceImpl;
+    use crate::pb::convert::json_to_value;
+    use crate::pb::synagraph::v1::capsule_service_server::CapsuleService;
+    use crate::pb::synagraph::v1::{
+        CapsuleArtifact, CapsulePolicy, LookupCapsuleRequest, PurgeCapsulesRequest,
+        StoreCapsuleRequest,
+    };
+    use crate::server::grpc::tests::{sample_config, sample_context, tenant_request};
+    use crate::server::grpc::RequestTenantSlug;
+    use serde_json::json;
+    use tonic::{Code, Request};
+    use uuid::Uuid;
+
+    fn sample_capsules() -> (CapsuleServiceImpl, Uuid) {
+        let tenant = Uuid::new_v4();
+        let svc = CapsuleServiceImpl::new(&sample_config(tenant), sample_context());
+        (svc, tenant)
+    }
+
+    fn slug_request<T>(tenant: Uuid, slug: &str, message: T) -> Request<T> {
+        let mut request = tenant_request(tenant, message);
+        request
+            .extensions_mut()
+            .insert(RequestTenantSlug(slug.to_string()));
+        request
+    }
+
+    fn store_request(key: &str, tenant: &str, hash: &str) -> StoreCapsuleRequest {
+        StoreCapsuleRequest {
+            key: key.into(),
+            artifact: Some(CapsuleArtifact {
+                answer: Some(json_to_value(&json!("Quarterly revenue was up 23%."))),
+                policy: Some(CapsulePolicy {
+                    tenant: tenant.into(),
+                    ..Default::default()
+                }),
+                ttl_seconds: Some(3600),
+                hash: hash.into(),
+                ..Default::default()
+            }),
+            expires_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn store_then_lookup_returns_artifact() {
+        let (svc, tenant) = sample_capsules();
+
+        let first = svc
+            .store_capsule(tenant_request(
+                tenant,
+                store_request("acme:report", "acme", "sg-1"),
+            ))
+            .await
+            .expect("store")
+            .into_inner();
+        assert!(first.created);
+        assert_eq!(first.hash, "sg-1");
+
+        let second = svc
+            .store_capsule(tenant_request(
+                tenant,
+                store_request("acme:report", "acme", "sg-1"),
+            ))
+            .await
+            .expect("restore")
+            .into_inner();
+        assert!(!second.created);
+
+        let found = svc
+            .lookup_capsule(tenant_request(
+                tenant,
+                LookupCapsuleRequest {
+                    key: "acme:report".into(),
+                },
+            ))
+            .await
+            .expect("lookup")
+            .into_inner();
+        let artifact = found.artifact.expect("artifact");
+        assert_eq!(artifact.hash, "sg-1");
+        assert_eq!(artifact.ttl_seconds, Some(3600));
+        assert_eq!(artifact.provenance.len(), 1);
+        assert!(found.expires_at.is_some());
+        assert!(found.ttl_remaining_seconds.unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn tenant_slug_must_match_policy() {
+        let (svc, tenant) = sample_capsules();
+
+        let err = svc
+            .store_capsule(slug_request(
+                tenant,
+                "acme",
+                store_request("globex:report", "globex", "sg-1"),
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+
+        svc.store_capsule(tenant_request(
+            tenant,
+            store_request("globex:report", "globex", "sg-1"),
+        ))
+        .await
+        .expect("store without slug");
+
+        let err = svc
+            .lookup_capsule(slug_request(
+                tenant,
+                "acme",
+                LookupCapsuleRequest {
+                    key: "globex:report".into(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn store_validates_artifact() {
+        let (svc, tenant) = sample_capsules();
+
+        let mut missing_artifact = store_request("acme:report", "acme", "sg-1");
+        missing_artifact.artifact = None;
+        let err = svc
+            .store_capsule(tenant_request(tenant, missing_artifact))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+
+        let err = svc
+            .store_capsule(tenant_request(
+                tenant,
+                store_request("acme:report", "acme", ""),
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn purge_removes_capsules() {
+        let (svc, tenant) = sample_capsules();
+
+        svc.store_capsule(tenant_request(
+            tenant,
+            store_request("acme:report", "acme", "sg-1"),
+        ))
+        .await
+        .expect("store");
+
+        let purged = svc
+            .purge_capsules(tenant_request(
+                tenant,
+                PurgeCapsulesRequest {
+                    keys: vec!["acme:report".into(), "acme:unknown".into()],
+                },
+            ))
+            .await
+            .expect("purge")
+            .into_inner();
+        assert_eq!(purged.purged, 1);
+        assert!(purged.revoked_hashes.is_empty());
+
+        let err = svc
+            .lookup_capsule(tenant_request(
+                tenant,
+                LookupCapsuleRequest {
+                    key: "acme:report".into(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+
+        let err = svc
+            .purge_capsules(tenant_request(
+                tenant,
+                PurgeCapsulesRequest { keys: vec![] },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+}
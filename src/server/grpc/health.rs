@@ -0,0 +1,142 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// Keeps the standard `grpc.health.v1.Health` service in step with the storage backend.
+
+use std::time::Duration;
+
+use tonic::server::NamedService;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+
+use super::capsule::CapsuleServiceImpl;
+use super::GraphServiceImpl;
+use crate::pb::synagraph::v1::capsule_service_server::CapsuleServiceServer;
+use crate::pb::synagraph::v1::graph_service_server::GraphServiceServer;
+use crate::state::AppContext;
+
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Services reported through the health endpoint. The empty name covers the server as a whole.
+const REPORTED_SERVICES: [&str; 3] = [
+    "",
+    <GraphServiceServer<GraphServiceImpl> as NamedService>::NAME,
+    <CapsuleServiceServer<CapsuleServiceImpl> as NamedService>::NAME,
+];
+
+/// Publishes the current storage status, then keeps polling `NodeRepository::health_check` in
+/// the background and mirrors the result into the health service.
+pub(super) async fn track_storage_health(ctx: AppContext, mut reporter: HealthReporter) {
+    let mut previous = refresh(&ctx, &mut reporter).await;
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(HEALTH_POLL_INTERVAL);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let status = refresh(&ctx, &mut reporter).await;
+            if status != previous {
+                tracing::info!(?status, "grpc health status changed");
+                previous = status;
+            }
+        }
+    });
+}
+
+async fn refresh(ctx: &AppContext, reporter: &mut HealthReporter) -> ServingStatus {
+    let status = match ctx.repos.nodes.health_check().await {
+        Ok(()) => ServingStatus::Serving,
+        Err(err) => {
+            tracing::warn!(?err, "storage health check failed");
+            ServingStatus::NotServing
+        }
+    };
+
+    for service in REPORTED_SERVICES {
+        reporter.set_service_status(service, status).await;
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use sqlx::postgres::PgPoolOptions;
+    use tonic_health::pb::health_client::HealthClient;
+    use tonic_health::pb::HealthCheckRequest;
+    use tonic_health::server::health_reporter;
+    use tonic_health::ServingStatus;
+
+    use super::{refresh, track_storage_health, REPORTED_SERVICES};
+    use crate::repository::postgres::PostgresNodeRepository;
+    use crate::repository::RepositoryBundle;
+    use crate::server::grpc::tests::sample_context;
+    use crate::state::{AppContext, DashboardHandle};
+
+    /// A context whose node repository points at a port nothing listens on.
+    fn unreachable_storage() -> AppContext {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_lazy("postgres://synagraph@127.0.0.1:1/synagraph")
+            .expect("valid connection string");
+        let mut repos = RepositoryBundle::in_memory();
+        repos.nodes = Arc::new(PostgresNodeRepository::from_pool(pool));
+        AppContext::new(
+            repos,
+            DashboardHandle::new(),
+            crate::scedge::ScedgeBridge::new(None),
+        )
+    }
+
+    async fn statuses<T>(client: &mut HealthClient<T>) -> Vec<i32>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<tonic::codegen::StdError>,
+        T::ResponseBody: tonic::codegen::Body<Data = tonic::codegen::Bytes> + Send + 'static,
+        <T::ResponseBody as tonic::codegen::Body>::Error: Into<tonic::codegen::StdError> + Send,
+    {
+        let mut statuses = Vec::new();
+        for service in REPORTED_SERVICES {
+            let response = client
+                .check(HealthCheckRequest {
+                    service: service.into(),
+                })
+                .await
+                .expect("service is registered");
+            statuses.push(response.into_inner().status);
+        }
+        statuses
+    }
+
+    #[tokio::test]
+    async fn refresh_mirrors_storage_health_into_every_service() {
+        let (mut reporter, server) = health_reporter();
+        let mut client = HealthClient::new(server);
+
+        let failing = unreachable_storage();
+        assert_eq!(
+            refresh(&failing, &mut reporter).await,
+            ServingStatus::NotServing
+        );
+        let not_serving = ServingStatus::NotServing as i32;
+        assert_eq!(statuses(&mut client).await, vec![not_serving; 3]);
+
+        // Recovery flips every service back.
+        assert_eq!(
+            refresh(&sample_context(), &mut reporter).await,
+            ServingStatus::Serving
+        );
+        let serving = ServingStatus::Serving as i32;
+        assert_eq!(statuses(&mut client).await, vec![serving; 3]);
+    }
+
+    #[tokio::test]
+    async fn tracking_publishes_the_status_before_returning() {
+        let (reporter, server) = health_reporter();
+        let mut client = HealthClient::new(server);
+
+        track_storage_health(unreachable_storage(), reporter).await;
+        let not_serving = ServingStatus::NotServing as i32;
+        assert_eq!(statuses(&mut client).await, vec![not_serving; 3]);
+    }
+}
@@ -1,6 +1,8 @@
 // SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
 // Axum HTTP endpoints live here, including the readiness probe consumed by downstream systems.
 
+mod graph;
+
 use std::net::SocketAddr;
 
 use anyhow::{Context, Result};
@@ -15,7 +17,9 @@ use tokio::net::TcpListener;
 use tower_http::services::{ServeDir, ServeFile};
 
 use crate::config::AppConfig;
-use crate::domain::capsule::{CapsuleIngestRequest, CapsuleLookupResponse};
+use crate::domain::capsule::{
+    self, CapsuleError, CapsuleEvents, CapsuleIngestRequest, CapsuleLookupResponse,
+};
 use crate::domain::node::KnowledgeNode;
 use crate::repository::UpsertOutcome;
 use crate::scedge::{ScedgeError, ScedgeStatus};
@@ -60,6 +64,23 @@ pub async fn serve(cfg: AppConfig, ctx: AppContext) -> Result<()> {
         .route("/operations/store", post(api_store))
         .route("/operations/lookup", post(api_lookup))
         .route("/operations/purge", post(api_purge))
+        .route("/nodes", post(graph::api_upsert_node))
+        .route("/nodes/:id", get(graph::api_get_node))
+        .route("/edges", post(graph::api_relate_nodes))
+        .route(
+            "/edges/:id",
+            get(graph::api_get_edge)
+                .patch(graph::api_update_edge)
+                .delete(graph::api_delete_edge),
+        )
+        .route("/edges/:id/expire", post(graph::api_expire_edge))
+        .route("/neighbors/:id", get(graph::api_neighbors))
+        .route("/path", get(graph::api_shortest_path))
+        .route("/subgraph", post(graph::api_subgraph))
+        .route("/activate", post(graph::api_activate))
+        .route("/search", post(graph::api_search))
+        .route("/query", post(graph::api_query))
+        .route("/admin/communities", post(graph::api_detect_communities))
         .route("/lookup", get(api_capsule_lookup))
         .route("/ingest/capsule", post(api_capsule_store))
         .route("/capsules/purge", post(api_capsule_purge))
@@ -282,22 +303,15 @@ async fn api_capsule_lookup(
     Query(query): Query<CapsuleLookupQuery>,
 ) -> Result<Json<CapsuleLookupResponse>, (StatusCode, Json<Value>)> {
     let tenant_id = resolve_tenant(&state.cfg, query.tenant.as_deref());
-    let node = state
-        .ctx
-        .repos
-        .nodes
-        .get_by_key(tenant_id, &query.key)
-        .await
-        .map_err(internal_error)?
-        .ok_or_else(cache_miss)?;
-
-    let capsule = CapsuleLookupResponse::from_node(&node).map_err(internal_error)?;
-
-    if let Some(expected) = &query.tenant {
-        if capsule.artifact.policy.tenant != *expected {
-            return Err(cache_miss());
-        }
-    }
+    let capsule = capsule::lookup_capsule(
+        &state.ctx.repos,
+        tenant_id,
+        query.tenant.as_deref(),
+        &query.key,
+    )
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(cache_miss)?;
 
     Ok(Json(capsule))
 }
@@ -307,84 +321,37 @@ async fn api_capsule_store(
     Json(body): Json<CapsuleStoreBody>,
 ) -> (StatusCode, Json<Value>) {
     let CapsuleStoreBody { tenant, capsule } = body;
-
-    if let Some(expected) = tenant.as_ref() {
-        if capsule.artifact.policy.tenant != *expected {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({ "error": "policy.tenant mismatch" })),
-            );
-        }
-    }
-
-    if capsule.artifact.policy.tenant.is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "artifact.policy.tenant is required" })),
-        );
-    }
-
     let tenant_id = resolve_tenant(&state.cfg, tenant.as_deref());
-    let existing_node = match state
-        .ctx
-        .repos
-        .nodes
-        .get_by_key(tenant_id, &capsule.key)
-        .await
+    let events = capsule_events(&state);
+
+    match capsule::store_capsule(
+        &state.ctx.repos,
+        events.as_ref(),
+        tenant_id,
+        tenant.as_deref(),
+        capsule,
+    )
+    .await
     {
-        Ok(node) => node,
-        Err(err) => return internal_error(err),
-    };
-    let response_capsule = capsule.clone();
-    let node = match capsule.into_node(tenant_id) {
-        Ok(node) => node,
-        Err(err) => return internal_error(err),
-    };
-
-    let existing_capsule = existing_node
-        .as_ref()
-        .and_then(|node| CapsuleLookupResponse::from_node(node).ok());
-
-    match state.ctx.repos.nodes.upsert(tenant_id, node).await {
-        Ok(outcome) => {
-            let status = match outcome {
+        Ok(receipt) => {
+            let status = match receipt.outcome {
                 UpsertOutcome::Created => "created",
                 UpsertOutcome::Updated => "updated",
             };
-            if state.cfg.scedge_event_bus_enabled {
-                let tenant_slug = response_capsule.artifact.policy.tenant.clone();
-                let subject = state.cfg.scedge_event_bus_subject.clone();
-                let new_hash = response_capsule.artifact.hash.clone();
-                let event = if let (UpsertOutcome::Updated, Some(old_capsule)) =
-                    (outcome, existing_capsule)
-                {
-                    json!({
-                        "type": "SUPERSEDED_BY",
-                        "tenant": tenant_slug,
-                        "old_hash": old_capsule.artifact.hash,
-                        "new_hash": new_hash,
-                    })
-                } else {
-                    json!({
-                        "type": "UPSERT_NODE",
-                        "tenant": tenant_slug,
-                        "key": response_capsule.key,
-                        "hash": new_hash,
-                    })
-                };
-                publish_graph_event(&state, &subject, event).await;
-            }
             (
                 StatusCode::OK,
                 Json(json!({
                     "status": status,
-                    "key": response_capsule.key,
-                    "hash": response_capsule.artifact.hash,
-                    "tenant": response_capsule.artifact.policy.tenant
+                    "key": receipt.key,
+                    "hash": receipt.hash,
+                    "tenant": receipt.tenant
                 })),
             )
         }
-        Err(err) => internal_error(err),
+        Err(CapsuleError::Invalid(message)) => {
+            (StatusCode::BAD_REQUEST, Json(json!({ "error": message })))
+        }
+        Err(CapsuleError::Storage(err)) => internal_error(err),
     }
 }
 
@@ -393,49 +360,26 @@ async fn api_capsule_purge(
     Json(body): Json<CapsulePurgeBody>,
 ) -> (StatusCode, Json<Value>) {
     let tenant_id = resolve_tenant(&state.cfg, body.tenant.as_deref());
-    let mut purged = 0_u32;
-    let mut revoked: Vec<String> = Vec::new();
 
     let mut keys: Vec<String> = Vec::new();
     if let Some(key) = body.key {
         keys.push(key);
     }
     if let Some(list) = body.keys {
-        keys.extend(list.into_iter().filter(|k| !k.is_empty()));
+        keys.extend(list);
     }
 
-    for key in keys {
-        match state.ctx.repos.nodes.delete_by_key(tenant_id, &key).await {
-            Ok(Some(node)) => {
-                purged += 1;
-                if state.cfg.scedge_event_bus_enabled {
-                    if let Ok(capsule) = CapsuleLookupResponse::from_node(&node) {
-                        let tenant_slug = capsule.artifact.policy.tenant.clone();
-                        let hash = capsule.artifact.hash.clone();
-                        revoked.push(hash.clone());
-                        let event = json!({
-                            "type": "REVOKE_CAPSULE",
-                            "tenant": tenant_slug,
-                            "capsule_id": capsule.key,
-                            "hash": hash,
-                        });
-                        let subject = state.cfg.scedge_event_bus_subject.clone();
-                        publish_graph_event(&state, &subject, event).await;
-                    }
-                }
-            }
-            Ok(None) => {}
-            Err(err) => return internal_error(err),
-        }
+    let events = capsule_events(&state);
+    match capsule::purge_capsules(&state.ctx.repos, events.as_ref(), tenant_id, &keys).await {
+        Ok(receipt) => (
+            StatusCode::OK,
+            Json(json!({
+                "purged": receipt.purged,
+                "revoked_hashes": receipt.revoked_hashes,
+            })),
+        ),
+        Err(err) => internal_error(err),
     }
-
-    (
-        StatusCode::OK,
-        Json(json!({
-            "purged": purged,
-            "revoked_hashes": revoked,
-        })),
-    )
 }
 
 async fn api_scedge_status(State(state): State<HttpState>) -> Json<ScedgeStatus> {
@@ -500,7 +444,7 @@ fn cache_miss() -> (StatusCode, Json<Value>) {
 }
 
 fn internal_error<E: std::fmt::Display>(err: E) -> (StatusCode, Json<Value>) {
-    tracing::error!(error = %err, "capsule handler error");
+    tracing::error!(error = %err, "http handler error");
     (
         StatusCode::INTERNAL_SERVER_ERROR,
         Json(json!({ "error": err.to_string() })),
@@ -516,26 +460,19 @@ fn resolve_tenant(cfg: &AppConfig, slug: Option<&str>) -> Uuid {
     cfg.default_tenant_id
 }
 
-async fn publish_graph_event(state: &HttpState, subject: &str, payload: Value) {
-    if let Err(err) = state.ctx.repos.bus.publish(subject, &payload).await {
-        tracing::error!(error = %err, "failed to publish scedge graph event");
-    }
+fn capsule_events(state: &HttpState) -> Option<CapsuleEvents> {
+    CapsuleEvents::from_config(&state.cfg, state.ctx.repos.bus.clone())
 }
 
 #[cfg(test)]
-mod tests {
+pub(super) mod tests {
     use super::*;
     use axum::extract::State;
     use std::collections::HashMap;
-    use std::sync::Arc;
     use uuid::Uuid;
 
     use crate::domain::capsule::{CapsuleArtifact, CapsuleIngestRequest, CapsulePolicy};
-    use crate::repository::in_memory::{
-        InMemoryBus, InMemoryCache, InMemoryEdgeRepository, InMemoryEmbeddingRepository,
-        InMemoryNodeRepository, InMemoryOutboxRepository,
-    };
-    use crate::repository::RepositoryBundle;
+    use crate::repository::{DistanceMetric, RepositoryBundle};
     use crate::state::{AppContext, DashboardHandle};
     use serde_json::json;
 
@@ -551,19 +488,14 @@ mod tests {
             scedge_event_bus_enabled: false,
             scedge_event_bus_subject: "scedge:events".into(),
             tenant_slugs: HashMap::new(),
+            grpc_default_tenant_fallback: true,
+            vector_distance_metric: DistanceMetric::default(),
         }
     }
 
-    fn sample_state() -> HttpState {
+    pub(super) fn sample_state() -> HttpState {
         let cfg = sample_config();
-        let repos = RepositoryBundle::new(
-            Arc::new(InMemoryNodeRepository::new()),
-            Arc::new(InMemoryEdgeRepository::new()),
-            Arc::new(InMemoryEmbeddingRepository::new()),
-            Arc::new(InMemoryOutboxRepository::new()),
-            Arc::new(InMemoryCache::default()),
-            Arc::new(InMemoryBus::default()),
-        );
+        let repos = RepositoryBundle::in_memory();
         let dashboard = DashboardHandle::new();
         let scedge = crate::scedge::ScedgeBridge::new(None);
         let ctx = AppContext::new(repos, dashboard, scedge);
@@ -0,0 +1,1594 @@
+// SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
+// REST resources for graph nodes and edges, shaped after the types in `dashboard/src/api.ts`.
+
+use std::collections::HashSet;
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::{internal_error, HttpState};
+use crate::domain::node::KnowledgeNode;
+use crate::graph::activation::{
+    activate, ActivationSpec, DEFAULT_ACTIVATION_HOPS, DEFAULT_DECAY, DEFAULT_VECTOR_SEEDS,
+    MAX_ACTIVATION_HOPS,
+};
+use crate::graph::community::{
+    detect_communities, CommunityAlgorithm, CommunitySpec, DEFAULT_COMMUNITY_ITERATIONS,
+    MAX_COMMUNITY_ITERATIONS,
+};
+use crate::graph::hybrid::{hybrid_search, HybridSpec, RankedBy, DEFAULT_TOP_K, MAX_TOP_K};
+use crate::graph::query::{run_query, QueryError};
+use crate::graph::subgraph::{
+    extract_subgraph, SubgraphSpec, DEFAULT_MAX_EDGES, DEFAULT_MAX_NODES, MAX_EDGES_LIMIT,
+    MAX_NODES_LIMIT, MAX_SUBGRAPH_HOPS,
+};
+use crate::repository::pattern::PatternValue;
+use crate::repository::{
+    DimensionMismatch, Direction, DistanceMetric, EdgeUpdate, KnowledgeEdge, SimilarityFilter,
+    TraversalFilter, UpsertOutcome,
+};
+
+const DEFAULT_EDGE_WEIGHT: f32 = 1.0;
+const DEFAULT_NEIGHBOR_LIMIT: usize = 50;
+const MAX_NEIGHBOR_LIMIT: usize = 500;
+const MAX_NEIGHBOR_HOPS: u8 = 4;
+
+type ApiError = (StatusCode, Json<Value>);
+
+#[derive(Debug, Deserialize)]
+pub(super) struct NodeBody {
+    tenant_id: Option<Uuid>,
+    node_id: Option<Uuid>,
+    kind: String,
+    #[serde(default)]
+    payload: Value,
+    embedding: Option<Vec<f32>>,
+    provenance: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct UpsertNodeResponse {
+    node_id: Uuid,
+    created: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct EdgeBody {
+    tenant_id: Option<Uuid>,
+    from_id: Uuid,
+    to_id: Uuid,
+    kind: String,
+    weight: Option<f32>,
+    payload: Option<Value>,
+}
+
+/// Partial edge update; omitted fields keep their current value.
+#[derive(Debug, Deserialize)]
+pub(super) struct EdgePatchBody {
+    tenant_id: Option<Uuid>,
+    weight: Option<f32>,
+    payload: Option<Value>,
+    valid_from: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct ExpireEdgeBody {
+    tenant_id: Option<Uuid>,
+    /// End of validity; now when omitted.
+    at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct RelateNodesResponse {
+    edge_id: Uuid,
+    created: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct TenantQuery {
+    tenant_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct NeighborsQuery {
+    tenant_id: Option<Uuid>,
+    rel: Option<String>,
+    hops: Option<u8>,
+    limit: Option<usize>,
+    #[serde(default)]
+    direction: Direction,
+    as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct PathQuery {
+    tenant_id: Option<Uuid>,
+    from: Uuid,
+    to: Uuid,
+    rel: Option<String>,
+    max_hops: Option<u8>,
+    #[serde(default)]
+    direction: Direction,
+    as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct SubgraphBody {
+    tenant_id: Option<Uuid>,
+    seeds: Vec<Uuid>,
+    hops: Option<u8>,
+    rel: Option<String>,
+    #[serde(default)]
+    kinds: Vec<String>,
+    #[serde(default)]
+    direction: Direction,
+    max_nodes: Option<usize>,
+    max_edges: Option<usize>,
+    as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct ActivateBody {
+    tenant_id: Option<Uuid>,
+    #[serde(default)]
+    seeds: Vec<Uuid>,
+    vector: Option<Vec<f32>>,
+    vector_seeds: Option<usize>,
+    /// Metric for picking the vector seeds; the configured default when omitted.
+    distance_metric: Option<DistanceMetric>,
+    hops: Option<u8>,
+    decay: Option<f64>,
+    rel: Option<String>,
+    #[serde(default)]
+    direction: Direction,
+    limit: Option<usize>,
+    #[serde(default)]
+    include_seeds: bool,
+    as_of: Option<DateTime<Utc>>,
+}
+
+/// Hybrid search input from the dashboard's `hybridSearch`; at least one of `query_text` and
+/// `query_vector` is required.
+#[derive(Debug, Deserialize)]
+pub(super) struct SearchBody {
+    tenant_id: Option<Uuid>,
+    query_text: Option<String>,
+    query_vector: Option<Vec<f32>>,
+    top_k: Option<usize>,
+    /// The configured default when omitted.
+    distance_metric: Option<DistanceMetric>,
+    filter: Option<SimilarityFilter>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct QueryBody {
+    tenant_id: Option<Uuid>,
+    query: String,
+    as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct CommunitiesBody {
+    tenant_id: Option<Uuid>,
+    #[serde(default)]
+    algorithm: CommunityAlgorithm,
+    rel: Option<String>,
+    max_iterations: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct GraphNode {
+    node_id: Uuid,
+    kind: String,
+    payload: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    embedding: Option<Vec<f32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provenance: Option<Value>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<KnowledgeNode> for GraphNode {
+    fn from(node: KnowledgeNode) -> Self {
+        Self {
+            node_id: node.id,
+            kind: node.kind,
+            payload: node.payload_json,
+            embedding: node.vector,
+            provenance: node.provenance,
+            created_at: node.created_at,
+            updated_at: node.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct GraphEdge {
+    edge_id: Uuid,
+    kind: String,
+    from_id: Uuid,
+    to_id: Uuid,
+    weight: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<Value>,
+    created_at: DateTime<Utc>,
+    valid_from: DateTime<Utc>,
+    /// Absent while the edge has not expired.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    valid_to: Option<DateTime<Utc>>,
+    /// Hops from the start node, present on traversal results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    depth: Option<u8>,
+}
+
+impl From<KnowledgeEdge> for GraphEdge {
+    fn from(edge: KnowledgeEdge) -> Self {
+        Self {
+            edge_id: edge.id,
+            kind: edge.rel,
+            from_id: edge.src,
+            to_id: edge.dst,
+            weight: edge.weight,
+            payload: edge.props,
+            created_at: edge.created_at,
+            valid_from: edge.valid_from,
+            valid_to: edge.valid_to,
+            depth: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct NeighborsResponse {
+    node: Option<GraphNode>,
+    neighbors: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct PathResponse {
+    found: bool,
+    cost: f64,
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct ActivatedGraphNode {
+    #[serde(flatten)]
+    node: GraphNode,
+    score: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct ActivateResponse {
+    nodes: Vec<ActivatedGraphNode>,
+    truncated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct HybridSearchResponse {
+    took_ms: u64,
+    top_k: usize,
+    results: Vec<HybridSearchResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct HybridSearchResult {
+    node_id: Uuid,
+    /// Reciprocal rank fusion score; higher is better.
+    score: f64,
+    reason: String,
+    metadata: HybridSearchMetadata,
+}
+
+/// The node and where each ranking placed it.
+#[derive(Debug, Serialize)]
+pub(super) struct HybridSearchMetadata {
+    kind: String,
+    payload: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text_rank: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text_score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vector_rank: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vector_score: Option<f64>,
+}
+
+/// Query rows keyed by the returned variable names.
+#[derive(Debug, Serialize)]
+pub(super) struct QueryResponse {
+    columns: Vec<String>,
+    rows: Vec<serde_json::Map<String, Value>>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct CommunitiesResponse {
+    algorithm: CommunityAlgorithm,
+    nodes: usize,
+    components: usize,
+    communities: usize,
+    modularity: f64,
+    truncated: bool,
+    took_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct SubgraphResponse {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+    truncated: bool,
+}
+
+pub(super) async fn api_upsert_node(
+    State(state): State<HttpState>,
+    Json(body): Json<NodeBody>,
+) -> Result<Json<UpsertNodeResponse>, ApiError> {
+    let tenant = body.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    if body.kind.trim().is_empty() {
+        return Err(bad_request("kind is required"));
+    }
+    let vector = match body.embedding {
+        Some(values) if values.iter().any(|value| !value.is_finite()) => {
+            return Err(bad_request("embedding must contain finite values"))
+        }
+        Some(values) if values.is_empty() => None,
+        other => other,
+    };
+
+    let mut node = KnowledgeNode::new(tenant, body.kind, body.payload);
+    if let Some(id) = body.node_id {
+        node.id = id;
+    }
+    node.vector = vector;
+    node.provenance = body.provenance;
+
+    let outcome = state
+        .ctx
+        .repos
+        .nodes
+        .upsert(tenant, node.clone())
+        .await
+        .map_err(vector_error)?;
+    let created = matches!(outcome, UpsertOutcome::Created);
+    state
+        .ctx
+        .dashboard
+        .record_store(tenant, &node.kind, node.id, created);
+
+    Ok(Json(UpsertNodeResponse {
+        node_id: node.id,
+        created,
+    }))
+}
+
+pub(super) async fn api_get_node(
+    State(state): State<HttpState>,
+    Path(node_id): Path<Uuid>,
+    Query(query): Query<TenantQuery>,
+) -> Result<Json<GraphNode>, ApiError> {
+    let tenant = query.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    let node = state
+        .ctx
+        .repos
+        .nodes
+        .get(tenant, node_id)
+        .await
+        .map_err(internal_error)?;
+    state
+        .ctx
+        .dashboard
+        .record_lookup(tenant, node_id, node.is_some());
+
+    node.map(|node| Json(node.into()))
+        .ok_or_else(|| node_not_found(node_id))
+}
+
+pub(super) async fn api_relate_nodes(
+    State(state): State<HttpState>,
+    Json(body): Json<EdgeBody>,
+) -> Result<Json<RelateNodesResponse>, ApiError> {
+    let tenant = body.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    if body.kind.trim().is_empty() {
+        return Err(bad_request("kind is required"));
+    }
+    let weight = body.weight.unwrap_or(DEFAULT_EDGE_WEIGHT);
+    if !weight.is_finite() {
+        return Err(bad_request("weight must be finite"));
+    }
+
+    for id in [body.from_id, body.to_id] {
+        let found = state
+            .ctx
+            .repos
+            .nodes
+            .get(tenant, id)
+            .await
+            .map_err(internal_error)?;
+        if found.is_none() {
+            return Err(node_not_found(id));
+        }
+    }
+
+    let linked = state
+        .ctx
+        .repos
+        .edges
+        .link(
+            tenant,
+            body.from_id,
+            body.to_id,
+            &body.kind,
+            weight,
+            body.payload,
+        )
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(RelateNodesResponse {
+        edge_id: linked.id,
+        created: matches!(linked.outcome, UpsertOutcome::Created),
+    }))
+}
+
+pub(super) async fn api_get_edge(
+    State(state): State<HttpState>,
+    Path(edge_id): Path<Uuid>,
+    Query(query): Query<TenantQuery>,
+) -> Result<Json<GraphEdge>, ApiError> {
+    let tenant = query.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    let edge = fetch_edge(&state, tenant, edge_id).await?;
+    Ok(Json(edge.into()))
+}
+
+pub(super) async fn api_update_edge(
+    State(state): State<HttpState>,
+    Path(edge_id): Path<Uuid>,
+    Json(body): Json<EdgePatchBody>,
+) -> Result<Json<GraphEdge>, ApiError> {
+    let tenant = body.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    if body.weight.is_some_and(|weight| !weight.is_finite()) {
+        return Err(bad_request("weight must be finite"));
+    }
+    let update = EdgeUpdate {
+        weight: body.weight,
+        props: body.payload,
+        valid_from: body.valid_from,
+    };
+    if let Some(valid_from) = update.valid_from {
+        let edge = fetch_edge(&state, tenant, edge_id).await?;
+        if edge.valid_to.is_some_and(|end| end < valid_from) {
+            return Err(bad_request("valid_from must not be after the edge expired"));
+        }
+    }
+
+    state
+        .ctx
+        .repos
+        .edges
+        .update_edge(tenant, edge_id, update)
+        .await
+        .map_err(internal_error)?
+        .map(|edge| Json(edge.into()))
+        .ok_or_else(|| edge_not_found(edge_id))
+}
+
+pub(super) async fn api_delete_edge(
+    State(state): State<HttpState>,
+    Path(edge_id): Path<Uuid>,
+    Query(query): Query<TenantQuery>,
+) -> Result<Json<GraphEdge>, ApiError> {
+    let tenant = query.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    state
+        .ctx
+        .repos
+        .edges
+        .unlink(tenant, edge_id)
+        .await
+        .map_err(internal_error)?
+        .map(|edge| Json(edge.into()))
+        .ok_or_else(|| edge_not_found(edge_id))
+}
+
+/// Ends an edge's validity at `at` (now by default) without deleting it, so traversals as of
+/// earlier times still follow it. Expiring an expired edge keeps its first end.
+pub(super) async fn api_expire_edge(
+    State(state): State<HttpState>,
+    Path(edge_id): Path<Uuid>,
+    Json(body): Json<ExpireEdgeBody>,
+) -> Result<Json<GraphEdge>, ApiError> {
+    let tenant = body.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    let at = body.at.unwrap_or_else(Utc::now);
+    let edge = fetch_edge(&state, tenant, edge_id).await?;
+    if edge.valid_to.is_none() && at < edge.valid_from {
+        return Err(bad_request("at must not be before the edge became valid"));
+    }
+
+    state
+        .ctx
+        .repos
+        .edges
+        .expire_edge(tenant, edge_id, at)
+        .await
+        .map_err(internal_error)?
+        .map(|edge| Json(edge.into()))
+        .ok_or_else(|| edge_not_found(edge_id))
+}
+
+/// Returns the node with the nodes it links to and the connecting edges. A missing node yields
+/// `node: null` with empty lists, matching the dashboard contract.
+pub(super) async fn api_neighbors(
+    State(state): State<HttpState>,
+    Path(node_id): Path<Uuid>,
+    Query(query): Query<NeighborsQuery>,
+) -> Result<Json<NeighborsResponse>, ApiError> {
+    let tenant = query.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    let hops = query.hops.unwrap_or(1).max(1);
+    if hops > MAX_NEIGHBOR_HOPS {
+        return Err(bad_request(&format!(
+            "hops must not exceed {MAX_NEIGHBOR_HOPS}"
+        )));
+    }
+    let limit = match query.limit {
+        None | Some(0) => DEFAULT_NEIGHBOR_LIMIT,
+        Some(n) => n.min(MAX_NEIGHBOR_LIMIT),
+    };
+    let filter = TraversalFilter {
+        rel: query.rel.filter(|rel| !rel.is_empty()),
+        direction: query.direction,
+        as_of: query.as_of,
+    };
+
+    let repos = &state.ctx.repos;
+    let Some(node) = repos
+        .nodes
+        .get(tenant, node_id)
+        .await
+        .map_err(internal_error)?
+    else {
+        return Ok(Json(NeighborsResponse {
+            node: None,
+            neighbors: Vec::new(),
+            edges: Vec::new(),
+        }));
+    };
+
+    let reached = repos
+        .edges
+        .neighbors(tenant, node_id, &filter, hops, limit)
+        .await
+        .map_err(internal_error)?;
+
+    let mut seen = HashSet::new();
+    let mut neighbors = Vec::new();
+    let mut edges = Vec::with_capacity(reached.len());
+    for neighbor in reached {
+        if seen.insert(neighbor.node.id) {
+            neighbors.push(neighbor.node.into());
+        }
+        edges.push(GraphEdge {
+            depth: Some(neighbor.depth),
+            ..neighbor.edge.into()
+        });
+    }
+
+    Ok(Json(NeighborsResponse {
+        node: Some(node.into()),
+        neighbors,
+        edges,
+    }))
+}
+
+pub(super) async fn api_shortest_path(
+    State(state): State<HttpState>,
+    Query(query): Query<PathQuery>,
+) -> Result<Json<PathResponse>, ApiError> {
+    let tenant = query.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    let max_hops = match query.max_hops {
+        None | Some(0) => MAX_NEIGHBOR_HOPS,
+        Some(n) if n > MAX_NEIGHBOR_HOPS => {
+            return Err(bad_request(&format!(
+                "max_hops must not exceed {MAX_NEIGHBOR_HOPS}"
+            )))
+        }
+        Some(n) => n,
+    };
+    let filter = TraversalFilter {
+        rel: query.rel.filter(|rel| !rel.is_empty()),
+        direction: query.direction,
+        as_of: query.as_of,
+    };
+
+    let repos = &state.ctx.repos;
+    for id in [query.from, query.to] {
+        if repos
+            .nodes
+            .get(tenant, id)
+            .await
+            .map_err(internal_error)?
+            .is_none()
+        {
+            return Err(node_not_found(id));
+        }
+    }
+
+    let path = repos
+        .edges
+        .shortest_path(tenant, query.from, query.to, max_hops, &filter)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(match path {
+        Some(path) => PathResponse {
+            found: true,
+            cost: path.cost,
+            nodes: path.nodes.into_iter().map(Into::into).collect(),
+            edges: path.edges.into_iter().map(Into::into).collect(),
+        },
+        None => PathResponse {
+            found: false,
+            cost: 0.0,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        },
+    }))
+}
+
+/// Exports the induced subgraph around `seeds`. `hops` defaults to 1; 0 returns the seeds and the
+/// edges between them.
+pub(super) async fn api_subgraph(
+    State(state): State<HttpState>,
+    Json(body): Json<SubgraphBody>,
+) -> Result<Json<SubgraphResponse>, ApiError> {
+    let tenant = body.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    if body.seeds.is_empty() {
+        return Err(bad_request("seeds must not be empty"));
+    }
+    let hops = body.hops.unwrap_or(1);
+    if hops > MAX_SUBGRAPH_HOPS {
+        return Err(bad_request(&format!(
+            "hops must not exceed {MAX_SUBGRAPH_HOPS}"
+        )));
+    }
+    let spec = SubgraphSpec {
+        seeds: body.seeds,
+        hops,
+        filter: TraversalFilter {
+            rel: body.rel.filter(|rel| !rel.is_empty()),
+            direction: body.direction,
+            as_of: body.as_of,
+        },
+        kinds: body.kinds,
+        max_nodes: match body.max_nodes {
+            None | Some(0) => DEFAULT_MAX_NODES,
+            Some(n) => n.min(MAX_NODES_LIMIT),
+        },
+        max_edges: match body.max_edges {
+            None | Some(0) => DEFAULT_MAX_EDGES,
+            Some(n) => n.min(MAX_EDGES_LIMIT),
+        },
+    };
+
+    let subgraph = extract_subgraph(&state.ctx.repos, tenant, &spec)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(SubgraphResponse {
+        nodes: subgraph.nodes.into_iter().map(Into::into).collect(),
+        edges: subgraph.edges.into_iter().map(Into::into).collect(),
+        truncated: subgraph.truncated,
+    }))
+}
+
+/// Ranks nodes by spreading activation from `seeds` and/or the nearest hits for `vector`.
+pub(super) async fn api_activate(
+    State(state): State<HttpState>,
+    Json(body): Json<ActivateBody>,
+) -> Result<Json<ActivateResponse>, ApiError> {
+    let tenant = body.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    let vector = body.vector.filter(|values| !values.is_empty());
+    if body.seeds.is_empty() && vector.is_none() {
+        return Err(bad_request("seeds or vector is required"));
+    }
+    if vector
+        .as_ref()
+        .is_some_and(|values| values.iter().any(|value| !value.is_finite()))
+    {
+        return Err(bad_request("vector must contain finite values"));
+    }
+    let hops = body.hops.unwrap_or(DEFAULT_ACTIVATION_HOPS);
+    if hops > MAX_ACTIVATION_HOPS {
+        return Err(bad_request(&format!(
+            "hops must not exceed {MAX_ACTIVATION_HOPS}"
+        )));
+    }
+    let decay = body.decay.unwrap_or(DEFAULT_DECAY);
+    if !(decay > 0.0 && decay < 1.0) {
+        return Err(bad_request("decay must be between 0 and 1"));
+    }
+    let spec = ActivationSpec {
+        seeds: body.seeds,
+        vector,
+        vector_seeds: match body.vector_seeds {
+            None | Some(0) => DEFAULT_VECTOR_SEEDS,
+            Some(n) => n.min(MAX_NEIGHBOR_LIMIT),
+        },
+        metric: body
+            .distance_metric
+            .unwrap_or(state.cfg.vector_distance_metric),
+        hops,
+        decay,
+        filter: TraversalFilter {
+            rel: body.rel.filter(|rel| !rel.is_empty()),
+            direction: body.direction,
+            as_of: body.as_of,
+        },
+        limit: match body.limit {
+            None | Some(0) => DEFAULT_NEIGHBOR_LIMIT,
+            Some(n) => n.min(MAX_NEIGHBOR_LIMIT),
+        },
+        include_seeds: body.include_seeds,
+    };
+
+    let activation = activate(&state.ctx.repos, tenant, &spec)
+        .await
+        .map_err(vector_error)?;
+
+    Ok(Json(ActivateResponse {
+        nodes: activation
+            .nodes
+            .into_iter()
+            .map(|activated| ActivatedGraphNode {
+                node: activated.node.into(),
+                score: activated.score,
+            })
+            .collect(),
+        truncated: activation.truncated,
+    }))
+}
+
+/// Fuses full-text matches for `query_text` with the nearest nodes to `query_vector`.
+pub(super) async fn api_search(
+    State(state): State<HttpState>,
+    Json(body): Json<SearchBody>,
+) -> Result<Json<HybridSearchResponse>, ApiError> {
+    let tenant = body.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    let text = body
+        .query_text
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty());
+    let vector = body.query_vector.filter(|values| !values.is_empty());
+    if text.is_none() && vector.is_none() {
+        return Err(bad_request("query_text or query_vector is required"));
+    }
+    if vector
+        .as_ref()
+        .is_some_and(|values| values.iter().any(|value| !value.is_finite()))
+    {
+        return Err(bad_request("query_vector must contain finite values"));
+    }
+    let spec = HybridSpec {
+        text,
+        vector,
+        metric: body
+            .distance_metric
+            .unwrap_or(state.cfg.vector_distance_metric),
+        filter: body.filter.unwrap_or_default(),
+        top_k: match body.top_k {
+            None | Some(0) => DEFAULT_TOP_K,
+            Some(n) => n.min(MAX_TOP_K),
+        },
+    };
+
+    let started = Instant::now();
+    let hits = hybrid_search(&state.ctx.repos, tenant, &spec)
+        .await
+        .map_err(vector_error)?;
+
+    let rank = |ranked: Option<RankedBy>| ranked.map(|ranked| ranked.rank);
+    let score = |ranked: Option<RankedBy>| ranked.map(|ranked| ranked.score);
+    Ok(Json(HybridSearchResponse {
+        results: hits
+            .into_iter()
+            .map(|hit| HybridSearchResult {
+                node_id: hit.node.id,
+                score: hit.score,
+                reason: hit.reason(spec.metric),
+                metadata: HybridSearchMetadata {
+                    kind: hit.node.kind,
+                    payload: hit.node.payload_json,
+                    text_rank: rank(hit.text),
+                    text_score: score(hit.text),
+                    vector_rank: rank(hit.vector),
+                    vector_score: score(hit.vector),
+                },
+            })
+            .collect(),
+        top_k: spec.top_k,
+        took_ms: started.elapsed().as_millis() as u64,
+    }))
+}
+
+pub(super) async fn api_query(
+    State(state): State<HttpState>,
+    Json(body): Json<QueryBody>,
+) -> Result<Json<QueryResponse>, ApiError> {
+    let tenant = body.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    let result = match run_query(&state.ctx.repos, tenant, &body.query, body.as_of).await {
+        Ok(result) => result,
+        Err(QueryError::Invalid(message)) => return Err(bad_request(&message)),
+        Err(QueryError::Storage(err)) => return Err(internal_error(err)),
+    };
+
+    let rows = result
+        .rows
+        .into_iter()
+        .map(|row| {
+            result
+                .columns
+                .iter()
+                .cloned()
+                .zip(row.into_iter().map(|value| match value {
+                    PatternValue::Node(node) => json!(GraphNode::from(node)),
+                    PatternValue::Edge(edge) => json!(GraphEdge::from(edge)),
+                }))
+                .collect()
+        })
+        .collect();
+
+    Ok(Json(QueryResponse {
+        columns: result.columns,
+        rows,
+    }))
+}
+
+/// Recomputes the tenant's components and communities; pattern queries then filter on
+/// `n.component` and `n.community`.
+pub(super) async fn api_detect_communities(
+    State(state): State<HttpState>,
+    Json(body): Json<CommunitiesBody>,
+) -> Result<Json<CommunitiesResponse>, ApiError> {
+    let tenant = body.tenant_id.unwrap_or(state.cfg.default_tenant_id);
+    let spec = CommunitySpec {
+        algorithm: body.algorithm,
+        rel: body.rel.filter(|rel| !rel.is_empty()),
+        max_iterations: match body.max_iterations {
+            None | Some(0) => DEFAULT_COMMUNITY_ITERATIONS,
+            Some(n) => n.min(MAX_COMMUNITY_ITERATIONS),
+        },
+    };
+
+    let started = Instant::now();
+    let report = detect_communities(&state.ctx.repos, tenant, &spec)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(CommunitiesResponse {
+        algorithm: report.algorithm,
+        nodes: report.nodes,
+        components: report.components,
+        communities: report.communities,
+        modularity: report.modularity,
+        truncated: report.truncated,
+        took_ms: started.elapsed().as_millis() as u64,
+    }))
+}
+
+async fn fetch_edge(state: &HttpState, tenant: Uuid, id: Uuid) -> Result<KnowledgeEdge, ApiError> {
+    state
+        .ctx
+        .repos
+        .edges
+        .get_edge(tenant, id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| edge_not_found(id))
+}
+
+fn bad_request(message: &str) -> ApiError {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": message })))
+}
+
+/// A vector of the wrong length is the caller's mistake; other failures are internal.
+fn vector_error(err: anyhow::Error) -> ApiError {
+    match err.downcast_ref::<DimensionMismatch>() {
+        Some(mismatch) => bad_request(&mismatch.to_string()),
+        None => internal_error(err),
+    }
+}
+
+fn node_not_found(id: Uuid) -> ApiError {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": format!("node {id} not found") })),
+    )
+}
+
+fn edge_not_found(id: Uuid) -> ApiError {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": format!("edge {id} not found") })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::http::tests::sample_state;
+
+    fn node_body(tenant: Uuid, payload: Value) -> NodeBody {
+        serde_json::from_value(json!({
+            "tenant_id": tenant,
+            "kind": "note",
+            "payload": payload,
+            "embedding": [0.1, 0.9],
+            "provenance": {"source": "dashboard"},
+            "decay_lambda": 0.05
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn upsert_then_get_node_round_trips() {
+        let state = sample_state();
+        let tenant = Uuid::new_v4();
+
+        let Json(created) = api_upsert_node(
+            State(state.clone()),
+            Json(node_body(tenant, json!({"title": "first"}))),
+        )
+        .await
+        .unwrap();
+        assert!(created.created);
+
+        let mut update = node_body(tenant, json!({"title": "second"}));
+        update.node_id = Some(created.node_id);
+        let Json(updated) = api_upsert_node(State(state.clone()), Json(update))
+            .await
+            .unwrap();
+        assert!(!updated.created);
+
+        let Json(node) = api_get_node(
+            State(state.clone()),
+            Path(created.node_id),
+            Query(TenantQuery {
+                tenant_id: Some(tenant),
+            }),
+        )
+        .await
+        .unwrap();
+        let body = serde_json::to_value(node).unwrap();
+        assert_eq!(body["payload"]["title"], "second");
+        assert_eq!(body["embedding"], json!([0.1f32, 0.9f32]));
+        assert_eq!(body["provenance"]["source"], "dashboard");
+
+        let (status, _) = api_get_node(
+            State(state),
+            Path(created.node_id),
+            Query(TenantQuery { tenant_id: None }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn upsert_node_rejects_invalid_input() {
+        let state = sample_state();
+        let mut body = node_body(Uuid::new_v4(), json!({}));
+        body.kind = " ".into();
+        let (status, _) = api_upsert_node(State(state), Json(body)).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn upsert_node_rejects_embeddings_of_another_length() {
+        let state = sample_state();
+        let tenant = state.cfg.default_tenant_id;
+        let mut body = node_body(tenant, json!({}));
+        body.embedding = Some(vec![1.0, 0.0]);
+        let Json(stored) = api_upsert_node(State(state.clone()), Json(body))
+            .await
+            .unwrap();
+        assert!(stored.created);
+
+        let mut body = node_body(tenant, json!({}));
+        body.embedding = Some(vec![1.0]);
+        let (status, Json(error)) = api_upsert_node(State(state), Json(body)).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            error["error"],
+            "vector has 1 values but stored vectors have 2"
+        );
+    }
+
+    #[tokio::test]
+    async fn relate_and_list_neighbors() {
+        let state = sample_state();
+        let tenant = state.cfg.default_tenant_id;
+        let source = KnowledgeNode::new(tenant, "note", json!({"title": "source"}));
+        let target = KnowledgeNode::new(tenant, "note", json!({"title": "target"}));
+        for node in [&source, &target] {
+            state
+                .ctx
+                .repos
+                .nodes
+                .upsert(tenant, node.clone())
+                .await
+                .unwrap();
+        }
+
+        let relate = |to_id: Uuid| -> EdgeBody {
+            serde_json::from_value(json!({
+                "from_id": source.id,
+                "to_id": to_id,
+                "kind": "cites",
+                "weight": 0.5,
+                "payload": {"page": 3}
+            }))
+            .unwrap()
+        };
+        let Json(related) = api_relate_nodes(State(state.clone()), Json(relate(target.id)))
+            .await
+            .unwrap();
+        assert!(related.created);
+
+        let (status, _) = api_relate_nodes(State(state.clone()), Json(relate(Uuid::new_v4())))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let Json(response) = api_neighbors(
+            State(state.clone()),
+            Path(source.id),
+            Query(NeighborsQuery {
+                tenant_id: None,
+                rel: None,
+                hops: None,
+                limit: None,
+                direction: Direction::Outgoing,
+                as_of: None,
+            }),
+        )
+        .await
+        .unwrap();
+        let body = serde_json::to_value(response).unwrap();
+        assert_eq!(body["node"]["node_id"], json!(source.id));
+        assert_eq!(body["neighbors"][0]["node_id"], json!(target.id));
+        assert_eq!(body["edges"][0]["edge_id"], json!(related.edge_id));
+        assert_eq!(body["edges"][0]["kind"], "cites");
+        assert_eq!(body["edges"][0]["from_id"], json!(source.id));
+        assert_eq!(body["edges"][0]["payload"]["page"], 3);
+
+        let incoming: NeighborsQuery =
+            serde_json::from_value(json!({"direction": "incoming"})).unwrap();
+        let Json(citing) = api_neighbors(State(state.clone()), Path(target.id), Query(incoming))
+            .await
+            .unwrap();
+        let body = serde_json::to_value(citing).unwrap();
+        assert_eq!(body["neighbors"][0]["node_id"], json!(source.id));
+        assert_eq!(body["edges"][0]["to_id"], json!(target.id));
+
+        let Json(missing) = api_neighbors(
+            State(state),
+            Path(Uuid::new_v4()),
+            Query(NeighborsQuery {
+                tenant_id: None,
+                rel: None,
+                hops: None,
+                limit: None,
+                direction: Direction::Outgoing,
+                as_of: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(missing.node.is_none());
+        assert!(missing.neighbors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn relinking_updates_then_edges_can_be_patched_and_deleted() {
+        let state = sample_state();
+        let tenant = state.cfg.default_tenant_id;
+        let source = KnowledgeNode::new(tenant, "note", json!({}));
+        let target = KnowledgeNode::new(tenant, "note", json!({}));
+        for node in [&source, &target] {
+            state
+                .ctx
+                .repos
+                .nodes
+                .upsert(tenant, node.clone())
+                .await
+                .unwrap();
+        }
+        let relate = |weight: f32| -> EdgeBody {
+            serde_json::from_value(json!({
+                "from_id": source.id,
+                "to_id": target.id,
+                "kind": "cites",
+                "weight": weight
+            }))
+            .unwrap()
+        };
+
+        let Json(first) = api_relate_nodes(State(state.clone()), Json(relate(1.0)))
+            .await
+            .unwrap();
+        let Json(second) = api_relate_nodes(State(state.clone()), Json(relate(2.0)))
+            .await
+            .unwrap();
+        assert!(first.created);
+        assert!(!second.created);
+        assert_eq!(first.edge_id, second.edge_id);
+
+        let tenant_query = || Query(TenantQuery { tenant_id: None });
+        let Json(edge) = api_get_edge(State(state.clone()), Path(first.edge_id), tenant_query())
+            .await
+            .unwrap();
+        assert_eq!(edge.weight, 2.0);
+
+        let patch: EdgePatchBody =
+            serde_json::from_value(json!({"payload": {"note": "x"}})).unwrap();
+        let Json(patched) = api_update_edge(State(state.clone()), Path(first.edge_id), Json(patch))
+            .await
+            .unwrap();
+        assert_eq!(patched.weight, 2.0);
+        assert_eq!(patched.payload, Some(json!({"note": "x"})));
+
+        let Json(removed) =
+            api_delete_edge(State(state.clone()), Path(first.edge_id), tenant_query())
+                .await
+                .unwrap();
+        assert_eq!(removed.edge_id, first.edge_id);
+        let (status, _) = api_get_edge(State(state.clone()), Path(first.edge_id), tenant_query())
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = api_delete_edge(State(state), Path(first.edge_id), tenant_query())
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn expired_edges_leave_current_traversals_but_not_history() {
+        let state = sample_state();
+        let tenant = state.cfg.default_tenant_id;
+        let source = KnowledgeNode::new(tenant, "note", json!({}));
+        let target = KnowledgeNode::new(tenant, "note", json!({}));
+        for node in [&source, &target] {
+            state
+                .ctx
+                .repos
+                .nodes
+                .upsert(tenant, node.clone())
+                .await
+                .unwrap();
+        }
+        let linked = state
+            .ctx
+            .repos
+            .edges
+            .link(tenant, source.id, target.id, "cites", 1.0, None)
+            .await
+            .unwrap();
+        let before = Utc::now();
+        let patch: EdgePatchBody =
+            serde_json::from_value(json!({ "valid_from": before - chrono::Duration::hours(1) }))
+                .unwrap();
+        let Json(backdated) = api_update_edge(State(state.clone()), Path(linked.id), Json(patch))
+            .await
+            .unwrap();
+        assert_eq!(backdated.valid_from, before - chrono::Duration::hours(1));
+
+        let expire = |at: DateTime<Utc>| -> ExpireEdgeBody {
+            serde_json::from_value(json!({ "at": at })).unwrap()
+        };
+        let (status, _) = api_expire_edge(
+            State(state.clone()),
+            Path(linked.id),
+            Json(expire(before - chrono::Duration::hours(2))),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let Json(expired) =
+            api_expire_edge(State(state.clone()), Path(linked.id), Json(expire(before)))
+                .await
+                .unwrap();
+        assert_eq!(expired.valid_to, Some(before));
+        assert!(json!(expired)["valid_from"].is_string());
+
+        let neighbors = |as_of: Option<DateTime<Utc>>| {
+            let query: NeighborsQuery = serde_json::from_value(json!({ "as_of": as_of })).unwrap();
+            api_neighbors(State(state.clone()), Path(source.id), Query(query))
+        };
+        let Json(now) = neighbors(None).await.unwrap();
+        assert!(now.edges.is_empty());
+        let Json(then) = neighbors(Some(before - chrono::Duration::minutes(1)))
+            .await
+            .unwrap();
+        assert_eq!(then.edges.len(), 1);
+        assert_eq!(then.edges[0].valid_to, Some(before));
+
+        let (status, _) = api_expire_edge(State(state), Path(Uuid::new_v4()), Json(expire(before)))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn shortest_path_reports_route_or_absence() {
+        let state = sample_state();
+        let tenant = state.cfg.default_tenant_id;
+        let nodes: Vec<_> = ["a", "b", "c"]
+            .into_iter()
+            .map(|title| KnowledgeNode::new(tenant, "note", json!({ "title": title })))
+            .collect();
+        for node in &nodes {
+            state
+                .ctx
+                .repos
+                .nodes
+                .upsert(tenant, node.clone())
+                .await
+                .unwrap();
+        }
+        for pair in nodes.windows(2) {
+            state
+                .ctx
+                .repos
+                .edges
+                .link(tenant, pair[0].id, pair[1].id, "next", 2.0, None)
+                .await
+                .unwrap();
+        }
+
+        let query = |from: Uuid, to: Uuid| {
+            Query(PathQuery {
+                tenant_id: None,
+                from,
+                to,
+                rel: None,
+                max_hops: None,
+                direction: Direction::Outgoing,
+                as_of: None,
+            })
+        };
+
+        let Json(path) = api_shortest_path(State(state.clone()), query(nodes[0].id, nodes[2].id))
+            .await
+            .unwrap();
+        let body = serde_json::to_value(path).unwrap();
+        assert_eq!(body["found"], true);
+        assert_eq!(body["cost"], 1.0);
+        assert_eq!(body["nodes"][2]["payload"]["title"], "c");
+        assert_eq!(body["edges"][0]["from_id"], json!(nodes[0].id));
+
+        let Json(reverse) =
+            api_shortest_path(State(state.clone()), query(nodes[2].id, nodes[0].id))
+                .await
+                .unwrap();
+        assert!(!reverse.found);
+
+        let (status, _) = api_shortest_path(State(state), query(nodes[0].id, Uuid::new_v4()))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn subgraph_exports_nodes_and_edges_with_truncation() {
+        let state = sample_state();
+        let tenant = state.cfg.default_tenant_id;
+        let nodes: Vec<_> = ["hub", "a", "b"]
+            .into_iter()
+            .map(|title| KnowledgeNode::new(tenant, "note", json!({ "title": title })))
+            .collect();
+        for node in &nodes {
+            state
+                .ctx
+                .repos
+                .nodes
+                .upsert(tenant, node.clone())
+                .await
+                .unwrap();
+        }
+        for (src, dst) in [(0, 1), (0, 2), (1, 2)] {
+            state
+                .ctx
+                .repos
+                .edges
+                .link(tenant, nodes[src].id, nodes[dst].id, "rel", 1.0, None)
+                .await
+                .unwrap();
+        }
+
+        let body = |max_nodes: Option<usize>| {
+            Json(SubgraphBody {
+                tenant_id: None,
+                seeds: vec![nodes[0].id],
+                hops: None,
+                rel: None,
+                kinds: Vec::new(),
+                direction: Direction::Outgoing,
+                max_nodes,
+                max_edges: None,
+                as_of: None,
+            })
+        };
+
+        let Json(full) = api_subgraph(State(state.clone()), body(None))
+            .await
+            .unwrap();
+        let full = serde_json::to_value(full).unwrap();
+        assert_eq!(full["nodes"].as_array().unwrap().len(), 3);
+        assert_eq!(full["edges"].as_array().unwrap().len(), 3);
+        assert_eq!(full["truncated"], false);
+
+        let Json(capped) = api_subgraph(State(state.clone()), body(Some(2)))
+            .await
+            .unwrap();
+        assert_eq!(capped.nodes.len(), 2);
+        assert_eq!(capped.edges.len(), 1);
+        assert!(capped.truncated);
+
+        let mut empty = body(None);
+        empty.0.seeds.clear();
+        let (status, _) = api_subgraph(State(state), empty).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn activation_ranks_nodes_from_seeds_or_vector() {
+        let state = sample_state();
+        let tenant = state.cfg.default_tenant_id;
+        let mut nodes: Vec<_> = ["seed", "near", "far"]
+            .into_iter()
+            .map(|title| KnowledgeNode::new(tenant, "note", json!({ "title": title })))
+            .collect();
+        nodes[0].vector = Some(vec![1.0, 0.0]);
+        for node in &nodes {
+            state
+                .ctx
+                .repos
+                .nodes
+                .upsert(tenant, node.clone())
+                .await
+                .unwrap();
+        }
+        for pair in nodes.windows(2) {
+            state
+                .ctx
+                .repos
+                .edges
+                .link(tenant, pair[0].id, pair[1].id, "next", 1.0, None)
+                .await
+                .unwrap();
+        }
+
+        let body = |seeds: Vec<Uuid>, vector: Option<Vec<f32>>| {
+            Json(ActivateBody {
+                tenant_id: None,
+                seeds,
+                vector,
+                vector_seeds: Some(1),
+                distance_metric: None,
+                hops: None,
+                decay: None,
+                rel: None,
+                direction: Direction::Outgoing,
+                limit: None,
+                include_seeds: false,
+                as_of: None,
+            })
+        };
+
+        let Json(ranked) = api_activate(State(state.clone()), body(vec![nodes[0].id], None))
+            .await
+            .unwrap();
+        let ranked = serde_json::to_value(ranked).unwrap();
+        assert_eq!(ranked["nodes"][0]["payload"]["title"], "near");
+        assert_eq!(ranked["nodes"][0]["score"], 0.5);
+        assert_eq!(ranked["nodes"][1]["payload"]["title"], "far");
+        assert_eq!(ranked["nodes"][1]["score"], 0.25);
+        assert_eq!(ranked["truncated"], false);
+
+        let Json(by_vector) =
+            api_activate(State(state.clone()), body(Vec::new(), Some(vec![1.0, 0.0])))
+                .await
+                .unwrap();
+        assert_eq!(by_vector.nodes.len(), 2);
+        assert_eq!(by_vector.nodes[0].score, 0.5);
+
+        let (status, _) = api_activate(
+            State(state.clone()),
+            body(Vec::new(), Some(vec![1.0, 0.0, 0.0])),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = api_activate(State(state), body(Vec::new(), None))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn search_fuses_text_and_vector_matches() {
+        let state = sample_state();
+        let tenant = state.cfg.default_tenant_id;
+        let mut refund = KnowledgeNode::new(tenant, "capsule", json!({ "title": "Refund window" }));
+        refund.vector = Some(vec![1.0, 0.0]);
+        let mut shipping = KnowledgeNode::new(tenant, "capsule", json!({ "title": "Shipping" }));
+        shipping.vector = Some(vec![0.8, 0.6]);
+        let mut note = KnowledgeNode::new(tenant, "note", json!({ "title": "refund notes" }));
+        note.vector = Some(vec![0.0, 1.0]);
+        for node in [&refund, &shipping, &note] {
+            state
+                .ctx
+                .repos
+                .nodes
+                .upsert(tenant, node.clone())
+                .await
+                .unwrap();
+        }
+
+        let search = |body: Value| {
+            api_search(
+                State(state.clone()),
+                Json(serde_json::from_value(body).unwrap()),
+            )
+        };
+
+        let Json(found) = search(json!({
+            "query_text": "refund",
+            "query_vector": [1.0, 0.0],
+            "top_k": 2,
+            "filter": { "kind": "capsule" }
+        }))
+        .await
+        .unwrap();
+        let found = serde_json::to_value(found).unwrap();
+        assert_eq!(found["top_k"], 2);
+        assert!(found["took_ms"].is_u64());
+        let results = found["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["node_id"], json!(refund.id));
+        assert_eq!(results[0]["metadata"]["text_rank"], 1);
+        assert_eq!(results[0]["metadata"]["vector_rank"], 1);
+        assert!(results[0]["reason"]
+            .as_str()
+            .unwrap()
+            .starts_with("text #1"));
+        assert_eq!(results[1]["node_id"], json!(shipping.id));
+        assert!(results[1]["metadata"].get("text_rank").is_none());
+
+        let (status, _) = search(json!({ "query_text": "  ", "query_vector": [] }))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = search(json!({ "query_vector": [1.0, 0.0, 0.0] }))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn query_returns_rows_keyed_by_variable() {
+        let state = sample_state();
+        let tenant = state.cfg.default_tenant_id;
+        let source = KnowledgeNode::new(tenant, "source", json!({ "region": "EU" }));
+        let capsule = KnowledgeNode::new(tenant, "capsule", json!({ "title": "derived" }));
+        for node in [&source, &capsule] {
+            state
+                .ctx
+                .repos
+                .nodes
+                .upsert(tenant, node.clone())
+                .await
+                .unwrap();
+        }
+        state
+            .ctx
+            .repos
+            .edges
+            .link(tenant, capsule.id, source.id, "derived_from", 1.0, None)
+            .await
+            .unwrap();
+
+        let body = |query: &str| {
+            Json(QueryBody {
+                tenant_id: None,
+                query: query.into(),
+                as_of: None,
+            })
+        };
+
+        let Json(result) = api_query(
+            State(state.clone()),
+            body(
+                "MATCH (c:capsule)-[r:derived_from]->(s:source) \
+                 WHERE s.payload.region = 'EU' RETURN c, r",
+            ),
+        )
+        .await
+        .unwrap();
+        let result = serde_json::to_value(result).unwrap();
+        assert_eq!(result["columns"], json!(["c", "r"]));
+        assert_eq!(result["rows"][0]["c"]["payload"]["title"], "derived");
+        assert_eq!(result["rows"][0]["r"]["to_id"], json!(source.id));
+
+        let (status, Json(error)) = api_query(State(state), body("MATCH (c) RETURN x"))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error["error"]
+            .as_str()
+            .unwrap()
+            .contains("unknown variable"));
+    }
+
+    #[tokio::test]
+    async fn community_detection_labels_nodes_for_queries() {
+        let state = sample_state();
+        let tenant = state.cfg.default_tenant_id;
+        let repos = &state.ctx.repos;
+        let mut nodes = Vec::new();
+        for title in ["a", "b", "c", "d", "lonely"] {
+            let node = KnowledgeNode::new(tenant, "note", json!({ "title": title }));
+            repos.nodes.upsert(tenant, node.clone()).await.unwrap();
+            nodes.push(node);
+        }
+        for (src, dst) in [(0, 1), (2, 3)] {
+            repos
+                .edges
+                .link(tenant, nodes[src].id, nodes[dst].id, "related", 1.0, None)
+                .await
+                .unwrap();
+        }
+
+        let body: CommunitiesBody =
+            serde_json::from_value(json!({ "algorithm": "label_propagation" })).unwrap();
+        let Json(report) = api_detect_communities(State(state.clone()), Json(body))
+            .await
+            .unwrap();
+        let report = serde_json::to_value(report).unwrap();
+        assert_eq!(report["algorithm"], "label_propagation");
+        assert_eq!(report["nodes"], 4);
+        assert_eq!(report["components"], 2);
+        assert_eq!(report["communities"], 2);
+
+        let label = nodes[0].id.min(nodes[1].id);
+        let Json(result) = api_query(
+            State(state),
+            Json(QueryBody {
+                tenant_id: None,
+                query: format!("MATCH (n) WHERE n.component = '{label}' RETURN n"),
+                as_of: None,
+            }),
+        )
+        .await
+        .unwrap();
+        let mut found: Vec<&str> = result
+            .rows
+            .iter()
+            .map(|row| row["n"]["payload"]["title"].as_str().unwrap())
+            .collect();
+        found.sort();
+        assert_eq!(found, vec!["a", "b"]);
+    }
+}
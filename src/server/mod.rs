@@ -10,6 +10,8 @@ use tokio::try_join;
 use crate::config::AppConfig;
 use crate::state::AppContext;
 
+pub use grpc::TENANT_METADATA_KEY;
+
 pub async fn run(cfg: AppConfig, ctx: AppContext) -> Result<()> {
     let http_future = http::serve(cfg.clone(), ctx.clone());
     let grpc_future = grpc::serve(cfg.clone(), ctx);
@@ -19,6 +19,12 @@ pub struct DashboardHandle {
     inner: Arc<RwLock<DashboardData>>,
 }
 
+impl Default for DashboardHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DashboardHandle {
     pub fn new() -> Self {
         Self {
@@ -41,6 +47,23 @@ impl DashboardHandle {
         ));
     }
 
+    /// Records a finished bulk load as a single history entry so large imports do not flush the
+    /// rest of the history.
+    pub fn record_bulk_store(&self, tenant: Uuid, created: u64, updated: u64, failed: u64) {
+        let mut guard = self.inner.write();
+        guard.metrics.total_stores += created + updated;
+        guard.metrics.last_updated = Some(Utc::now());
+        guard.push_history(HistoryEvent::new(
+            "BULK_STORE",
+            tenant,
+            json!({
+                "created": created,
+                "updated": updated,
+                "failed": failed,
+            }),
+        ));
+    }
+
     pub fn record_lookup(&self, tenant: Uuid, node_id: Uuid, hit: bool) {
         let mut guard = self.inner.write();
         guard.metrics.total_lookups += 1;
@@ -1,28 +1,45 @@
 // SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
-// Integration test: spins up the gRPC server and exercises UpsertNode through a tonic client.
+// Integration test: spins up the gRPC server and exercises UpsertNode, bulk loading, health
+// checking, and reflection through tonic clients.
 
 use std::collections::HashMap;
 use std::net::{SocketAddr, TcpListener};
 use std::sync::Arc;
 use std::time::Duration;
 
+use sqlx::postgres::PgPoolOptions;
 use synagraph::config::AppConfig;
+use synagraph::pb::synagraph::v1::bulk_upsert_nodes_request::Item;
+use synagraph::pb::synagraph::v1::bulk_upsert_nodes_response::Outcome;
 use synagraph::pb::synagraph::v1::graph_service_client::GraphServiceClient;
-use synagraph::pb::synagraph::v1::UpsertNodeRequest;
-use synagraph::repository::in_memory::{
-    InMemoryBus, InMemoryCache, InMemoryEdgeRepository, InMemoryEmbeddingRepository,
-    InMemoryNodeRepository, InMemoryOutboxRepository,
+use synagraph::pb::synagraph::v1::{
+    BulkUpsertNodesRequest, GetNodeRequest, LinkNodesRequest, UpsertNodeRequest,
 };
-use synagraph::repository::RepositoryBundle;
+use synagraph::repository::postgres::PostgresNodeRepository;
+use synagraph::repository::{DistanceMetric, RepositoryBundle};
 use synagraph::scedge::ScedgeBridge;
 use synagraph::server;
 use synagraph::state::{AppContext, DashboardHandle};
 use tokio::net::TcpStream;
 use tokio::time::sleep;
 use tonic::transport::Channel;
+use tonic::{Code, Request};
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_client::HealthClient;
+use tonic_health::pb::HealthCheckRequest;
+use tonic_reflection::pb::server_reflection_client::ServerReflectionClient;
+use tonic_reflection::pb::server_reflection_request::MessageRequest;
+use tonic_reflection::pb::server_reflection_response::MessageResponse;
+use tonic_reflection::pb::ServerReflectionRequest;
 use uuid::Uuid;
 
+const ACME_TENANT: Uuid = Uuid::from_u128(0x6163_6d65_0000_4000_8000_0000_0000_0001);
+
 async fn start_server() -> SocketAddr {
+    start_server_with(RepositoryBundle::in_memory()).await
+}
+
+async fn start_server_with(repos: RepositoryBundle) -> SocketAddr {
     let listener = TcpListener::bind("127.0.0.1:0").expect("bind temp port");
     let port = listener.local_addr().expect("local addr").port();
     drop(listener);
@@ -39,17 +56,11 @@ async fn start_server() -> SocketAddr {
         scedge_base_url: None,
         scedge_event_bus_enabled: false,
         scedge_event_bus_subject: "scedge:events".into(),
-        tenant_slugs: HashMap::new(),
+        tenant_slugs: HashMap::from([("acme".to_string(), ACME_TENANT)]),
+        grpc_default_tenant_fallback: true,
+        vector_distance_metric: DistanceMetric::default(),
     };
 
-    let repos = RepositoryBundle::new(
-        Arc::new(InMemoryNodeRepository::new()),
-        Arc::new(InMemoryEdgeRepository::new()),
-        Arc::new(InMemoryEmbeddingRepository::new()),
-        Arc::new(InMemoryOutboxRepository::new()),
-        Arc::new(InMemoryCache::default()),
-        Arc::new(InMemoryBus::default()),
-    );
     let dashboard = DashboardHandle::new();
     let scedge = ScedgeBridge::new(None);
     let ctx = AppContext::new(repos, dashboard, scedge);
@@ -69,6 +80,14 @@ async fn start_server() -> SocketAddr {
     panic!("grpc server failed to start in time");
 }
 
+async fn channel(addr: SocketAddr) -> Channel {
+    Channel::from_shared(format!("http://{}", addr))
+        .expect("valid endpoint")
+        .connect()
+        .await
+        .expect("channel connects")
+}
+
 async fn connect(addr: SocketAddr) -> GraphServiceClient<Channel> {
     GraphServiceClient::connect(format!("http://{}", addr))
         .await
@@ -85,6 +104,7 @@ async fn upsert_node_roundtrip_via_grpc() {
             node_id: String::new(),
             kind: "note".into(),
             payload_json: "{\"title\":\"grpc-test\"}".into(),
+            ..Default::default()
         })
         .await
         .expect("upsert succeeds")
@@ -98,6 +118,7 @@ async fn upsert_node_roundtrip_via_grpc() {
             node_id: node_id.to_string(),
             kind: "note".into(),
             payload_json: "{\"title\":\"grpc-test-updated\"}".into(),
+            ..Default::default()
         })
         .await
         .expect("upsert succeeds")
@@ -105,3 +126,218 @@ async fn upsert_node_roundtrip_via_grpc() {
 
     assert!(!response_update.created);
 }
+
+fn with_tenant<T>(tenant: &str, message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request
+        .metadata_mut()
+        .insert(server::TENANT_METADATA_KEY, tenant.parse().unwrap());
+    request
+}
+
+#[tokio::test]
+async fn tenant_header_scopes_requests() {
+    let addr = start_server().await;
+    let mut client = connect(addr).await;
+
+    let created = client
+        .upsert_node(with_tenant(
+            "acme",
+            UpsertNodeRequest {
+                node_id: String::new(),
+                kind: "note".into(),
+                payload_json: "{\"title\":\"acme-only\"}".into(),
+                ..Default::default()
+            },
+        ))
+        .await
+        .expect("upsert succeeds")
+        .into_inner();
+
+    let fetched = client
+        .get_node(with_tenant(
+            &ACME_TENANT.to_string(),
+            GetNodeRequest {
+                node_id: created.node_id.clone(),
+            },
+        ))
+        .await
+        .expect("node visible to owning tenant")
+        .into_inner();
+    assert_eq!(fetched.node.unwrap().tenant_id, ACME_TENANT.to_string());
+
+    let hidden = client
+        .get_node(GetNodeRequest {
+            node_id: created.node_id.clone(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(hidden.code(), Code::NotFound);
+
+    for unknown in ["globex".to_string(), Uuid::new_v4().to_string()] {
+        let rejected = client
+            .get_node(with_tenant(
+                &unknown,
+                GetNodeRequest {
+                    node_id: created.node_id.clone(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(rejected.code(), Code::PermissionDenied, "{unknown}");
+    }
+}
+
+#[tokio::test]
+async fn health_reports_serving_for_graph_services() {
+    let addr = start_server().await;
+    let mut client = HealthClient::new(channel(addr).await);
+
+    for service in [
+        "",
+        "synagraph.v1.GraphService",
+        "synagraph.v1.CapsuleService",
+    ] {
+        let response = client
+            .check(HealthCheckRequest {
+                service: service.into(),
+            })
+            .await
+            .expect("health check succeeds")
+            .into_inner();
+        assert_eq!(response.status, ServingStatus::Serving as i32, "{service}");
+    }
+}
+
+#[tokio::test]
+async fn health_reports_not_serving_when_storage_is_unreachable() {
+    // Nothing listens on port 1, so every storage health check fails.
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://synagraph@127.0.0.1:1/synagraph")
+        .expect("valid connection string");
+    let mut repos = RepositoryBundle::in_memory();
+    repos.nodes = Arc::new(PostgresNodeRepository::from_pool(pool));
+    let addr = start_server_with(repos).await;
+    let mut client = HealthClient::new(channel(addr).await);
+
+    for service in [
+        "",
+        "synagraph.v1.GraphService",
+        "synagraph.v1.CapsuleService",
+    ] {
+        let response = client
+            .check(HealthCheckRequest {
+                service: service.into(),
+            })
+            .await
+            .expect("health check succeeds")
+            .into_inner();
+        assert_eq!(
+            response.status,
+            ServingStatus::NotServing as i32,
+            "{service}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn reflection_lists_registered_services() {
+    let addr = start_server().await;
+    let mut client = ServerReflectionClient::new(channel(addr).await);
+
+    let request = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(MessageRequest::ListServices(String::new())),
+    };
+    let mut stream = client
+        .server_reflection_info(tokio_stream::once(request))
+        .await
+        .expect("reflection stream opens")
+        .into_inner();
+    let response = stream
+        .message()
+        .await
+        .expect("reflection responds")
+        .expect("response message");
+
+    let Some(MessageResponse::ListServicesResponse(list)) = response.message_response else {
+        panic!(
+            "unexpected reflection response: {:?}",
+            response.message_response
+        );
+    };
+    let names: Vec<_> = list
+        .service
+        .into_iter()
+        .map(|service| service.name)
+        .collect();
+    for expected in [
+        "synagraph.v1.GraphService",
+        "synagraph.v1.CapsuleService",
+        "grpc.health.v1.Health",
+    ] {
+        assert!(names.iter().any(|name| name == expected), "{names:?}");
+    }
+}
+
+#[tokio::test]
+async fn bulk_upsert_streams_per_item_outcomes() {
+    let addr = start_server().await;
+    let mut client = connect(addr).await;
+
+    let src = Uuid::new_v4().to_string();
+    let dst = Uuid::new_v4().to_string();
+    let node = |node_id: &str, payload_json: &str| BulkUpsertNodesRequest {
+        item: Some(Item::Node(UpsertNodeRequest {
+            node_id: node_id.into(),
+            kind: "note".into(),
+            payload_json: payload_json.into(),
+            ..Default::default()
+        })),
+    };
+    let items = vec![
+        node(&src, "{\"title\":\"source\"}"),
+        node("", "not-json"),
+        node(&dst, "{\"title\":\"target\"}"),
+        BulkUpsertNodesRequest {
+            item: Some(Item::Edge(LinkNodesRequest {
+                src_id: src.clone(),
+                dst_id: dst.clone(),
+                rel: "cites".into(),
+                ..Default::default()
+            })),
+        },
+        node(&src, "{\"title\":\"source v2\"}"),
+    ];
+
+    let mut stream = client
+        .bulk_upsert_nodes(tokio_stream::iter(items))
+        .await
+        .expect("bulk stream opens")
+        .into_inner();
+    let mut outcomes = Vec::new();
+    while let Some(response) = stream.message().await.expect("stream stays healthy") {
+        outcomes.push((response.index, response.outcome));
+    }
+
+    assert_eq!(
+        outcomes,
+        vec![
+            (0, Outcome::Created as i32),
+            (1, Outcome::Error as i32),
+            (2, Outcome::Created as i32),
+            (3, Outcome::Created as i32),
+            (4, Outcome::Updated as i32),
+        ]
+    );
+
+    let stored = client
+        .get_node(GetNodeRequest { node_id: src })
+        .await
+        .expect("bulk node persisted")
+        .into_inner()
+        .node
+        .expect("node present");
+    assert_eq!(stored.payload_json, "{\"title\":\"source v2\"}");
+}
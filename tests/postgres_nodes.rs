@@ -1,149 +1,1000 @@
 // SynaGraph is open-source under the Apache License 2.0; see LICENSE for usage and contributions.
-// Integration test for Postgres-backed node repository (requires DATABASE_URL).
+// Integration tests for the Postgres-backed repositories (require DATABASE_URL).
 
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
 use chrono::Utc;
 use serde_json::json;
 use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
 use synagraph::domain::node::KnowledgeNode;
+use synagraph::graph::community::{detect_communities, CommunityAlgorithm, CommunitySpec};
+use synagraph::graph::query::run_query;
+use synagraph::repository::pattern::PatternValue;
 use synagraph::repository::postgres::{
-    PostgresEdgeRepository, PostgresEmbeddingRepository, PostgresNodeRepository,
-    PostgresOutboxRepository,
+    InMemoryBus, InMemoryCache, PostgresEdgeRepository, PostgresEmbeddingRepository,
+    PostgresNodeRepository, PostgresOutboxRepository,
 };
 use synagraph::repository::{
-    EdgeRepository, EmbeddingRepository, NodeEmbedding, NodeRepository, OutboxKind,
-    OutboxRepository, UpsertOutcome,
+    DimensionMismatch, Direction, DistanceMetric, EdgeLink, EdgeRepository, EdgeUpdate,
+    EmbeddingRepository, NodeCursor, NodeEmbedding, NodeRepository, OutboxKind, OutboxRepository,
+    RepositoryBundle, SimilarityFilter, TraversalFilter, UpsertOutcome,
 };
+use tokio::sync::{Mutex, MutexGuard};
 use uuid::Uuid;
 
 const MIGRATIONS: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
 
-#[tokio::test]
-async fn postgres_node_repository_respects_tenant_rls() -> Result<()> {
-    dotenvy::dotenv().ok();
-    let database_url = match std::env::var("DATABASE_URL") {
-        Ok(url) => url,
-        Err(_) => {
-            eprintln!(
-                "skipping postgres_node_repository_respects_tenant_rls (DATABASE_URL not set)"
-            );
-            return Ok(());
-        }
-    };
+/// Every test empties the shared tables before it starts, so they take turns.
+static DATABASE: Mutex<()> = Mutex::const_new(());
+
+/// A migrated database with empty tables and two registered tenants. The turn is held until the
+/// test drops it.
+struct TestDb {
+    pool: PgPool,
+    repo: PostgresNodeRepository,
+    edges: PostgresEdgeRepository,
+    tenant_a: Uuid,
+    tenant_b: Uuid,
+    _turn: MutexGuard<'static, ()>,
+}
 
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .acquire_timeout(Duration::from_secs(5))
-        .connect(&database_url)
+impl TestDb {
+    /// Returns `None` when `DATABASE_URL` is not set, so the calling test can skip.
+    async fn open(test: &str) -> Result<Option<Self>> {
+        dotenvy::dotenv().ok();
+        let Ok(database_url) = std::env::var("DATABASE_URL") else {
+            eprintln!("skipping {test} (DATABASE_URL not set)");
+            return Ok(None);
+        };
+
+        let turn = DATABASE.lock().await;
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .acquire_timeout(Duration::from_secs(5))
+            .connect(&database_url)
+            .await?;
+        MIGRATIONS.run(&pool).await?;
+        sqlx::query(
+            r#"
+            TRUNCATE outbox_events,
+                     node_communities,
+                     node_embeddings,
+                     knowledge_edges,
+                     knowledge_nodes,
+                     tenants
+            RESTART IDENTITY CASCADE
+        "#,
+        )
+        .execute(&pool)
         .await?;
 
-    MIGRATIONS.run(&pool).await?;
+        let mut db = Self {
+            repo: PostgresNodeRepository::from_pool(pool.clone()),
+            edges: PostgresEdgeRepository::new(pool.clone()),
+            pool,
+            tenant_a: Uuid::nil(),
+            tenant_b: Uuid::nil(),
+            _turn: turn,
+        };
+        db.tenant_a = db.add_tenant("Tenant A").await?;
+        db.tenant_b = db.add_tenant("Tenant B").await?;
+        Ok(Some(db))
+    }
 
-    // Clean tables between runs.
-    sqlx::query(
-        r#"
-        TRUNCATE outbox_events,
-                 node_embeddings,
-                 knowledge_edges,
-                 knowledge_nodes,
-                 tenants
-        RESTART IDENTITY CASCADE
-    "#,
-    )
-    .execute(&pool)
-    .await?;
+    async fn add_tenant(&self, name: &str) -> Result<Uuid> {
+        let tenant = Uuid::new_v4();
+        sqlx::query("INSERT INTO tenants (id, name) VALUES ($1, $2)")
+            .bind(tenant)
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+        Ok(tenant)
+    }
 
-    let tenant_a = Uuid::new_v4();
-    let tenant_b = Uuid::new_v4();
+    fn bundle(&self) -> RepositoryBundle {
+        RepositoryBundle::new(
+            Arc::new(self.repo.clone()),
+            Arc::new(self.edges.clone()),
+            Arc::new(PostgresEmbeddingRepository::new(self.pool.clone())),
+            Arc::new(PostgresOutboxRepository::new(self.pool.clone())),
+            Arc::new(InMemoryCache),
+            Arc::new(InMemoryBus),
+        )
+    }
 
-    sqlx::query("INSERT INTO tenants (id, name) VALUES ($1, $2)")
-        .bind(tenant_a)
-        .bind("Tenant A")
-        .execute(&pool)
-        .await?;
+    async fn note(&self, title: &str) -> Result<KnowledgeNode> {
+        let node = KnowledgeNode::new(self.tenant_a, "note", json!({ "title": title }));
+        self.repo.upsert(self.tenant_a, node.clone()).await?;
+        Ok(node)
+    }
 
-    sqlx::query("INSERT INTO tenants (id, name) VALUES ($1, $2)")
-        .bind(tenant_b)
-        .bind("Tenant B")
-        .execute(&pool)
-        .await?;
+    /// Three notes of tenant A in a `RELATED` cycle, origin -> neighbor -> far -> origin, plus a
+    /// direct `MENTIONS` edge from origin to far. Every edge has weight 1.
+    async fn sample_graph(&self) -> Result<SampleGraph> {
+        let origin = self.note("origin").await?;
+        let neighbor = self.note("neighbor").await?;
+        let far = self.note("far").await?;
+        let tenant = self.tenant_a;
+        self.edges
+            .link(
+                tenant,
+                origin.id,
+                neighbor.id,
+                "RELATED",
+                1.0,
+                Some(json!({ "source": "test" })),
+            )
+            .await?;
+        self.edges
+            .link(tenant, neighbor.id, far.id, "RELATED", 1.0, None)
+            .await?;
+        self.edges
+            .link(tenant, far.id, origin.id, "RELATED", 1.0, None)
+            .await?;
+        let mentions = self
+            .edges
+            .link(tenant, origin.id, far.id, "MENTIONS", 1.0, None)
+            .await?
+            .id;
+        Ok(SampleGraph {
+            origin,
+            neighbor,
+            far,
+            mentions,
+        })
+    }
 
-    let repo = PostgresNodeRepository::connect(&database_url).await?;
-    let pool = repo.pool();
-    let edge_repo = PostgresEdgeRepository::new(pool.clone());
-    let embedding_repo = PostgresEmbeddingRepository::new(pool.clone());
-    let outbox_repo = PostgresOutboxRepository::new(pool.clone());
+    /// The sample graph with vectors that only differ in their first two values: origin
+    /// (1, 0), neighbor (6, 8) and far (0, 1).
+    async fn embedded_graph(&self) -> Result<SampleGraph> {
+        let graph = self.sample_graph().await?;
+        for (node, x, y) in [
+            (&graph.origin, 1.0, 0.0),
+            (&graph.neighbor, 6.0, 8.0),
+            (&graph.far, 0.0, 1.0),
+        ] {
+            let mut embedded = node.clone();
+            embedded.vector = Some(embed(x, y));
+            self.repo.upsert(self.tenant_a, embedded).await?;
+        }
+        Ok(graph)
+    }
+}
 
-    let mut node = KnowledgeNode::new(tenant_a, "note", json!({ "title": "pg" }));
-    let node_id = node.id;
+struct SampleGraph {
+    origin: KnowledgeNode,
+    neighbor: KnowledgeNode,
+    far: KnowledgeNode,
+    mentions: Uuid,
+}
 
+fn only(rel: &str) -> TraversalFilter {
+    TraversalFilter {
+        rel: Some(rel.into()),
+        ..Default::default()
+    }
+}
+
+/// A vector the size of the `vector` column.
+fn embed(x: f32, y: f32) -> Vec<f32> {
+    let mut values = vec![0.0_f32; 1536];
+    values[0] = x;
+    values[1] = y;
+    values
+}
+
+fn titles(rows: &[Vec<PatternValue>], column: usize) -> Vec<String> {
+    rows.iter()
+        .map(|row| match &row[column] {
+            PatternValue::Node(node) => node.payload_json["title"].as_str().unwrap().into(),
+            PatternValue::Edge(edge) => edge.rel.clone(),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn nodes_are_scoped_to_their_tenant() -> Result<()> {
+    let Some(db) = TestDb::open("nodes_are_scoped_to_their_tenant").await? else {
+        return Ok(());
+    };
+    let (repo, tenant_a, tenant_b) = (&db.repo, db.tenant_a, db.tenant_b);
+
+    let mut node = KnowledgeNode::new(tenant_a, "note", json!({ "title": "pg" }));
     let outcome = repo.upsert(tenant_a, node.clone()).await?;
     assert!(matches!(outcome, UpsertOutcome::Created));
 
-    let fetched = repo.get(tenant_a, node_id).await?;
+    let fetched = repo.get(tenant_a, node.id).await?;
     let fetched = fetched.expect("node present for tenant A");
     assert_eq!(fetched.tenant_id, tenant_a);
     assert_eq!(fetched.payload_json["title"], "pg");
 
-    let forbidden = repo.get(tenant_b, node_id).await?;
+    let forbidden = repo.get(tenant_b, node.id).await?;
     assert!(forbidden.is_none(), "tenant B should not see tenant A node");
 
     node.payload_json = json!({ "title": "pg-updated" });
     let outcome = repo.upsert(tenant_a, node.clone()).await?;
     assert!(matches!(outcome, UpsertOutcome::Updated));
 
-    let results = repo.query_by_kind(tenant_a, "note", 10, None).await?;
+    let results = repo.query_by_kind(tenant_a, "note", 10, None, true).await?;
     assert_eq!(results.len(), 1);
     assert_eq!(results[0].payload_json["title"], "pg-updated");
 
-    // Edge repository: link another node and ensure tenant isolation.
-    let neighbor = KnowledgeNode::new(tenant_a, "note", json!({ "title": "neighbor" }));
-    repo.upsert(tenant_a, neighbor.clone()).await?;
+    // Batch node fetches keep input order and skip unknown ids.
+    let other = KnowledgeNode::new(tenant_a, "note", json!({ "title": "other" }));
+    repo.upsert(tenant_a, other.clone()).await?;
+    let members = [other.id, Uuid::new_v4(), node.id];
+    let fetched: Vec<_> = repo
+        .get_many(tenant_a, &members)
+        .await?
+        .iter()
+        .map(|n| n.id)
+        .collect();
+    assert_eq!(fetched, vec![other.id, node.id]);
+    assert!(repo.get_many(tenant_b, &members).await?.is_empty());
 
-    edge_repo
-        .link(tenant_a, node_id, neighbor.id, "RELATED", 1.0, None)
+    repo.health_check().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_by_kind_pages_with_cursor() -> Result<()> {
+    let Some(db) = TestDb::open("query_by_kind_pages_with_cursor").await? else {
+        return Ok(());
+    };
+    let (repo, tenant_a) = (&db.repo, db.tenant_a);
+
+    // Pages follow (created_at, id); a batch shares one creation time, so ties break by id.
+    let paged: Vec<KnowledgeNode> = (0..5)
+        .map(|index| KnowledgeNode::new(tenant_a, "page", json!({ "index": index })))
+        .collect();
+    repo.upsert_batch(tenant_a, paged.clone()).await?;
+    let mut seen = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = repo
+            .query_by_kind(tenant_a, "page", 2, cursor, false)
+            .await?;
+        seen.extend(page.iter().map(|node| node.id));
+        match page.last() {
+            Some(last) if page.len() == 2 => cursor = Some(NodeCursor::after(last)),
+            _ => break,
+        }
+    }
+    let mut expected: Vec<Uuid> = paged.iter().map(|node| node.id).collect();
+    expected.sort();
+    assert_eq!(seen, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_upserts_report_outcomes_in_order() -> Result<()> {
+    let Some(db) = TestDb::open("batch_upserts_report_outcomes_in_order").await? else {
+        return Ok(());
+    };
+    let (repo, tenant_a, tenant_b) = (&db.repo, db.tenant_a, db.tenant_b);
+
+    let existing = db.note("existing").await?;
+    let batched = KnowledgeNode::new(tenant_a, "batch", json!({ "title": "batched" }));
+    let outcomes = repo
+        .upsert_batch(tenant_a, vec![batched.clone(), existing])
         .await?;
+    assert!(matches!(
+        outcomes.as_slice(),
+        [UpsertOutcome::Created, UpsertOutcome::Updated]
+    ));
+    assert!(repo.get(tenant_b, batched.id).await?.is_none());
 
-    let neighbors = edge_repo.neighbors(tenant_a, node_id, None, 1, 10).await?;
+    // A batch writes each id once, the last occurrence winning.
+    let tenant_c = db.add_tenant("Tenant C").await?;
+    let first = KnowledgeNode::new(tenant_c, "batch", json!({ "v": 1 }));
+    let mut repeated = first.clone();
+    repeated.payload_json = json!({ "v": 2 });
+    repeated.vector = Some(vec![0.25; 1536]);
+    let other = KnowledgeNode::new(tenant_c, "batch", json!({ "v": 3 }));
+    let outcomes = repo
+        .upsert_batch(tenant_c, vec![first.clone(), other, repeated])
+        .await?;
+    assert!(matches!(
+        outcomes.as_slice(),
+        [
+            UpsertOutcome::Created,
+            UpsertOutcome::Created,
+            UpsertOutcome::Updated
+        ]
+    ));
+    let stored = repo.get(tenant_c, first.id).await?.expect("node exists");
+    assert_eq!(stored.payload_json, json!({ "v": 2 }));
+    assert_eq!(stored.vector, Some(vec![0.25; 1536]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn link_batch_links_all_or_nothing() -> Result<()> {
+    let Some(db) = TestDb::open("link_batch_links_all_or_nothing").await? else {
+        return Ok(());
+    };
+    let tenant_a = db.tenant_a;
+    let first = db.note("first").await?;
+    let other = db.note("other").await?;
+
+    // A relation repeated in the batch updates the edge linked first.
+    let link = |weight| EdgeLink {
+        src: first.id,
+        dst: other.id,
+        rel: "NEXT".into(),
+        weight,
+        props: None,
+    };
+    let linked = db
+        .edges
+        .link_batch(tenant_a, vec![link(1.0), link(2.0)])
+        .await?;
+    assert!(matches!(linked[0].outcome, UpsertOutcome::Created));
+    assert!(matches!(linked[1].outcome, UpsertOutcome::Updated));
+    assert_eq!(linked[0].id, linked[1].id);
+
+    let missing = EdgeLink {
+        dst: Uuid::new_v4(),
+        ..link(1.0)
+    };
+    assert!(db
+        .edges
+        .link_batch(tenant_a, vec![link(3.0), missing])
+        .await
+        .is_err());
+    let edge = db
+        .edges
+        .get_edge(tenant_a, linked[0].id)
+        .await?
+        .expect("edge exists");
+    assert_eq!(edge.weight, 2.0, "a failed batch links nothing");
+    Ok(())
+}
+
+#[tokio::test]
+async fn edges_are_upserted_updated_and_unlinked() -> Result<()> {
+    let Some(db) = TestDb::open("edges_are_upserted_updated_and_unlinked").await? else {
+        return Ok(());
+    };
+    let (edge_repo, tenant_a, tenant_b) = (&db.edges, db.tenant_a, db.tenant_b);
+    let node = db.note("node").await?;
+    let neighbor = db.note("neighbor").await?;
+
+    let linked = edge_repo
+        .link(tenant_a, node.id, neighbor.id, "RELATED", 1.0, None)
+        .await?;
+    assert!(matches!(linked.outcome, UpsertOutcome::Created));
+    let edge_id = linked.id;
+
+    // Linking again upserts on (tenant, src, dst, rel) instead of duplicating the edge.
+    let relinked = edge_repo
+        .link(tenant_a, node.id, neighbor.id, "RELATED", 2.0, None)
+        .await?;
+    assert!(matches!(relinked.outcome, UpsertOutcome::Updated));
+    assert_eq!(relinked.id, edge_id);
+    let updated = edge_repo
+        .update_edge(
+            tenant_a,
+            edge_id,
+            EdgeUpdate {
+                weight: Some(1.0),
+                props: Some(json!({ "source": "test" })),
+                ..Default::default()
+            },
+        )
+        .await?
+        .expect("edge exists");
+    assert_eq!(updated.weight, 1.0);
+    assert_eq!(updated.props, Some(json!({ "source": "test" })));
+    assert!(edge_repo.get_edge(tenant_b, edge_id).await?.is_none());
+    assert!(edge_repo
+        .update_edge(tenant_b, edge_id, EdgeUpdate::default())
+        .await?
+        .is_none());
+
+    let neighbors = edge_repo
+        .neighbors(tenant_a, node.id, &TraversalFilter::default(), 1, 10)
+        .await?;
     assert_eq!(neighbors.len(), 1);
-    assert_eq!(neighbors[0].kind, neighbor.kind);
+    assert_eq!(neighbors[0].node.id, neighbor.id);
+    assert_eq!(neighbors[0].edge.id, edge_id);
+    assert_eq!(neighbors[0].edge.rel, "RELATED");
+    assert_eq!(neighbors[0].depth, 1);
+    assert!(edge_repo
+        .neighbors(tenant_b, node.id, &TraversalFilter::default(), 1, 10)
+        .await?
+        .is_empty());
+
+    edge_repo
+        .link(tenant_a, neighbor.id, node.id, "CITES", 1.0, None)
+        .await?;
+    let all = edge_repo.list_edges(tenant_a, None, None, 10).await?;
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[0].id, edge_id);
+    assert_eq!(
+        edge_repo
+            .list_edges(tenant_a, Some("CITES"), None, 10)
+            .await?
+            .len(),
+        1
+    );
+    assert!(edge_repo
+        .list_edges(tenant_b, None, None, 10)
+        .await?
+        .is_empty());
+
+    assert!(edge_repo.unlink(tenant_b, edge_id).await?.is_none());
+    let removed = edge_repo.unlink(tenant_a, edge_id).await?;
+    assert_eq!(removed.map(|edge| edge.id), Some(edge_id));
+    assert!(edge_repo.get_edge(tenant_a, edge_id).await?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn traversals_follow_relation_and_direction() -> Result<()> {
+    let Some(db) = TestDb::open("traversals_follow_relation_and_direction").await? else {
+        return Ok(());
+    };
+    let (edge_repo, tenant_a) = (&db.edges, db.tenant_a);
+    let graph = db.sample_graph().await?;
+    let (origin, neighbor, far) = (graph.origin.id, graph.neighbor.id, graph.far.id);
+
+    // Multi-hop traversal stops at cycles and applies the relation filter at every hop.
+    let walk = edge_repo
+        .neighbors(tenant_a, origin, &TraversalFilter::default(), 4, 10)
+        .await?;
+    let reached: Vec<_> = walk.iter().map(|n| (n.node.id, n.depth)).collect();
+    assert_eq!(reached.len(), 2);
+    assert!(reached.contains(&(neighbor, 1)));
+    assert!(reached.contains(&(far, 1)));
+
+    let related = edge_repo
+        .neighbors(tenant_a, origin, &only("RELATED"), 4, 10)
+        .await?;
+    let reached: Vec<_> = related.iter().map(|n| (n.node.id, n.depth)).collect();
+    assert_eq!(reached, vec![(neighbor, 1), (far, 2)]);
+
+    // Incoming traversal walks edges backwards while reporting them as stored.
+    let incoming = TraversalFilter {
+        rel: Some("RELATED".into()),
+        direction: Direction::Incoming,
+        as_of: None,
+    };
+    let citing = edge_repo.neighbors(tenant_a, far, &incoming, 4, 10).await?;
+    let reached: Vec<_> = citing.iter().map(|n| (n.node.id, n.depth)).collect();
+    assert_eq!(reached, vec![(neighbor, 1), (origin, 2)]);
+    assert_eq!(citing[0].edge.dst, far);
+    let both = TraversalFilter {
+        rel: None,
+        direction: Direction::Both,
+        as_of: None,
+    };
+    let around = edge_repo.neighbors(tenant_a, far, &both, 1, 10).await?;
+    assert_eq!(around.len(), 3);
+
+    // Induced edges honour the relation filter.
+    let members = [far, Uuid::new_v4(), origin];
+    let among = edge_repo
+        .edges_among(tenant_a, &members, None, None, 10)
+        .await?;
+    assert_eq!(among.len(), 2);
+    let among = edge_repo
+        .edges_among(tenant_a, &members, Some("MENTIONS"), None, 10)
+        .await?;
+    assert_eq!(among.len(), 1);
+    assert_eq!(among[0].src, origin);
+    assert!(edge_repo
+        .edges_among(db.tenant_b, &members, None, None, 10)
+        .await?
+        .is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn shortest_path_sums_inverse_weights() -> Result<()> {
+    let Some(db) = TestDb::open("shortest_path_sums_inverse_weights").await? else {
+        return Ok(());
+    };
+    let (edge_repo, tenant_a) = (&db.edges, db.tenant_a);
+    let graph = db.sample_graph().await?;
+    let (origin, neighbor, far) = (graph.origin.id, graph.neighbor.id, graph.far.id);
+
+    // The direct MENTIONS edge (cost 1.0) beats two RELATED hops (2.0).
+    let path = edge_repo
+        .shortest_path(tenant_a, origin, far, 4, &TraversalFilter::default())
+        .await?
+        .expect("far is reachable");
+    assert_eq!(path.edges.len(), 1);
+    assert_eq!(path.edges[0].rel, "MENTIONS");
+    assert_eq!(path.cost, 1.0);
+    let related = edge_repo
+        .shortest_path(tenant_a, origin, far, 4, &only("RELATED"))
+        .await?
+        .expect("far is reachable via RELATED");
+    let ids: Vec<_> = related.nodes.iter().map(|n| n.id).collect();
+    assert_eq!(ids, vec![origin, neighbor, far]);
+    assert_eq!(related.cost, 2.0);
+    assert!(edge_repo
+        .shortest_path(db.tenant_b, origin, far, 4, &TraversalFilter::default())
+        .await?
+        .is_none());
 
-    let other_neighbors = edge_repo.neighbors(tenant_b, node_id, None, 1, 10).await?;
-    assert!(other_neighbors.is_empty());
+    let incoming = TraversalFilter {
+        rel: Some("RELATED".into()),
+        direction: Direction::Incoming,
+        as_of: None,
+    };
+    let back = edge_repo
+        .shortest_path(tenant_a, far, origin, 4, &incoming)
+        .await?
+        .expect("origin reachable backwards");
+    let ids: Vec<_> = back.nodes.iter().map(|n| n.id).collect();
+    assert_eq!(ids, vec![far, neighbor, origin]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn pattern_queries_match_the_in_memory_interpreter() -> Result<()> {
+    let Some(db) = TestDb::open("pattern_queries_match_the_in_memory_interpreter").await? else {
+        return Ok(());
+    };
+    db.sample_graph().await?;
+    let (bundle, tenant_a) = (db.bundle(), db.tenant_a);
+
+    let result = run_query(
+        &bundle,
+        tenant_a,
+        "MATCH (a:note)-[r:RELATED]->(b) WHERE r.props.source = 'test' AND r.weight <= 1 \
+         RETURN a, r, b",
+        None,
+    )
+    .await?;
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(titles(&result.rows, 0), vec!["origin"]);
+    assert_eq!(titles(&result.rows, 1), vec!["RELATED"]);
+    assert_eq!(titles(&result.rows, 2), vec!["neighbor"]);
+    let result = run_query(
+        &bundle,
+        tenant_a,
+        "MATCH (f)<-[:RELATED*2..2]-(s) WHERE f.payload.title = 'far' RETURN s",
+        None,
+    )
+    .await?;
+    assert_eq!(titles(&result.rows, 0), vec!["origin"]);
+    let result = run_query(
+        &bundle,
+        tenant_a,
+        "MATCH (a)-[:MENTIONS]-(b)-[*1..2]->(a) RETURN b",
+        None,
+    )
+    .await?;
+    let mut reached = titles(&result.rows, 0);
+    reached.sort();
+    assert_eq!(reached, vec!["far", "origin"]);
+    assert!(run_query(&bundle, db.tenant_b, "MATCH (n) RETURN n", None)
+        .await?
+        .rows
+        .is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn expired_edges_stay_visible_as_of_earlier_times() -> Result<()> {
+    let Some(db) = TestDb::open("expired_edges_stay_visible_as_of_earlier_times").await? else {
+        return Ok(());
+    };
+    let (edge_repo, tenant_a, tenant_b) = (&db.edges, db.tenant_a, db.tenant_b);
+    let graph = db.sample_graph().await?;
+    let (origin, far, mentions) = (graph.origin.id, graph.far.id, graph.mentions);
+    let bundle = db.bundle();
+
+    let mentioned = edge_repo
+        .get_edge(tenant_a, mentions)
+        .await?
+        .expect("edge exists");
+    assert_eq!(mentioned.valid_from, mentioned.created_at);
+    assert!(mentioned.valid_to.is_none());
+    let end = Utc::now();
+    let expired = edge_repo
+        .expire_edge(tenant_a, mentions, end)
+        .await?
+        .expect("edge exists");
+    assert!(expired.valid_to.is_some());
+    let again = edge_repo
+        .expire_edge(tenant_a, mentions, end + chrono::Duration::hours(1))
+        .await?
+        .expect("edge exists");
+    assert_eq!(again.valid_to, expired.valid_to);
+    assert!(edge_repo
+        .expire_edge(tenant_b, mentions, end)
+        .await?
+        .is_none());
+
+    let as_of = |at| TraversalFilter {
+        rel: Some("MENTIONS".into()),
+        as_of: Some(at),
+        ..Default::default()
+    };
+    assert!(edge_repo
+        .neighbors(tenant_a, origin, &only("MENTIONS"), 1, 10)
+        .await?
+        .is_empty());
+    let historical = edge_repo
+        .neighbors(tenant_a, origin, &as_of(mentioned.valid_from), 1, 10)
+        .await?;
+    assert_eq!(historical.len(), 1);
+    assert_eq!(historical[0].edge.valid_to, expired.valid_to);
+    assert!(edge_repo
+        .shortest_path(tenant_a, origin, far, 1, &only("MENTIONS"))
+        .await?
+        .is_none());
+    assert_eq!(
+        edge_repo
+            .list_edges(tenant_a, None, Some(mentioned.valid_from), 10)
+            .await?
+            .len(),
+        4
+    );
+    let pattern = "MATCH (a)-[:MENTIONS]->(b) RETURN b";
+    assert!(run_query(&bundle, tenant_a, pattern, None)
+        .await?
+        .rows
+        .is_empty());
+    let then = run_query(&bundle, tenant_a, pattern, Some(mentioned.valid_from)).await?;
+    assert_eq!(titles(&then.rows, 0), vec!["far"]);
+
+    // Linking again starts a new edge.
+    let relinked = edge_repo
+        .link(tenant_a, origin, far, "MENTIONS", 1.0, None)
+        .await?;
+    assert!(matches!(relinked.outcome, UpsertOutcome::Created));
+    assert_ne!(relinked.id, mentions);
+    assert_eq!(
+        edge_repo
+            .edges_among(tenant_a, &[origin, far], Some("MENTIONS"), None, 10)
+            .await?
+            .len(),
+        1
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn detected_communities_can_be_queried() -> Result<()> {
+    let Some(db) = TestDb::open("detected_communities_can_be_queried").await? else {
+        return Ok(());
+    };
+    let graph = db.sample_graph().await?;
+    let bundle = db.bundle();
+
+    // Community detection labels every node with current edges, and pattern queries filter on
+    // the stored labels.
+    let spec = CommunitySpec {
+        algorithm: CommunityAlgorithm::Louvain,
+        rel: None,
+        max_iterations: 20,
+    };
+    let report = detect_communities(&bundle, db.tenant_a, &spec).await?;
+    assert_eq!((report.nodes, report.components), (3, 1));
+    let component = graph.origin.id.min(graph.neighbor.id).min(graph.far.id);
+    let pattern = format!("MATCH (n) WHERE n.component = '{component}' RETURN n");
+    let labelled = run_query(&bundle, db.tenant_a, &pattern, None).await?;
+    assert_eq!(labelled.rows.len(), 3);
+    assert!(run_query(&bundle, db.tenant_b, &pattern, None)
+        .await?
+        .rows
+        .is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn vectors_round_trip_and_rank_by_metric() -> Result<()> {
+    let Some(db) = TestDb::open("vectors_round_trip_and_rank_by_metric").await? else {
+        return Ok(());
+    };
+    let (repo, tenant_a, tenant_b) = (&db.repo, db.tenant_a, db.tenant_b);
+    let graph = db.embedded_graph().await?;
+    let (origin, neighbor, far) = (graph.origin.id, graph.neighbor.id, graph.far.id);
+
+    // Vectors round-trip through upserts, reads and traversals; list queries may leave them out.
+    let fetched = repo.get(tenant_a, neighbor).await?.expect("node exists");
+    assert_eq!(fetched.vector, Some(embed(6.0, 8.0)));
+    let listed = repo.query_by_kind(tenant_a, "note", 10, None, true).await?;
+    assert!(listed.iter().all(|node| node.vector.is_some()));
+    let listed = repo
+        .query_by_kind(tenant_a, "note", 10, None, false)
+        .await?;
+    assert!(listed.iter().all(|node| node.vector.is_none()));
+    let reached = db
+        .edges
+        .neighbors(tenant_a, far, &only("RELATED"), 1, 10)
+        .await?;
+    assert_eq!(reached[0].node.vector, Some(embed(1.0, 0.0)));
 
-    // Embedding repository currently a stub; ensure calls succeed.
-    embedding_repo
-        .upsert_embedding(
+    // Vectors the column cannot hold are rejected before they reach it.
+    let mut short = KnowledgeNode::new(tenant_a, "note", json!({}));
+    short.vector = Some(vec![1.0, 0.0]);
+    let err = repo.upsert(tenant_a, short.clone()).await.unwrap_err();
+    assert!(err.downcast_ref::<DimensionMismatch>().is_some());
+    let err = repo.upsert_batch(tenant_a, vec![short]).await.unwrap_err();
+    assert!(err.downcast_ref::<DimensionMismatch>().is_some());
+
+    // Similarity search ranks by the requested pgvector metric and skips nodes without vectors.
+    db.note("no vector").await?;
+    let query = embed(1.0, 0.0);
+    for (metric, expected, scores) in [
+        (
+            DistanceMetric::Cosine,
+            [origin, neighbor, far],
+            [1.0, 0.6, 0.0],
+        ),
+        (
+            DistanceMetric::L2,
+            [origin, far, neighbor],
+            [1.0, 1.0 / (1.0 + 2f64.sqrt()), 1.0 / (1.0 + 89f64.sqrt())],
+        ),
+        (
+            DistanceMetric::InnerProduct,
+            [neighbor, origin, far],
+            [6.0, 1.0, 0.0],
+        ),
+    ] {
+        let hits = repo
+            .search_similar(tenant_a, &query, metric, &SimilarityFilter::default(), 10)
+            .await?;
+        let ranked: Vec<Uuid> = hits.iter().map(|hit| hit.node.id).collect();
+        assert_eq!(ranked, expected, "{metric:?}");
+        for (hit, score) in hits.iter().zip(scores) {
+            assert!(
+                (hit.score - score).abs() < 1e-6,
+                "{metric:?}: {}",
+                hit.score
+            );
+            assert_eq!(hit.distance_metric, metric);
+        }
+        assert!(repo
+            .search_similar(tenant_b, &query, metric, &SimilarityFilter::default(), 10)
+            .await?
+            .is_empty());
+    }
+    assert_eq!(
+        repo.search_similar(
             tenant_a,
-            NodeEmbedding {
-                node_id: neighbor.id,
-                tenant_id: tenant_a,
-                model: "test".to_string(),
-                dim: 4,
-                vec: vec![0.0; 4],
-                created_at: Utc::now(),
+            &query,
+            DistanceMetric::Cosine,
+            &SimilarityFilter::default(),
+            1
+        )
+        .await?
+        .len(),
+        1
+    );
+    let mismatch = repo
+        .search_similar(
+            tenant_a,
+            &[1.0, 0.0],
+            DistanceMetric::Cosine,
+            &SimilarityFilter::default(),
+            1,
+        )
+        .await
+        .unwrap_err();
+    assert!(mismatch.downcast_ref::<DimensionMismatch>().is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn similarity_filters_narrow_candidates() -> Result<()> {
+    let Some(db) = TestDb::open("similarity_filters_narrow_candidates").await? else {
+        return Ok(());
+    };
+    let (repo, tenant_a) = (&db.repo, db.tenant_a);
+    let graph = db.embedded_graph().await?;
+    let (origin, neighbor, far) = (graph.origin.id, graph.neighbor.id, graph.far.id);
+
+    // Filters narrow the candidates on kind, policy fields and payload containment before ranking.
+    let mut flagged = repo.get(tenant_a, neighbor).await?.expect("node exists");
+    flagged.policy =
+        Some(json!({ "phi": true, "region": "eu", "compliance_tags": ["gdpr", "hipaa"] }));
+    repo.upsert(tenant_a, flagged).await?;
+    for (filter, expected) in [
+        (
+            SimilarityFilter {
+                phi: Some(false),
+                ..Default::default()
+            },
+            vec![origin, far],
+        ),
+        (
+            SimilarityFilter {
+                region: Some("eu".into()),
+                compliance_tags: vec!["hipaa".into()],
+                ..Default::default()
+            },
+            vec![neighbor],
+        ),
+        (
+            SimilarityFilter {
+                payload: Some(json!({ "title": "far" })),
+                ..Default::default()
             },
+            vec![far],
+        ),
+        (
+            SimilarityFilter {
+                kind: Some("capsule".into()),
+                ..Default::default()
+            },
+            vec![],
+        ),
+    ] {
+        let hits = repo
+            .search_similar(
+                tenant_a,
+                &embed(1.0, 0.0),
+                DistanceMetric::Cosine,
+                &filter,
+                10,
+            )
+            .await?;
+        let ranked: Vec<Uuid> = hits.iter().map(|hit| hit.node.id).collect();
+        assert_eq!(ranked, expected, "{filter:?}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn full_text_search_matches_stemmed_words() -> Result<()> {
+    let Some(db) = TestDb::open("full_text_search_matches_stemmed_words").await? else {
+        return Ok(());
+    };
+    let (repo, tenant_a) = (&db.repo, db.tenant_a);
+    db.note("origin").await?;
+    let mut neighbor = db.note("neighbor").await?;
+
+    // Full-text search matches stemmed payload words and honours the similarity filters.
+    let found = repo
+        .search_text(tenant_a, "neighbors", &SimilarityFilter::default(), 10)
+        .await?;
+    let found: Vec<Uuid> = found.iter().map(|hit| hit.node.id).collect();
+    assert_eq!(found, vec![neighbor.id]);
+
+    neighbor.policy = Some(json!({ "phi": true }));
+    repo.upsert(tenant_a, neighbor).await?;
+    let unflagged = SimilarityFilter {
+        phi: Some(false),
+        ..Default::default()
+    };
+    assert!(repo
+        .search_text(tenant_a, "neighbor", &unflagged, 10)
+        .await?
+        .is_empty());
+    assert!(repo
+        .search_text(db.tenant_b, "neighbor", &SimilarityFilter::default(), 10)
+        .await?
+        .is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn embeddings_are_kept_and_searched_per_model() -> Result<()> {
+    let Some(db) = TestDb::open("embeddings_are_kept_and_searched_per_model").await? else {
+        return Ok(());
+    };
+    let (tenant_a, tenant_b) = (db.tenant_a, db.tenant_b);
+    let embedding_repo = PostgresEmbeddingRepository::new(db.pool.clone());
+    let graph = db.sample_graph().await?;
+    let (origin, neighbor, far) = (graph.origin.id, graph.neighbor.id, graph.far.id);
+
+    // Nodes carry one embedding per model, and searches stay within a model's space.
+    let embedding = |node_id: Uuid, model: &str, vec: Vec<f32>| NodeEmbedding {
+        node_id,
+        tenant_id: tenant_a,
+        model: model.to_string(),
+        dim: vec.len() as i32,
+        vec,
+        created_at: Utc::now(),
+    };
+    for stored in [
+        embedding(neighbor, "small", vec![1.0, 0.0]),
+        embedding(neighbor, "large", vec![0.0, 0.0, 1.0, 0.0]),
+        embedding(far, "small", vec![0.0, 1.0]),
+        embedding(far, "small", vec![0.6, 0.8]),
+        embedding(origin, "large", vec![0.0, 1.0, 0.0, 0.0]),
+    ] {
+        embedding_repo.upsert_embedding(tenant_a, stored).await?;
+    }
+    let embeddings = embedding_repo.get_embeddings(tenant_a, neighbor).await?;
+    let models: Vec<&str> = embeddings.iter().map(|e| e.model.as_str()).collect();
+    assert_eq!(models, vec!["large", "small"]);
+    assert_eq!(embeddings[0].vec, vec![0.0, 0.0, 1.0, 0.0]);
+    assert_eq!(embeddings[0].dim, 4);
+    let far_embeddings = embedding_repo.get_embeddings(tenant_a, far).await?;
+    assert_eq!(
+        far_embeddings.len(),
+        1,
+        "upserting the same model replaces it"
+    );
+    let nearest = embedding_repo
+        .search_by_model(tenant_a, "small", &[0.0, 1.0], DistanceMetric::Cosine, 5)
+        .await?;
+    let ranked: Vec<Uuid> = nearest.iter().map(|hit| hit.embedding.node_id).collect();
+    assert_eq!(ranked, vec![far, neighbor]);
+    assert!((nearest[0].score - 0.8).abs() < 1e-6);
+    // Euclidean distance puts the exact match first and scores it 1.
+    let nearest = embedding_repo
+        .search_by_model(tenant_a, "small", &[0.6, 0.8], DistanceMetric::L2, 1)
+        .await?;
+    assert_eq!(nearest[0].embedding.node_id, far);
+    assert!((nearest[0].score - 1.0).abs() < 1e-6);
+    assert_eq!(nearest[0].distance_metric, DistanceMetric::L2);
+    let nearest = embedding_repo
+        .search_by_model(
+            tenant_a,
+            "large",
+            &[0.0, 1.0, 0.0, 0.0],
+            DistanceMetric::Cosine,
+            1,
         )
         .await?;
-    let embeddings = embedding_repo.get_embeddings(tenant_a, neighbor.id).await?;
-    assert!(embeddings.is_empty(), "stub currently no-ops");
+    assert_eq!(nearest[0].embedding.node_id, origin);
+    assert!(embedding_repo
+        .search_by_model(
+            tenant_a,
+            "small",
+            &[1.0, 0.0, 0.0],
+            DistanceMetric::Cosine,
+            5
+        )
+        .await?
+        .is_empty());
+    assert!(embedding_repo
+        .search_by_model(tenant_b, "small", &[0.0, 1.0], DistanceMetric::Cosine, 5)
+        .await?
+        .is_empty());
+    assert!(embedding_repo
+        .get_embeddings(tenant_b, neighbor)
+        .await?
+        .is_empty());
+    assert!(embedding_repo
+        .upsert_embedding(tenant_b, embedding(neighbor, "small", vec![1.0, 0.0]))
+        .await
+        .is_err());
+    let mut mismatched = embedding(far, "small", vec![1.0, 0.0]);
+    mismatched.dim = 3;
+    assert!(embedding_repo
+        .upsert_embedding(tenant_a, mismatched)
+        .await
+        .is_err());
+    assert!(embedding_repo
+        .upsert_embedding(tenant_a, embedding(far, "empty", Vec::new()))
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn outbox_events_are_claimed_and_published() -> Result<()> {
+    let Some(db) = TestDb::open("outbox_events_are_claimed_and_published").await? else {
+        return Ok(());
+    };
+    let outbox_repo = PostgresOutboxRepository::new(db.pool.clone());
+    let node = db.note("origin").await?;
 
-    // Outbox repository roundtrip.
     let event_id = outbox_repo
-        .enqueue(tenant_a, OutboxKind::Upsert, json!({"node_id": node_id}))
+        .enqueue(db.tenant_a, OutboxKind::Upsert, json!({"node_id": node.id}))
         .await?;
     assert!(event_id > 0);
     let mut batch = outbox_repo.claim_batch(10).await?;
     assert_eq!(batch.len(), 1);
-    assert_eq!(batch[0].tenant_id, tenant_a);
-    assert_eq!(batch[0].payload["node_id"], json!(node_id));
+    assert_eq!(batch[0].tenant_id, db.tenant_a);
+    assert_eq!(batch[0].payload["node_id"], json!(node.id));
     outbox_repo
         .mark_published(&[batch.pop().unwrap().id])
         .await?;
-
-    repo.health_check().await?;
-
+    assert!(outbox_repo.claim_batch(10).await?.is_empty());
     Ok(())
 }